@@ -1,11 +1,54 @@
 // Copyright (c) Microsoft Corporation.
 // Licensed under the MIT license.
 
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
-#[derive(Debug)]
+/// Exit status of a command that ran on a remote host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitStatus {
+    /// Exit code of the command.
+    code: i32,
+    /// Signal that terminated the command, if any.
+    signal: Option<String>,
+    /// Was the command terminated because it timed out?
+    timed_out: bool,
+}
+
+/// Direction of a file transfer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    /// From the orchestrator to a remote host.
+    Upload,
+    /// From a remote host to the orchestrator.
+    Download,
+}
+
+/// Copy of a file or directory between the orchestrator and a remote host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileTransfer {
+    /// Direction of the transfer.
+    direction: TransferDirection,
+    /// Path of the file or directory to copy.
+    source: String,
+    /// Path to which the file or directory is copied.
+    destination: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Action {
     /// Name of this action.
     name: String,
@@ -13,27 +56,137 @@ pub struct Action {
     commands: Vec<String>,
     /// Worker on which this task should run.
     runs_on: String,
+    /// Maximum amount of time that this task may run.
+    timeout: Option<Duration>,
+    /// Names of actions that must complete before this task runs.
+    needs: Vec<String>,
+    /// Labels that the runner of this task must have.
+    labels: HashMap<String, String>,
+    /// File transfer to be performed instead of running commands.
+    transfer: Option<FileTransfer>,
+    /// Paths of files on the remote host to collect once this task has run.
+    artifacts: Vec<String>,
     /// Output of this task.
     output: Option<Vec<String>>,
+    /// Exit status of this task.
+    exit_status: Option<ExitStatus>,
+    /// Time at which this task started.
+    started_at: Option<SystemTime>,
+    /// Amount of time that this task took to run.
+    duration: Option<Duration>,
 }
 
 //======================================================================================================================
 // Associated Functions
 //======================================================================================================================
 
+impl ExitStatus {
+    /// Instantiates a new [ExitStatus].
+    pub fn new(code: i32, signal: Option<String>, timed_out: bool) -> Self {
+        Self {
+            code,
+            signal,
+            timed_out,
+        }
+    }
+
+    /// Returns the exit code of the target [ExitStatus].
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// Returns the signal that terminated the command, if any.
+    pub fn signal(&self) -> Option<&str> {
+        self.signal.as_deref()
+    }
+
+    /// Checks if the command was terminated because it timed out.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Checks if the target [ExitStatus] denotes a successful execution.
+    pub fn success(&self) -> bool {
+        self.code == 0 && self.signal.is_none() && !self.timed_out
+    }
+}
+
+impl FileTransfer {
+    /// Instantiates a new [FileTransfer].
+    pub fn new(direction: TransferDirection, source: &str, destination: &str) -> Self {
+        Self {
+            direction,
+            source: source.to_string(),
+            destination: destination.to_string(),
+        }
+    }
+
+    /// Returns the direction of the target [FileTransfer].
+    pub fn direction(&self) -> TransferDirection {
+        self.direction
+    }
+
+    /// Returns the path of the file or directory that is copied by the target [FileTransfer].
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the path to which the target [FileTransfer] copies.
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+}
+
 impl Action {
     /// Instantiates a new [Action].
-    pub fn new(name: &str, commands: Vec<String>, runs_on: &str) -> Self {
-        log::trace!("action: commands={:?}, runs_on={:?}", commands, runs_on);
+    pub fn new(
+        name: &str,
+        commands: Vec<String>,
+        runs_on: &str,
+        timeout: Option<Duration>,
+        needs: Vec<String>,
+        labels: HashMap<String, String>,
+    ) -> Self {
+        log::trace!(
+            "action: commands={:?}, runs_on={:?}, timeout={:?}, needs={:?}, labels={:?}",
+            commands,
+            runs_on,
+            timeout,
+            needs,
+            labels
+        );
 
         Self {
             name: name.to_string(),
             commands,
             runs_on: runs_on.to_string(),
+            timeout,
+            needs,
+            labels,
+            transfer: None,
+            artifacts: Vec::new(),
             output: None,
+            exit_status: None,
+            started_at: None,
+            duration: None,
         }
     }
 
+    /// Instantiates a new [Action] that transfers files instead of running commands.
+    pub fn new_transfer(
+        name: &str,
+        transfer: FileTransfer,
+        runs_on: &str,
+        timeout: Option<Duration>,
+        needs: Vec<String>,
+        labels: HashMap<String, String>,
+    ) -> Self {
+        log::trace!("action: transfer={:?}", transfer);
+        let mut action: Self = Self::new(name, Vec::new(), runs_on, timeout, needs, labels);
+        action.transfer = Some(transfer);
+        action
+    }
+
     /// Returns the list of commands of the target [Action].
     pub fn commands(&self) -> &Vec<String> {
         &self.commands
@@ -49,6 +202,36 @@ impl Action {
         &self.runs_on
     }
 
+    /// Returns the maximum amount of time that the target [Action] may run.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Returns the names of actions that must complete before the target [Action] runs.
+    pub fn needs(&self) -> &Vec<String> {
+        &self.needs
+    }
+
+    /// Returns the labels that the runner of the target [Action] must have.
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// Returns the file transfer of the target [Action], if any.
+    pub fn transfer(&self) -> Option<&FileTransfer> {
+        self.transfer.as_ref()
+    }
+
+    /// Returns the paths of files on the remote host to collect once the target [Action] has run.
+    pub fn artifacts(&self) -> &Vec<String> {
+        &self.artifacts
+    }
+
+    /// Sets the paths of files on the remote host to collect once the target [Action] has run.
+    pub fn set_artifacts(&mut self, artifacts: Vec<String>) {
+        self.artifacts = artifacts;
+    }
+
     /// returns the name of the target [Action].
     pub fn name(&self) -> &str {
         &self.name
@@ -58,4 +241,52 @@ impl Action {
     pub fn set_output(&mut self, output: Vec<String>) {
         self.output = Some(output);
     }
+
+    /// Returns the exit status of the target [Action].
+    pub fn exit_status(&self) -> &Option<ExitStatus> {
+        &self.exit_status
+    }
+
+    /// Sets the exit status of the target [Action].
+    pub fn set_exit_status(&mut self, exit_status: ExitStatus) {
+        self.exit_status = Some(exit_status);
+    }
+
+    /// Returns the time at which the target [Action] started.
+    pub fn started_at(&self) -> Option<SystemTime> {
+        self.started_at
+    }
+
+    /// Returns the amount of time that the target [Action] took to run.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    /// Records the time at which the target [Action] started and how long it took to run.
+    pub fn set_timing(&mut self, started_at: SystemTime, duration: Duration) {
+        self.started_at = Some(started_at);
+        self.duration = Some(duration);
+    }
+
+    /// Checks if the target [Action] has completed successfully.
+    pub fn succeeded(&self) -> bool {
+        match &self.exit_status {
+            Some(exit_status) => exit_status.success(),
+            None => false,
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl fmt::Display for TransferDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: &str = match self {
+            TransferDirection::Upload => "upload",
+            TransferDirection::Download => "download",
+        };
+        write!(f, "{}", s)
+    }
 }
@@ -6,23 +6,36 @@
 //======================================================================================================================
 
 use anyhow::Result;
-use clap::{Arg, ArgMatches, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
+/// Command that the program runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramCommand {
+    /// Serves requests to run jobs. This is the default.
+    Serve,
+    /// Checks job files without running them.
+    Validate(Vec<String>),
+    /// Shows what running a job file with some parameters would do, without running it.
+    Plan(String, Vec<(String, String)>),
+}
+
 /// Program Arguments
 #[derive(Debug)]
 pub struct ProgramArguments {
+    /// Command to run.
+    command: ProgramCommand,
     /// Location for configuration file.
-    config_file: String,
+    config_file: Option<String>,
     /// Username for authentication.
-    username: String,
+    username: Option<String>,
     /// Location for public key.
-    public_key_path: String,
+    public_key_path: Option<String>,
     /// Location for private key.
-    private_key_path: String,
+    private_key_path: Option<String>,
 }
 
 //======================================================================================================================
@@ -35,6 +48,39 @@ impl ProgramArguments {
         let matches: ArgMatches = Command::new(app_name)
             .author(app_author)
             .about(app_about)
+            // Arguments for serving requests are not required by subcommands.
+            .subcommand_negates_reqs(true)
+            .subcommand(
+                Command::new("validate")
+                    .about("Checks job files for mistakes without running them")
+                    .arg(
+                        Arg::new("job-file")
+                            .value_parser(clap::value_parser!(String))
+                            .action(ArgAction::Append)
+                            .required(true)
+                            .value_name("path")
+                            .help("Sets location for job file"),
+                    ),
+            )
+            .subcommand(
+                Command::new("plan")
+                    .about("Shows what running a job would do without running it")
+                    .arg(
+                        Arg::new("job-file")
+                            .value_parser(clap::value_parser!(String))
+                            .required(true)
+                            .value_name("path")
+                            .help("Sets location for job file"),
+                    )
+                    .arg(
+                        Arg::new("param")
+                            .long("param")
+                            .value_parser(clap::value_parser!(String))
+                            .action(ArgAction::Append)
+                            .value_name("key=value")
+                            .help("Sets a parameter of the job, as the query of a run request would"),
+                    ),
+            )
             .arg(
                 Arg::new("config-file")
                     .long("config-file")
@@ -69,48 +115,68 @@ impl ProgramArguments {
             )
             .get_matches();
 
-        let config_file: String = matches
-            .get_one::<String>("config-file")
-            .ok_or(anyhow::anyhow!("Missing configuration file"))?
-            .to_string();
-        let username: String = matches
-            .get_one::<String>("username")
-            .ok_or(anyhow::anyhow!("Missing username"))?
-            .to_string();
-        let private_key_path: String = matches
-            .get_one::<String>("private-key")
-            .ok_or(anyhow::anyhow!("Missing private key"))?
-            .to_string();
-        let public_key_path: String = matches
-            .get_one::<String>("public-key")
-            .ok_or(anyhow::anyhow!("Missing public key"))?
-            .to_string();
+        let command: ProgramCommand = match matches.subcommand() {
+            Some(("validate", matches)) => ProgramCommand::Validate(
+                matches
+                    .get_many::<String>("job-file")
+                    .ok_or(anyhow::anyhow!("Missing job file"))?
+                    .cloned()
+                    .collect(),
+            ),
+            Some(("plan", matches)) => {
+                let job_file: String = matches
+                    .get_one::<String>("job-file")
+                    .ok_or(anyhow::anyhow!("Missing job file"))?
+                    .to_string();
+                let mut parameters: Vec<(String, String)> = Vec::new();
+                for param in matches.get_many::<String>("param").into_iter().flatten() {
+                    match param.split_once('=') {
+                        Some((key, value)) if !key.is_empty() => parameters.push((key.to_string(), value.to_string())),
+                        _ => anyhow::bail!("Malformed parameter (param={:?})", param),
+                    }
+                }
+                ProgramCommand::Plan(job_file, parameters)
+            },
+            _ => ProgramCommand::Serve,
+        };
 
         Ok(Self {
-            config_file,
-            username,
-            public_key_path,
-            private_key_path,
+            command,
+            config_file: matches.get_one::<String>("config-file").cloned(),
+            username: matches.get_one::<String>("username").cloned(),
+            public_key_path: matches.get_one::<String>("public-key").cloned(),
+            private_key_path: matches.get_one::<String>("private-key").cloned(),
         })
     }
 
+    /// Returns the command to run.
+    pub fn command(&self) -> &ProgramCommand {
+        &self.command
+    }
+
     /// Returns the location for the configuration file.
-    pub fn config_file(&self) -> &str {
-        &self.config_file
+    pub fn config_file(&self) -> Result<&str> {
+        self.config_file
+            .as_deref()
+            .ok_or(anyhow::anyhow!("Missing configuration file"))
     }
 
     /// Returns the username for authentication.
-    pub fn username(&self) -> &str {
-        &self.username
+    pub fn username(&self) -> Result<&str> {
+        self.username.as_deref().ok_or(anyhow::anyhow!("Missing username"))
     }
 
     /// Returns the location for the public key.
-    pub fn public_key_path(&self) -> &str {
-        &self.public_key_path
+    pub fn public_key_path(&self) -> Result<&str> {
+        self.public_key_path
+            .as_deref()
+            .ok_or(anyhow::anyhow!("Missing public key"))
     }
 
     /// Returns the location for the private key.
-    pub fn private_key_path(&self) -> &str {
-        &self.private_key_path
+    pub fn private_key_path(&self) -> Result<&str> {
+        self.private_key_path
+            .as_deref()
+            .ok_or(anyhow::anyhow!("Missing private key"))
     }
 }
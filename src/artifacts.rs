@@ -0,0 +1,136 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::registry::JobId;
+use anyhow::Result;
+use std::{
+    fs::{self, File},
+    io::ErrorKind,
+    path::{Component, Components, Path, PathBuf},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Directory on the orchestrator where artifacts of jobs are kept, in one sub-directory per job.
+pub struct ArtifactStore {
+    /// Root directory of the store.
+    home: PathBuf,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl ArtifactStore {
+    /// Instantiates a new [ArtifactStore] rooted at a given directory.
+    pub fn new(home: &str) -> Self {
+        Self {
+            home: PathBuf::from(home),
+        }
+    }
+
+    /// Returns the directory where artifacts of a job are kept.
+    pub fn job_dir(&self, job_id: JobId) -> PathBuf {
+        self.home.join(job_id.to_string())
+    }
+
+    /// Returns the identifier that follows the largest identifier of a job that has a directory in the target
+    /// [ArtifactStore]. Directories outlive the history of jobs, so their identifiers must not be reused either.
+    pub fn next_id(&self) -> Result<JobId> {
+        let entries: fs::ReadDir = match fs::read_dir(&self.home) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => {
+                let msg: String = format!("failed to read artifact directory (path={:?}, e={:?})", self.home, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        Ok(entries
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str().and_then(|name| name.parse::<JobId>().ok()))
+            .map(|id| id + 1)
+            .max()
+            .unwrap_or(0))
+    }
+
+    /// Lists the artifacts of a job, along with their sizes in bytes. Paths are relative to the directory of the job.
+    pub fn list(&self, job_id: JobId) -> Result<Vec<(String, u64)>> {
+        let mut artifacts: Vec<(String, u64)> = Vec::new();
+        let job_dir: PathBuf = self.job_dir(job_id);
+        if job_dir.is_dir() {
+            Self::walk(&job_dir, &job_dir, &mut artifacts)?;
+        }
+        artifacts.sort();
+        Ok(artifacts)
+    }
+
+    /// Opens an artifact of a job, and returns it along with its size in bytes. Returns `None` if there is no such
+    /// artifact.
+    pub fn open(&self, job_id: JobId, path: &str) -> Result<Option<(File, u64)>> {
+        // Do not let requests escape the directory of the job.
+        let relative: &Path = Path::new(path);
+        if path.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            let msg: String = format!("malformed artifact path (path={:?})", path);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        let path: PathBuf = self.job_dir(job_id).join(relative);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        match File::open(&path).and_then(|file| file.metadata().map(|metadata| (file, metadata.len()))) {
+            Ok((file, length)) => Ok(Some((file, length))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                let msg: String = format!("failed to open artifact (path={:?}, e={:?})", path, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Checks if a name can be used as is for a directory or a file of the store, without escaping its parent
+    /// directory.
+    pub fn is_file_name(name: &str) -> bool {
+        let mut components: Components<'_> = Path::new(name).components();
+        matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        )
+    }
+
+    /// Collects all files under a directory.
+    fn walk(root: &Path, dir: &Path, artifacts: &mut Vec<(String, u64)>) -> Result<()> {
+        let entries: fs::ReadDir = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                let msg: String = format!("failed to read artifact directory (path={:?}, e={:?})", dir, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        for entry in entries.flatten() {
+            let path: PathBuf = entry.path();
+            match entry.metadata() {
+                Ok(metadata) if metadata.is_dir() => Self::walk(root, &path, artifacts)?,
+                Ok(metadata) => {
+                    if let Ok(relative) = path.strip_prefix(root) {
+                        artifacts.push((relative.to_string_lossy().to_string(), metadata.len()));
+                    }
+                },
+                Err(e) => log::warn!("failed to stat artifact (path={:?}, e={:?})", path, e),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{dependencies::DependencyTracker, rendezvous::Rendezvous};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Handle through which a job is cancelled.
+///
+/// Cancelling a job wakes up its workers that are blocked on barriers or on dependencies. Workers are expected to
+/// check [Cancellation::is_cancelled] before starting new tasks, and runners to kill commands that are in flight.
+pub struct Cancellation {
+    /// Has the job been cancelled?
+    cancelled: AtomicBool,
+    /// Barriers and dependencies of the job, once it is running.
+    blockers: Mutex<Option<Blockers>>,
+}
+
+/// Synchronization primitives on which workers of a job may block.
+struct Blockers {
+    barriers: Arc<Vec<Rendezvous>>,
+    dependencies: Arc<DependencyTracker>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Cancellation {
+    /// Instantiates a new [Cancellation].
+    pub fn new() -> Self {
+        Self {
+            cancelled: AtomicBool::new(false),
+            blockers: Mutex::new(None),
+        }
+    }
+
+    /// Checks if the job has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Attaches the barriers and dependencies of a job that is about to run, so that they are cancelled along with it.
+    /// They are cancelled right away if the job has already been cancelled.
+    pub fn attach(&self, barriers: Arc<Vec<Rendezvous>>, dependencies: Arc<DependencyTracker>) {
+        match self.blockers.lock() {
+            Ok(mut blockers) => {
+                let attached: Blockers = Blockers { barriers, dependencies };
+                if self.is_cancelled() {
+                    attached.release();
+                }
+                *blockers = Some(attached);
+            },
+            Err(e) => log::warn!("failed to attach barriers and dependencies (e={:?})", e),
+        }
+    }
+
+    /// Cancels the job.
+    pub fn cancel(&self) {
+        match self.blockers.lock() {
+            Ok(blockers) => {
+                self.cancelled.store(true, Ordering::Release);
+                if let Some(blockers) = &*blockers {
+                    blockers.release();
+                }
+            },
+            Err(e) => {
+                self.cancelled.store(true, Ordering::Release);
+                log::warn!("failed to release barriers and dependencies (e={:?})", e);
+            },
+        }
+    }
+}
+
+impl Blockers {
+    /// Wakes up all workers that are blocked on barriers or on dependencies.
+    fn release(&self) {
+        for barrier in self.barriers.iter() {
+            barrier.cancel();
+        }
+        self.dependencies.cancel();
+    }
+}
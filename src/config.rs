@@ -5,8 +5,23 @@
 // Imports
 //======================================================================================================================
 
-use crate::{credentials::Credentials, runner::Runner};
-use ::std::{fs::File, io::Read};
+use crate::{
+    credentials::Credentials,
+    notifier::Notifier,
+    runner::{local::LocalRunner, ssh::SshRunner, Runner},
+    secrets::SecretStore,
+    web::{
+        auth::{ApiToken, Scope, TokenKind},
+        tls::TlsAcceptor,
+    },
+    webhook::{GithubWebhook, WebhookEventKind, WebhookRule},
+};
+use ::std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Read,
+    time::Duration,
+};
 use ::yaml_rust::{Yaml, YamlLoader};
 use anyhow::Result;
 use std::sync::Mutex;
@@ -26,6 +41,8 @@ pub struct Config {
 
 impl Config {
     pub const ENV_VAR_PREFIX: &'static str = "DEMIKERNEL_";
+    /// Default local address of local runners.
+    const LOCAL_RUNNER_ADDR: &'static str = "127.0.0.1";
 
     /// Reads a configuration file into a [Config] object.
     pub fn new(config_path: &str) -> Result<Self> {
@@ -58,29 +75,61 @@ impl Config {
         Ok(Self { yaml })
     }
 
-    /// Retrieves the list of workers from target [Config] object.
-    pub fn get_workers(&self, credentials: &Credentials) -> Result<Vec<Mutex<Runner>>> {
+    /// Retrieves the list of workers from target [Config] object. Workers run actions over SSH (`type: ssh`, the
+    /// default) or as processes on this host (`type: local`). SSH workers are connected to lazily, so this does not
+    /// fail if some of them are unreachable.
+    pub fn get_workers(&self, credentials: &Credentials) -> Result<Vec<Mutex<Box<dyn Runner>>>> {
         let mut id: usize = 0;
-        let mut runners: Vec<Mutex<Runner>> = Vec::new();
+        let mut runners: Vec<Mutex<Box<dyn Runner>>> = Vec::new();
         for c in &self.yaml {
             if let Some(workers_config) = c["workers"].as_vec() {
                 for worker_config in workers_config {
-                    let hostname: String = match worker_config["hostname"].as_str() {
-                        Some(hostname) => hostname.to_string(),
-                        None => anyhow::bail!("missing hostname"),
+                    let labels: HashMap<String, String> = match &worker_config["labels"] {
+                        Yaml::BadValue => HashMap::new(),
+                        Yaml::Hash(labels_config) => {
+                            let mut labels: HashMap<String, String> = HashMap::new();
+                            for (key, value) in labels_config {
+                                match (key.as_str(), Self::parse_scalar(value)) {
+                                    (Some(key), Some(value)) => {
+                                        labels.insert(key.to_string(), value);
+                                    },
+                                    _ => anyhow::bail!("failed to parse label (key={:?}, value={:?})", key, value),
+                                }
+                            }
+                            labels
+                        },
+                        _ => anyhow::bail!("failed to parse labels"),
                     };
 
-                    let port: u16 = worker_config["port"]
-                        .as_i64()
-                        .ok_or(anyhow::anyhow!("failed to parse port number"))?
-                        as u16;
+                    let worker: Box<dyn Runner> = match worker_config["type"].as_str().unwrap_or("ssh") {
+                        "ssh" => {
+                            let hostname: String = match worker_config["hostname"].as_str() {
+                                Some(hostname) => hostname.to_string(),
+                                None => anyhow::bail!("missing hostname"),
+                            };
 
-                    let local_addr: String = match worker_config["local-address"].as_str() {
-                        Some(local_addr) => local_addr.to_string(),
-                        None => anyhow::bail!("missing local_addr"),
-                    };
+                            let port: u16 = worker_config["port"]
+                                .as_i64()
+                                .ok_or(anyhow::anyhow!("failed to parse port number"))?
+                                as u16;
+
+                            let local_addr: String = match worker_config["local-address"].as_str() {
+                                Some(local_addr) => local_addr.to_string(),
+                                None => anyhow::bail!("missing local_addr"),
+                            };
 
-                    let worker: Runner = Runner::new(id, &hostname, port, &local_addr, credentials)?;
+                            Box::new(SshRunner::new(id, &hostname, port, &local_addr, labels, credentials))
+                        },
+                        // Local runners execute actions on this host, so other workers reach them over loopback
+                        // unless told otherwise.
+                        "local" => {
+                            let local_addr: &str = worker_config["local-address"]
+                                .as_str()
+                                .unwrap_or(Self::LOCAL_RUNNER_ADDR);
+                            Box::new(LocalRunner::new(id, local_addr, labels))
+                        },
+                        kind => anyhow::bail!("unknown worker type (type={:?})", kind),
+                    };
                     runners.push(Mutex::new(worker));
                     id += 1;
                 }
@@ -90,6 +139,17 @@ impl Config {
         Ok(runners)
     }
 
+    /// Converts a scalar YAML value into a string.
+    pub fn parse_scalar(value: &Yaml) -> Option<String> {
+        match value {
+            Yaml::String(s) => Some(s.clone()),
+            Yaml::Integer(i) => Some(i.to_string()),
+            Yaml::Real(r) => Some(r.clone()),
+            Yaml::Boolean(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
     /// Retrieves the server address from target [Config] object.
     pub fn addr(&self) -> Result<String> {
         for c in &self.yaml {
@@ -138,11 +198,267 @@ impl Config {
         Err(anyhow::anyhow!(msg))
     }
 
+    /// Retrieves the TLS settings of the server from target [Config] object. Returns `None` if TLS is not enabled.
+    pub fn tls_acceptor(&self) -> Result<Option<TlsAcceptor>> {
+        let tls_config: &Yaml = match self.tls_config() {
+            Some(tls_config) => tls_config,
+            None => return Ok(None),
+        };
+
+        let (certificate_path, key_path): (&str, &str) =
+            match (tls_config["certificate"].as_str(), tls_config["key"].as_str()) {
+                (Some(certificate_path), Some(key_path)) => (certificate_path, key_path),
+                _ => {
+                    let msg: String = "tls must have a certificate and a key".to_string();
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+        let client_ca_path: Option<&str> = tls_config["client-ca"].as_str();
+
+        Ok(Some(TlsAcceptor::new(certificate_path, key_path, client_ca_path)?))
+    }
+
+    /// Retrieves the TLS entry of the server section from target [Config] object.
+    fn tls_config(&self) -> Option<&Yaml> {
+        for c in &self.yaml {
+            if let Some(server_config) = c["server"].as_vec() {
+                for c in server_config {
+                    if !c["tls"].is_badvalue() {
+                        return Some(&c["tls"]);
+                    }
+                }
+            }
+        }
+        None
+    }
+
     /// Retrieves the location of the jobs directory from target [Config] object.
     pub fn jobs_home(&self) -> String {
         "jobs".to_string()
     }
 
+    /// Retrieves the location of the artifacts directory from target [Config] object.
+    pub fn artifacts_home(&self) -> String {
+        for c in &self.yaml {
+            if let Some(artifacts_home) = c["artifacts-home"].as_str() {
+                return artifacts_home.to_string();
+            }
+        }
+        "artifacts".to_string()
+    }
+
+    /// Retrieves the location of the job history file from target [Config] object.
+    pub fn history_path(&self) -> String {
+        for c in &self.yaml {
+            if let Some(path) = c["history"]["path"].as_str() {
+                return path.to_string();
+            }
+        }
+        "history.jsonl".to_string()
+    }
+
+    /// Retrieves for how long finished jobs are kept in the history from target [Config] object. Jobs are kept
+    /// forever if no retention period is set.
+    pub fn history_retention(&self) -> Result<Option<Duration>> {
+        for c in &self.yaml {
+            match &c["history"]["retention-days"] {
+                Yaml::BadValue => {},
+                Yaml::Integer(days) if *days > 0 => {
+                    return Ok(Some(Duration::from_secs((*days as u64).saturating_mul(24 * 60 * 60))))
+                },
+                _ => {
+                    let msg: String = "failed to parse history retention".to_string();
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    /// Retrieves the GitHub webhook from target [Config] object. Returns `None` if webhooks are not enabled.
+    pub fn github_webhook(&self) -> Result<Option<GithubWebhook>> {
+        for c in &self.yaml {
+            let webhook_config: &Yaml = &c["webhooks"]["github"];
+            if webhook_config.is_badvalue() {
+                continue;
+            }
+
+            let secret: &str = match webhook_config["secret"].as_str() {
+                Some(secret) if !secret.is_empty() => secret,
+                _ => {
+                    let msg: String = "missing github webhook secret".to_string();
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+
+            let mut rules: Vec<WebhookRule> = Vec::new();
+            for rule_config in webhook_config["rules"].as_vec().unwrap_or(&Vec::new()) {
+                let (repository, job): (&str, &str) =
+                    match (rule_config["repository"].as_str(), rule_config["job"].as_str()) {
+                        (Some(repository), Some(job)) => (repository, job),
+                        _ => {
+                            let msg: String = "github webhook rule must have a repository and a job".to_string();
+                            log::error!("{}", msg);
+                            anyhow::bail!(msg);
+                        },
+                    };
+                let event: Option<WebhookEventKind> = match &rule_config["event"] {
+                    Yaml::BadValue => None,
+                    Yaml::String(event) => match WebhookEventKind::from_name(event) {
+                        Some(event) => Some(event),
+                        None => {
+                            let msg: String = format!("unsupported github webhook event (event={:?})", event);
+                            log::error!("{}", msg);
+                            anyhow::bail!(msg);
+                        },
+                    },
+                    event => {
+                        let msg: String = format!("failed to parse github webhook event (event={:?})", event);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                };
+                let branch: Option<&str> = match &rule_config["branch"] {
+                    Yaml::BadValue => None,
+                    Yaml::String(branch) => Some(branch),
+                    branch => {
+                        let msg: String = format!("failed to parse github webhook branch (branch={:?})", branch);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                };
+                rules.push(WebhookRule::new(repository, event, branch, job));
+            }
+
+            return Ok(Some(GithubWebhook::new(secret, rules)));
+        }
+        Ok(None)
+    }
+
+    /// Retrieves the commit status notifier from target [Config] object. Returns `None` if notifications are not
+    /// enabled. Statuses link to job pages under the public URL, which defaults to the bind address.
+    pub fn notifier(&self) -> Result<Option<Notifier>> {
+        for c in &self.yaml {
+            let notifier_config: &Yaml = &c["notifier"];
+            if notifier_config.is_badvalue() {
+                continue;
+            }
+
+            let api_url: &str = match notifier_config["url"].as_str() {
+                Some(api_url) if !api_url.is_empty() => api_url,
+                _ => {
+                    let msg: String = "missing notifier url".to_string();
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+            let token: Option<&str> = notifier_config["token"].as_str();
+            let context: &str = notifier_config["context"].as_str().unwrap_or("demikernel-ci");
+            let public_url: String = match notifier_config["public-url"].as_str() {
+                Some(public_url) => public_url.to_string(),
+                None => {
+                    let scheme: &str = if self.tls_config().is_some() { "https" } else { "http" };
+                    format!("{}://{}", scheme, self.addr()?)
+                },
+            };
+
+            return Ok(Some(Notifier::new(api_url, token, context, &public_url)));
+        }
+        Ok(None)
+    }
+
+    /// Retrieves the API tokens from target [Config] object. Bearer tokens are declared by the hexadecimal SHA-256 hash
+    /// of their value, and client certificates by that of their DER encoding. Returns `None` if no tokens are
+    /// declared, in which case authentication is disabled.
+    pub fn api_tokens(&self) -> Result<Option<Vec<ApiToken>>> {
+        for c in &self.yaml {
+            let tokens_config: &Vec<Yaml> = match c["tokens"].as_vec() {
+                Some(tokens_config) => tokens_config,
+                None => continue,
+            };
+
+            let mut tokens: Vec<ApiToken> = Vec::new();
+            for token_config in tokens_config {
+                let (name, kind, hash): (&str, TokenKind, &str) = match (
+                    token_config["name"].as_str(),
+                    token_config["sha256"].as_str(),
+                    token_config["certificate-sha256"].as_str(),
+                ) {
+                    (Some(name), Some(hash), None) => (name, TokenKind::Bearer, hash),
+                    (Some(name), None, Some(hash)) => {
+                        let client_ca: bool = self
+                            .tls_config()
+                            .is_some_and(|tls_config| tls_config["client-ca"].as_str().is_some());
+                        if !client_ca {
+                            let msg: String = format!(
+                                "client certificate tokens require a client certificate authority (name={:?})",
+                                name
+                            );
+                            log::error!("{}", msg);
+                            anyhow::bail!(msg);
+                        }
+                        (name, TokenKind::Certificate, hash)
+                    },
+                    _ => {
+                        let msg: String =
+                            "token must have a name and either a sha256 or a certificate-sha256 hash".to_string();
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                };
+                let mut scopes: HashSet<Scope> = HashSet::new();
+                for scope in token_config["scopes"].as_vec().unwrap_or(&Vec::new()) {
+                    match scope.as_str().map(|scope| scope.parse::<Scope>()) {
+                        Some(Ok(scope)) => {
+                            scopes.insert(scope);
+                        },
+                        _ => {
+                            let msg: String =
+                                format!("failed to parse token scope (name={:?}, scope={:?})", name, scope);
+                            log::error!("{}", msg);
+                            anyhow::bail!(msg);
+                        },
+                    }
+                }
+                tokens.push(ApiToken::new(name, kind, hash, scopes)?);
+            }
+
+            return Ok(Some(tokens));
+        }
+        Ok(None)
+    }
+
+    /// Retrieves the secrets that jobs may reference from target [Config] object. Each secret is read either from an
+    /// environment variable of the orchestrator or from a file that only the orchestrator may read.
+    pub fn secrets(&self) -> Result<SecretStore> {
+        let mut secrets: SecretStore = SecretStore::new();
+        for c in &self.yaml {
+            for secret_config in c["secrets"].as_vec().unwrap_or(&Vec::new()) {
+                let name: &str = match secret_config["name"].as_str() {
+                    Some(name) if !name.is_empty() => name,
+                    _ => {
+                        let msg: String = "secret must have a name".to_string();
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                };
+                match (secret_config["env"].as_str(), secret_config["file"].as_str()) {
+                    (Some(var), None) => secrets.insert_from_env(name, var)?,
+                    (None, Some(path)) => secrets.insert_from_file(name, path)?,
+                    _ => {
+                        let msg: String = format!("secret must have either an env or a file entry (name={:?})", name);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                }
+            }
+        }
+        Ok(secrets)
+    }
+
     /// Retrieves the prefix for environment variables from target [Config] object.
     pub fn env_var_prefix() -> String {
         Self::ENV_VAR_PREFIX.to_string()
@@ -6,6 +6,7 @@
 //======================================================================================================================
 
 /// Information required for authentication.
+#[derive(Clone)]
 pub struct Credentials {
     username: String,
     public_key_path: String,
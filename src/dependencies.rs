@@ -0,0 +1,117 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex, MutexGuard},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Tracks completion of actions across all workers of a job, so that actions can wait on the ones they need.
+pub struct DependencyTracker {
+    /// State of the tracker.
+    state: Mutex<DependencyState>,
+    /// Condition variable on which waiting actions block.
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct DependencyState {
+    /// Completed actions, and whether or not they succeeded.
+    completed: HashMap<String, bool>,
+    /// Has the tracker been cancelled?
+    cancelled: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl DependencyTracker {
+    /// Instantiates a new [DependencyTracker].
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(DependencyState::default()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Blocks until all actions in `needs` have completed. Fails if any of them failed, or if the target
+    /// [DependencyTracker] is cancelled.
+    pub fn wait_for(&self, needs: &[String]) -> Result<()> {
+        let mut state: MutexGuard<'_, DependencyState> = self.lock()?;
+
+        loop {
+            if state.cancelled {
+                anyhow::bail!("dependency tracker was cancelled");
+            }
+
+            let mut ready: bool = true;
+            for name in needs {
+                match state.completed.get(name) {
+                    Some(true) => {},
+                    Some(false) => {
+                        let msg: String = format!("dependency failed (name={:?})", name);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                    None => ready = false,
+                }
+            }
+
+            if ready {
+                return Ok(());
+            }
+
+            state = match self.condvar.wait(state) {
+                Ok(state) => state,
+                Err(e) => {
+                    let msg: String = format!("failed to wait on dependency tracker (e={:?})", e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+        }
+    }
+
+    /// Records that an action has completed, waking up actions that may be waiting on it.
+    pub fn complete(&self, name: &str, succeeded: bool) {
+        match self.lock() {
+            Ok(mut state) => {
+                state.completed.insert(name.to_string(), succeeded);
+                self.condvar.notify_all();
+            },
+            Err(e) => log::warn!("failed to record completed action (name={:?}, e={:?})", name, e),
+        }
+    }
+
+    /// Cancels the target [DependencyTracker], waking up all actions that are waiting on it.
+    pub fn cancel(&self) {
+        match self.lock() {
+            Ok(mut state) => {
+                state.cancelled = true;
+                self.condvar.notify_all();
+            },
+            Err(e) => log::warn!("failed to cancel dependency tracker (e={:?})", e),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, DependencyState>> {
+        match self.state.lock() {
+            Ok(state) => Ok(state),
+            Err(e) => {
+                let msg: String = format!("failed to lock dependency tracker (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    registry::{JobId, JobRecord, JobState},
+    report::ReportFormat,
+};
+use anyhow::Result;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant, SystemTime},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Append-only store of finished jobs, kept as a file with one JSON-encoded [JobRecord] per line. Reports of jobs are
+/// kept next to the history file, in a directory that is named after it with a `.reports` suffix, so that stores in the
+/// same directory do not share reports.
+pub struct HistoryStore {
+    /// Location of the history file.
+    path: PathBuf,
+    /// Directory where reports of jobs are kept.
+    reports_dir: PathBuf,
+    /// Maximum age of the jobs that are kept, if any.
+    retention: Option<Duration>,
+    /// Time at which old jobs were last pruned. This also serializes accesses to the history file.
+    last_pruned: Mutex<Instant>,
+}
+
+/// Criteria for querying the history of jobs.
+#[derive(Debug, Default)]
+pub struct HistoryFilter {
+    /// Name of the job.
+    pub name: Option<String>,
+    /// Final state of the job.
+    pub state: Option<JobState>,
+    /// Earliest time at which the job was submitted.
+    pub since: Option<SystemTime>,
+    /// Latest time at which the job was submitted.
+    pub until: Option<SystemTime>,
+    /// Maximum number of jobs to return, starting from the most recent ones.
+    pub limit: Option<usize>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl HistoryStore {
+    /// Interval at which old jobs are pruned.
+    const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    /// Opens the history store at a given location, creating it if needed, and prunes old jobs from it.
+    pub fn new(path: &str, retention: Option<Duration>) -> Result<Self> {
+        let path: PathBuf = PathBuf::from(path);
+        let reports_dir: PathBuf = Self::suffixed_path(&path, ".reports");
+        if let Err(e) = fs::create_dir_all(&reports_dir) {
+            let msg: String = format!("failed to create reports directory (path={:?}, e={:?})", reports_dir, e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        let store: Self = Self {
+            path,
+            reports_dir,
+            retention,
+            last_pruned: Mutex::new(Instant::now()),
+        };
+        {
+            let _guard: MutexGuard<'_, Instant> = store.lock()?;
+            store.prune()?;
+        }
+
+        Ok(store)
+    }
+
+    /// Returns the identifier that follows the largest identifier in the target [HistoryStore], so that identifiers
+    /// of jobs are not reused across restarts.
+    pub fn next_id(&self) -> Result<JobId> {
+        let _guard: MutexGuard<'_, Instant> = self.lock()?;
+        let records: Vec<JobRecord> = self.read()?;
+        Ok(records.iter().map(|record| record.id() + 1).max().unwrap_or(0))
+    }
+
+    /// Appends a finished job to the target [HistoryStore].
+    pub fn append(&self, record: &JobRecord) -> Result<()> {
+        let line: String = match serde_json::to_string(record) {
+            Ok(line) => line,
+            Err(e) => {
+                let msg: String = format!("failed to encode job record (id={:?}, e={:?})", record.id(), e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let mut last_pruned: MutexGuard<'_, Instant> = self.lock()?;
+        let mut file: File = match OpenOptions::new().create(true).append(true).open(&self.path) {
+            Ok(file) => file,
+            Err(e) => {
+                let msg: String = format!("failed to open history file (path={:?}, e={:?})", self.path, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        if let Err(e) = writeln!(file, "{}", line) {
+            let msg: String = format!("failed to write history file (path={:?}, e={:?})", self.path, e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        if last_pruned.elapsed() >= Self::PRUNE_INTERVAL {
+            self.prune()?;
+            *last_pruned = Instant::now();
+        }
+
+        Ok(())
+    }
+
+    /// Stores the report of a finished job in the target [HistoryStore].
+    pub fn save_report(&self, id: JobId, format: ReportFormat, report: &str) -> Result<()> {
+        let path: PathBuf = self.report_path(id, format);
+        let _guard: MutexGuard<'_, Instant> = self.lock()?;
+        if let Err(e) = fs::write(&path, report) {
+            let msg: String = format!("failed to write report (path={:?}, e={:?})", path, e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+        Ok(())
+    }
+
+    /// Retrieves the report of a finished job from the target [HistoryStore]. Returns `None` if there is no such
+    /// report.
+    pub fn report(&self, id: JobId, format: ReportFormat) -> Result<Option<String>> {
+        let path: PathBuf = self.report_path(id, format);
+        let _guard: MutexGuard<'_, Instant> = self.lock()?;
+        match fs::read_to_string(&path) {
+            Ok(report) => Ok(Some(report)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                let msg: String = format!("failed to read report (path={:?}, e={:?})", path, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Retrieves a job from the target [HistoryStore].
+    pub fn get(&self, id: JobId) -> Result<Option<JobRecord>> {
+        let _guard: MutexGuard<'_, Instant> = self.lock()?;
+        Ok(self.read()?.into_iter().find(|record| record.id() == id))
+    }
+
+    /// Retrieves the jobs that match a filter, in the order in which they finished.
+    pub fn query(&self, filter: &HistoryFilter) -> Result<Vec<JobRecord>> {
+        let _guard: MutexGuard<'_, Instant> = self.lock()?;
+        let mut records: Vec<JobRecord> = self
+            .read()?
+            .into_iter()
+            .filter(|record| filter.matches(record))
+            .collect();
+
+        if let Some(limit) = filter.limit {
+            if records.len() > limit {
+                records.drain(..records.len() - limit);
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Reads all jobs in the target [HistoryStore]. Malformed lines are skipped.
+    fn read(&self) -> Result<Vec<JobRecord>> {
+        let file: File = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                let msg: String = format!("failed to open history file (path={:?}, e={:?})", self.path, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let mut records: Vec<JobRecord> = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line: String = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    let msg: String = format!("failed to read history file (path={:?}, e={:?})", self.path, e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<JobRecord>(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => log::warn!("skipping malformed history entry (line={:?}, e={:?})", number + 1, e),
+            }
+        }
+
+        Ok(records)
+    }
+
+    /// Removes jobs that are older than the retention period, by rewriting the history file.
+    fn prune(&self) -> Result<()> {
+        let retention: Duration = match self.retention {
+            Some(retention) => retention,
+            None => return Ok(()),
+        };
+        // Nothing is old enough to be pruned if the retention period reaches back further than the clock.
+        let cutoff: SystemTime = match SystemTime::now().checked_sub(retention) {
+            Some(cutoff) => cutoff,
+            None => return Ok(()),
+        };
+
+        let records: Vec<JobRecord> = self.read()?;
+        let kept: Vec<&JobRecord> = records
+            .iter()
+            .filter(|record| record.submitted_at() >= cutoff)
+            .collect();
+        if kept.len() == records.len() {
+            return Ok(());
+        }
+
+        // Write to a temporary file first, so that the history is not lost if we fail half way.
+        let tmp_path: PathBuf = Self::suffixed_path(&self.path, ".tmp");
+        let mut contents: String = String::new();
+        for record in &kept {
+            match serde_json::to_string(record) {
+                Ok(line) => {
+                    contents.push_str(&line);
+                    contents.push('\n');
+                },
+                Err(e) => log::warn!("failed to encode job record (id={:?}, e={:?})", record.id(), e),
+            }
+        }
+        if let Err(e) = fs::write(&tmp_path, contents).and_then(|_| fs::rename(&tmp_path, &self.path)) {
+            let msg: String = format!("failed to prune history file (path={:?}, e={:?})", self.path, e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        // Reports of pruned jobs go away with them.
+        for record in records.iter().filter(|record| record.submitted_at() < cutoff) {
+            for format in [ReportFormat::Json, ReportFormat::Junit] {
+                let path: PathBuf = self.report_path(record.id(), format);
+                if let Err(e) = fs::remove_file(&path) {
+                    if e.kind() != ErrorKind::NotFound {
+                        log::warn!("failed to remove report (path={:?}, e={:?})", path, e);
+                    }
+                }
+            }
+        }
+
+        log::info!("pruned job history (removed={:?})", records.len() - kept.len());
+        Ok(())
+    }
+
+    fn report_path(&self, id: JobId, format: ReportFormat) -> PathBuf {
+        self.reports_dir.join(format!("{}.{}", id, format.extension()))
+    }
+
+    /// Returns a path that names a sibling of a file, by appending a suffix to its file name.
+    fn suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+        let mut suffixed_path: std::ffi::OsString = path.as_os_str().to_os_string();
+        suffixed_path.push(suffix);
+        PathBuf::from(suffixed_path)
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Instant>> {
+        match self.last_pruned.lock() {
+            Ok(guard) => Ok(guard),
+            Err(e) => {
+                let msg: String = format!("failed to lock history store (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+}
+
+impl HistoryFilter {
+    /// Checks if a job matches the target [HistoryFilter].
+    fn matches(&self, record: &JobRecord) -> bool {
+        // Instances of a job with a matrix are named after the job, followed by their matrix values.
+        if let Some(name) = &self.name {
+            if record.name() != name && !record.name().starts_with(&format!("{}[", name)) {
+                return false;
+            }
+        }
+        if let Some(state) = self.state {
+            if record.state() != state {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if record.submitted_at() < since {
+                return false;
+            }
+        }
+        if let Some(until) = self.until {
+            if record.submitted_at() > until {
+                return false;
+            }
+        }
+        true
+    }
+}
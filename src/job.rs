@@ -6,15 +6,18 @@
 //======================================================================================================================
 
 use crate::{
-    action::Action,
+    action::{Action, FileTransfer, TransferDirection},
+    config::Config,
     task::{Task, TaskQueue},
 };
 use ::anyhow::Result;
 use ::yaml_rust::{Yaml, YamlLoader};
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::{HashMap, HashSet, VecDeque},
     fs::File,
     io::Read,
+    path::Path,
+    time::Duration,
 };
 
 //======================================================================================================================
@@ -22,9 +25,22 @@ use std::{
 //======================================================================================================================
 
 pub struct Job {
+    /// Matrix values of this job instance, in the order in which they are declared.
+    combination: Vec<(String, String)>,
     env: HashMap<String, String>,
     tasks_queues: HashMap<String, TaskQueue>,
+    /// Labels that a runner must have to be assigned to each worker.
+    requirements: HashMap<String, HashMap<String, String>>,
+    /// Priority of this job when waiting for runners. Higher values are served first.
+    priority: i64,
+    /// Maximum amount of time that this job may wait for runners.
+    allocation_timeout: Option<Duration>,
     barrier_participants: Vec<usize>,
+    /// Names of the secrets that this job references.
+    secret_names: Vec<String>,
+    /// Values of the referenced secrets, keyed by environment variable name. These are kept apart from the
+    /// environment, so that they are not recorded along with the parameters of the job.
+    secrets: HashMap<String, String>,
 }
 
 //======================================================================================================================
@@ -32,54 +48,143 @@ pub struct Job {
 //======================================================================================================================
 
 impl Job {
-    const JOB_ENTRY_NAME: &'static str = "job";
-    const ACTION_ENTRY_NAME: &'static str = "action";
-    const BARRIER_ENTRY_NAME: &'static str = "barrier";
-    const RUNS_ON_ENTRY_NAME: &'static str = "runs-on";
-    const COMMANDS_ENTRY_NAME: &'static str = "commands";
+    pub const JOB_ENTRY_NAME: &'static str = "job";
+    pub const ACTION_ENTRY_NAME: &'static str = "action";
+    pub const BARRIER_ENTRY_NAME: &'static str = "barrier";
+    pub const RUNS_ON_ENTRY_NAME: &'static str = "runs-on";
+    pub const COMMANDS_ENTRY_NAME: &'static str = "commands";
+    pub const TIMEOUT_ENTRY_NAME: &'static str = "timeout";
+    pub const NEEDS_ENTRY_NAME: &'static str = "needs";
+    pub const MATRIX_ENTRY_NAME: &'static str = "matrix";
+    pub const LABELS_ENTRY_NAME: &'static str = "labels";
+    pub const PRIORITY_ENTRY_NAME: &'static str = "priority";
+    pub const ALLOCATION_TIMEOUT_ENTRY_NAME: &'static str = "allocation-timeout";
+    pub const UPLOAD_ENTRY_NAME: &'static str = "upload";
+    pub const DOWNLOAD_ENTRY_NAME: &'static str = "download";
+    pub const SOURCE_ENTRY_NAME: &'static str = "source";
+    pub const DESTINATION_ENTRY_NAME: &'static str = "destination";
+    pub const ARTIFACTS_ENTRY_NAME: &'static str = "artifacts";
+    pub const SECRETS_ENTRY_NAME: &'static str = "secrets";
 
-    pub fn new(job_path: &str, parameters: HashMap<String, String>) -> Result<Self> {
+    /// Loads a job file. If the job file has a matrix entry, one job instance is created for each combination of
+    /// matrix values, otherwise a single job instance is created.
+    pub fn load(job_path: &str, parameters: HashMap<String, String>) -> Result<Vec<Self>> {
         log::trace!("job: path={}, env={:?}", job_path, parameters);
         let mut job_s: String = String::new();
         File::open(job_path)?.read_to_string(&mut job_s)?;
 
         let yaml: Vec<Yaml> = YamlLoader::load_from_str(&job_s)?;
-        let mut job_entries: VecDeque<Task> = Self::parse(&yaml)?;
+        let combinations: Vec<Vec<(String, String)>> = Self::parse_matrix(&yaml)?;
+
+        let mut jobs: Vec<Self> = Vec::new();
+        for combination in combinations {
+            // Inject matrix values as environment variables.
+            let mut env: HashMap<String, String> = parameters.clone();
+            for (key, value) in &combination {
+                let key: String = format!("{}{}", Config::env_var_prefix(), key.to_uppercase().replace('-', "_"));
+                env.insert(key, value.to_string());
+            }
+            jobs.push(Self::new(&yaml, env, combination)?);
+        }
+
+        Ok(jobs)
+    }
+
+    /// Instantiates a single job instance.
+    fn new(yaml: &Vec<Yaml>, parameters: HashMap<String, String>, combination: Vec<(String, String)>) -> Result<Self> {
+        let mut job_entries: VecDeque<Task> = Self::parse(yaml)?;
+        Self::check_dependencies(&job_entries)?;
+
+        let priority: i64 = match &yaml[0][Self::PRIORITY_ENTRY_NAME] {
+            Yaml::BadValue => 0,
+            Yaml::Integer(priority) => *priority,
+            entry => {
+                let msg: String = format!(
+                    "failed to parse {} entry (entry={:?})",
+                    Self::PRIORITY_ENTRY_NAME,
+                    entry
+                );
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        let allocation_timeout: Option<Duration> = Self::parse_timeout(&yaml[0][Self::ALLOCATION_TIMEOUT_ENTRY_NAME])?;
+        let secret_names: Vec<String> = match &yaml[0][Self::SECRETS_ENTRY_NAME] {
+            Yaml::BadValue => Vec::new(),
+            entry => Self::parse_names(entry, Self::SECRETS_ENTRY_NAME)?,
+        };
 
         let mut tasks: HashMap<String, TaskQueue> = HashMap::new();
-        let mut barrier_participants = 0;
-        let mut barrier_participants_ = Vec::new();
+        let mut requirements: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut barrier_participants: Vec<usize> = Vec::new();
         loop {
             let job_entry: Option<Task> = job_entries.pop_front();
 
             match job_entry {
                 Some(Task::Action(task)) => {
-                    barrier_participants += 1;
                     // Insert task on the queue of the worker on which it should run.
                     let runs_on: String = task.runs_on().to_string();
 
+                    // Merge labels required by the task into the requirements of the worker.
+                    let worker_requirements: &mut HashMap<String, String> =
+                        requirements.entry(runs_on.clone()).or_default();
+                    for (key, value) in task.labels() {
+                        match worker_requirements.get(key) {
+                            Some(other) if other != value => {
+                                let msg: String = format!(
+                                    "conflicting labels (worker={:?}, key={:?}, values=[{:?}, {:?}])",
+                                    runs_on, key, other, value
+                                );
+                                log::error!("{}", msg);
+                                anyhow::bail!(msg);
+                            },
+                            _ => {
+                                worker_requirements.insert(key.clone(), value.clone());
+                            },
+                        }
+                    }
+
                     let task_queue = tasks.entry(runs_on).or_insert_with(|| TaskQueue::default());
                     task_queue.push_back(Task::Action(task));
                 },
                 Some(Task::Barrier(_)) => {
-                    // Insert barrier in all work queues.
+                    // Insert barrier in all work queues. Every worker that has a queue at this point participates.
+                    let barrier_index: usize = barrier_participants.len();
                     for (_, task_queue) in tasks.iter_mut() {
-                        task_queue.push_back(Task::Barrier(barrier_participants));
+                        task_queue.push_back(Task::Barrier(barrier_index));
                     }
-                    barrier_participants_.push(barrier_participants);
-                    barrier_participants = 0;
+                    barrier_participants.push(tasks.len());
                 },
                 None => break,
             }
         }
 
         Ok(Self {
+            combination,
             env: parameters,
             tasks_queues: tasks,
-            barrier_participants: barrier_participants_,
+            requirements,
+            priority,
+            allocation_timeout,
+            barrier_participants,
+            secret_names,
+            secrets: HashMap::new(),
         })
     }
 
+    /// Returns a name for the matrix combination of the target [Job], or an empty string if it has none.
+    pub fn variant(&self) -> String {
+        if self.combination.is_empty() {
+            return String::new();
+        }
+        let values: Vec<String> = self
+            .combination
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        format!("[{}]", values.join(","))
+    }
+
     // Return the set of tasks that are associated to a given worker.
     pub fn get_worker_tasks(&mut self, worker_name: &str) -> Option<TaskQueue> {
         self.tasks_queues.remove(worker_name)
@@ -93,8 +198,34 @@ impl Job {
         self.tasks_queues.len()
     }
 
-    pub fn get_task_names(&self) -> Vec<String> {
-        self.tasks_queues.keys().cloned().collect()
+    /// Returns the priority of the target [Job] when waiting for runners.
+    pub fn priority(&self) -> i64 {
+        self.priority
+    }
+
+    /// Overrides the priority of the target [Job].
+    pub fn set_priority(&mut self, priority: i64) {
+        self.priority = priority;
+    }
+
+    /// Returns the maximum amount of time that the target [Job] may wait for runners.
+    pub fn allocation_timeout(&self) -> Option<Duration> {
+        self.allocation_timeout
+    }
+
+    /// Overrides the maximum amount of time that the target [Job] may wait for runners.
+    pub fn set_allocation_timeout(&mut self, allocation_timeout: Duration) {
+        self.allocation_timeout = Some(allocation_timeout);
+    }
+
+    /// Returns the name of the environment variable that holds the address of the runner assigned to a worker.
+    pub fn worker_addr_var(worker_name: &str) -> String {
+        format!("{}{}", Config::env_var_prefix(), worker_name.to_uppercase())
+    }
+
+    /// Returns the labels that a runner must have to be assigned to each worker.
+    pub fn requirements(&self) -> &HashMap<String, HashMap<String, String>> {
+        &self.requirements
     }
 
     /// Parses a job file.
@@ -110,6 +241,9 @@ impl Job {
             },
         };
 
+        // Parse job-level timeout entry, which is used for actions that do not set their own.
+        let default_timeout: Option<Duration> = Self::parse_timeout(&doc[Self::TIMEOUT_ENTRY_NAME])?;
+
         // Parse task entries.
         let mut tasks: VecDeque<Task> = VecDeque::new();
         for task in job {
@@ -143,9 +277,42 @@ impl Job {
                         },
                     };
 
+                    // Parse file transfer entries, which replace the commands entry.
+                    let upload_entry: Option<&Yaml> = entry.get(&Yaml::from_str(Self::UPLOAD_ENTRY_NAME));
+                    let download_entry: Option<&Yaml> = entry.get(&Yaml::from_str(Self::DOWNLOAD_ENTRY_NAME));
+                    let has_commands: bool = entry.contains_key(&Yaml::from_str(Self::COMMANDS_ENTRY_NAME));
+                    let transfer: Option<FileTransfer> = match (upload_entry, download_entry, has_commands) {
+                        (None, None, _) => None,
+                        (Some(upload_entry), None, false) => {
+                            Some(Self::parse_transfer(upload_entry, TransferDirection::Upload)?)
+                        },
+                        (None, Some(download_entry), false) => {
+                            Some(Self::parse_transfer(download_entry, TransferDirection::Download)?)
+                        },
+                        _ => {
+                            let msg: String = format!(
+                                "only one of {}, {}, and {} entries is allowed (action={:?})",
+                                Self::COMMANDS_ENTRY_NAME,
+                                Self::UPLOAD_ENTRY_NAME,
+                                Self::DOWNLOAD_ENTRY_NAME,
+                                name
+                            );
+                            log::error!("{}", msg);
+                            anyhow::bail!(msg);
+                        },
+                    };
+
                     // Parse commands entry.
                     let commands: Vec<String> = match entry.get(&Yaml::from_str(Self::COMMANDS_ENTRY_NAME)) {
+                        _ if transfer.is_some() => Vec::new(),
                         Some(commands_entry) => match commands_entry.as_vec() {
+                            // Runners chain commands together, so there must be at least one.
+                            Some(commands_entry_vec) if commands_entry_vec.is_empty() => {
+                                let msg: String =
+                                    format!("empty {} entry (action={:?})", Self::COMMANDS_ENTRY_NAME, name);
+                                log::error!("{}", msg);
+                                anyhow::bail!(msg);
+                            },
                             Some(commands_entry_vec) => {
                                 let mut commands: Vec<String> = Vec::default();
                                 for command in commands_entry_vec {
@@ -174,9 +341,37 @@ impl Job {
                         },
                     };
 
+                    // Parse timeout entry.
+                    let timeout: Option<Duration> = match entry.get(&Yaml::from_str(Self::TIMEOUT_ENTRY_NAME)) {
+                        Some(timeout_entry) => Self::parse_timeout(timeout_entry)?,
+                        None => default_timeout,
+                    };
+
+                    // Parse needs entry.
+                    let needs: Vec<String> = match entry.get(&Yaml::from_str(Self::NEEDS_ENTRY_NAME)) {
+                        Some(needs_entry) => Self::parse_names(needs_entry, Self::NEEDS_ENTRY_NAME)?,
+                        None => Vec::new(),
+                    };
+
+                    // Parse labels entry.
+                    let labels: HashMap<String, String> = match entry.get(&Yaml::from_str(Self::LABELS_ENTRY_NAME)) {
+                        Some(labels_entry) => Self::parse_labels(labels_entry)?,
+                        None => HashMap::new(),
+                    };
+
+                    // Parse artifacts entry.
+                    let artifacts: Vec<String> = match entry.get(&Yaml::from_str(Self::ARTIFACTS_ENTRY_NAME)) {
+                        Some(artifacts_entry) => Self::parse_artifacts(artifacts_entry)?,
+                        None => Vec::new(),
+                    };
+
                     // Create action and insert it into the list of tasks.
-                    let action: Action = Action::new(&name, commands, &runs_on);
-                    tasks.push_back(Task::Action(action));
+                    let mut action: Action = match transfer {
+                        Some(transfer) => Action::new_transfer(&name, transfer, &runs_on, timeout, needs, labels),
+                        None => Action::new(&name, commands, &runs_on, timeout, needs, labels),
+                    };
+                    action.set_artifacts(artifacts);
+                    tasks.push_back(Task::Action(Box::new(action)));
                 }
                 // Check if we need to parse a barrier entry.
                 else if entry.contains_key(&Yaml::from_str(Self::BARRIER_ENTRY_NAME)) {
@@ -193,11 +388,323 @@ impl Job {
         Ok(tasks)
     }
 
+    /// Parses the matrix entry of a job file and returns all combinations of matrix values. A job file without a
+    /// matrix entry has a single, empty, combination.
+    pub fn parse_matrix(docs: &[Yaml]) -> Result<Vec<Vec<(String, String)>>> {
+        let mut combinations: Vec<Vec<(String, String)>> = vec![Vec::new()];
+
+        let matrix = match &docs[0][Self::MATRIX_ENTRY_NAME] {
+            Yaml::BadValue => return Ok(combinations),
+            Yaml::Hash(matrix) => matrix,
+            entry => {
+                let msg: String = format!("failed to parse {} entry (entry={:?})", Self::MATRIX_ENTRY_NAME, entry);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        for (key, values) in matrix {
+            let key: String = match key.as_str() {
+                Some(key)
+                    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') =>
+                {
+                    key.to_string()
+                },
+                _ => {
+                    let msg: String = format!("malformed {} key (key={:?})", Self::MATRIX_ENTRY_NAME, key);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+
+            let values: Vec<String> = match values.as_vec() {
+                Some(values) if !values.is_empty() => {
+                    let mut values_: Vec<String> = Vec::new();
+                    for value in values {
+                        match Config::parse_scalar(value) {
+                            Some(value) => values_.push(value),
+                            None => {
+                                let msg: String = format!(
+                                    "malformed {} value (key={:?}, value={:?})",
+                                    Self::MATRIX_ENTRY_NAME,
+                                    key,
+                                    value
+                                );
+                                log::error!("{}", msg);
+                                anyhow::bail!(msg);
+                            },
+                        }
+                    }
+                    values_
+                },
+                _ => {
+                    let msg: String = format!(
+                        "{} values must be a non-empty list (key={:?})",
+                        Self::MATRIX_ENTRY_NAME,
+                        key
+                    );
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+
+            // Extend every combination built so far with each value of this key.
+            let mut extended: Vec<Vec<(String, String)>> = Vec::new();
+            for combination in &combinations {
+                for value in &values {
+                    let mut combination: Vec<(String, String)> = combination.clone();
+                    combination.push((key.clone(), value.clone()));
+                    extended.push(combination);
+                }
+            }
+            combinations = extended;
+        }
+
+        Ok(combinations)
+    }
+
+    /// Parses a labels entry, which maps label names to values.
+    pub fn parse_labels(entry: &Yaml) -> Result<HashMap<String, String>> {
+        let mut labels: HashMap<String, String> = HashMap::new();
+        match entry.as_hash() {
+            Some(entry) => {
+                for (key, value) in entry {
+                    match (key.as_str(), Config::parse_scalar(value)) {
+                        (Some(key), Some(value)) => {
+                            labels.insert(key.to_string(), value);
+                        },
+                        _ => {
+                            let msg: String = format!(
+                                "failed to parse {} entry (key={:?}, value={:?})",
+                                Self::LABELS_ENTRY_NAME,
+                                key,
+                                value
+                            );
+                            log::error!("{}", msg);
+                            anyhow::bail!(msg);
+                        },
+                    }
+                }
+            },
+            None => {
+                let msg: String = format!("failed to parse {} entry (entry={:?})", Self::LABELS_ENTRY_NAME, entry);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+        Ok(labels)
+    }
+
+    /// Parses an upload or download entry, which has a source and a destination path.
+    pub fn parse_transfer(entry: &Yaml, direction: TransferDirection) -> Result<FileTransfer> {
+        let source: Option<&str> = entry[Self::SOURCE_ENTRY_NAME].as_str();
+        let destination: Option<&str> = entry[Self::DESTINATION_ENTRY_NAME].as_str();
+        match (source, destination) {
+            (Some(source), Some(destination)) if !source.is_empty() && !destination.is_empty() => {
+                Ok(FileTransfer::new(direction, source, destination))
+            },
+            _ => {
+                let msg: String = format!(
+                    "failed to parse {} entry, expected {} and {} paths (entry={:?})",
+                    direction,
+                    Self::SOURCE_ENTRY_NAME,
+                    Self::DESTINATION_ENTRY_NAME,
+                    entry
+                );
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Parses a timeout entry, which is expressed in seconds.
+    pub fn parse_timeout(entry: &Yaml) -> Result<Option<Duration>> {
+        match entry {
+            Yaml::BadValue => Ok(None),
+            Yaml::Integer(seconds) if *seconds > 0 => Ok(Some(Duration::from_secs(*seconds as u64))),
+            _ => {
+                let msg: String = format!("failed to parse timeout entry (entry={:?})", entry);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Parses an entry that is either a single string or a list of strings.
+    pub fn parse_names(entry: &Yaml, entry_name: &str) -> Result<Vec<String>> {
+        let names: Vec<&Yaml> = match entry {
+            Yaml::String(_) => vec![entry],
+            Yaml::Array(names) => names.iter().collect(),
+            _ => {
+                let msg: String = format!("failed to parse {} entry (entry={:?})", entry_name, entry);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let mut parsed: Vec<String> = Vec::new();
+        for name in names {
+            match name.as_str() {
+                Some(name) => parsed.push(name.to_string()),
+                None => {
+                    let msg: String = format!("failed to parse {} entry (entry={:?})", entry_name, name);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Parses an artifacts entry, which is either a single remote path or a list of remote paths. Artifacts are stored
+    /// under their file names, so these must be distinct within an action.
+    pub fn parse_artifacts(entry: &Yaml) -> Result<Vec<String>> {
+        let artifacts: Vec<String> = Self::parse_names(entry, Self::ARTIFACTS_ENTRY_NAME)?;
+        let mut file_names: HashSet<String> = HashSet::new();
+        for artifact in &artifacts {
+            let inserted: bool = match Path::new(artifact).file_name() {
+                Some(file_name) => file_names.insert(file_name.to_string_lossy().to_string()),
+                None => false,
+            };
+            if !inserted {
+                let msg: String = format!(
+                    "malformed or duplicate {} entry (path={:?})",
+                    Self::ARTIFACTS_ENTRY_NAME,
+                    artifact
+                );
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            }
+        }
+
+        Ok(artifacts)
+    }
+
+    /// Checks that dependencies between tasks are well-formed. Actions on the same worker run in order and barriers
+    /// wait for all workers that have been seen so far, so these implicit dependencies are taken into account when
+    /// looking for cycles. Action names may repeat, unless another action needs them.
+    fn check_dependencies(tasks: &VecDeque<Task>) -> Result<()> {
+        let needed: HashSet<&str> = tasks
+            .iter()
+            .filter_map(|task| match task {
+                Task::Action(action) => Some(action.needs().iter().map(|name| name.as_str())),
+                Task::Barrier(_) => None,
+            })
+            .flatten()
+            .collect();
+
+        // Assign a node to each task, and index actions by name.
+        let mut node_names: Vec<String> = Vec::new();
+        let mut action_nodes: HashMap<String, usize> = HashMap::new();
+        for (node, task) in tasks.iter().enumerate() {
+            match task {
+                Task::Action(action) => {
+                    let duplicate: bool = action_nodes.insert(action.name().to_string(), node).is_some();
+                    if duplicate && needed.contains(action.name()) {
+                        let msg: String = format!(
+                            "duplicate action name, which another action needs (name={:?})",
+                            action.name()
+                        );
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    }
+                    node_names.push(action.name().to_string());
+                },
+                Task::Barrier(_) => node_names.push(format!("{}#{}", Self::BARRIER_ENTRY_NAME, node)),
+            }
+        }
+
+        // Build the dependency graph, with edges pointing from a task to the ones that it waits for.
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); tasks.len()];
+        let mut last_node: HashMap<String, usize> = HashMap::new();
+        for (node, task) in tasks.iter().enumerate() {
+            match task {
+                Task::Action(action) => {
+                    if let Some(previous) = last_node.insert(action.runs_on().to_string(), node) {
+                        edges[node].push(previous);
+                    }
+                    for name in action.needs() {
+                        match action_nodes.get(name) {
+                            Some(dependency) => edges[node].push(*dependency),
+                            None => {
+                                let msg: String =
+                                    format!("unknown dependency (action={:?}, needs={:?})", action.name(), name);
+                                log::error!("{}", msg);
+                                anyhow::bail!(msg);
+                            },
+                        }
+                    }
+                },
+                Task::Barrier(_) => {
+                    for previous in last_node.values_mut() {
+                        edges[node].push(*previous);
+                        *previous = node;
+                    }
+                },
+            }
+        }
+
+        // Look for cycles with a depth-first search.
+        let mut visited: HashSet<usize> = HashSet::new();
+        for node in 0..tasks.len() {
+            let mut path: Vec<usize> = Vec::new();
+            if let Some(cycle) = Self::find_cycle(node, &edges, &mut visited, &mut path) {
+                let cycle: Vec<&str> = cycle.iter().map(|node| node_names[*node].as_str()).collect();
+                let msg: String = format!("dependency cycle (cycle={:?})", cycle);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Looks for a cycle that is reachable from a given node, returning the nodes in the cycle if one is found.
+    fn find_cycle(
+        node: usize,
+        edges: &Vec<Vec<usize>>,
+        visited: &mut HashSet<usize>,
+        path: &mut Vec<usize>,
+    ) -> Option<Vec<usize>> {
+        if let Some(pos) = path.iter().position(|n| *n == node) {
+            return Some(path[pos..].to_vec());
+        }
+        if !visited.insert(node) {
+            return None;
+        }
+
+        path.push(node);
+        for next in &edges[node] {
+            if let Some(cycle) = Self::find_cycle(*next, edges, visited, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+
+        None
+    }
+
     /// Returns the environment variables that should be set for the job.
     pub fn env(&self) -> &HashMap<String, String> {
         &self.env
     }
 
+    /// Returns the names of the secrets that the target [Job] references.
+    pub fn secret_names(&self) -> &Vec<String> {
+        &self.secret_names
+    }
+
+    /// Returns the values of the secrets that the target [Job] references, keyed by environment variable name.
+    pub fn secrets(&self) -> &HashMap<String, String> {
+        &self.secrets
+    }
+
+    /// Sets the values of the secrets that the target [Job] references.
+    pub fn set_secrets(&mut self, secrets: HashMap<String, String>) {
+        self.secrets = secrets;
+    }
+
     /// Appends an environment variable to the job.
     pub fn append_env(&mut self, key: String, value: String) {
         self.env.insert(key, value);
@@ -7,34 +7,65 @@
 
 mod action;
 mod args;
+mod artifacts;
+mod cancellation;
 mod config;
 mod credentials;
+mod dependencies;
+mod history;
 mod job;
+mod notifier;
+mod plan;
+mod registry;
+mod rendezvous;
+mod report;
 mod runner;
 mod scheduler;
+mod secrets;
 mod task;
+#[cfg(test)]
+mod tests;
+mod transfer;
+mod validator;
 mod web;
+mod webhook;
 mod worker;
 
 //======================================================================================================================
 // Imports
 //======================================================================================================================
 
-use crate::args::ProgramArguments;
+use crate::args::{ProgramArguments, ProgramCommand};
 use crate::credentials::Credentials;
 use ::flexi_logger::Logger;
 use ::std::sync::Once;
 use anyhow::Result;
 use config::Config;
-use http::Request;
+use history::{HistoryFilter, HistoryStore};
+use http::{header::CONTENT_TYPE, Method, Request, Response, StatusCode, Version};
 use job::Job;
+use registry::{JobId, JobRecord, JobState};
+use report::ReportFormat;
 use runner::Runner;
 use scheduler::Scheduler;
+use secrets::SecretStore;
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    fs::File,
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::{Duration, UNIX_EPOCH},
 };
-use web::server::HttpServer;
+use validator::{Diagnostic, JobValidator, Severity};
+use web::{
+    auth::{Authenticator, Scope},
+    body::HttpBody,
+    server::HttpServer,
+};
+use webhook::{GithubWebhook, WebhookEvent};
 
 //======================================================================================================================
 // Static Variables
@@ -43,6 +74,27 @@ use web::server::HttpServer;
 /// Guardian to the logging initialize function.
 static INIT_LOG: Once = Once::new();
 
+/// Query parameter that requests the output of a job to be streamed back.
+const STREAM_PARAMETER: &str = "STREAM";
+
+/// Query parameter that overrides the priority of a job.
+const PRIORITY_PARAMETER: &str = "PRIORITY";
+
+/// Query parameter that overrides the maximum amount of time (in seconds) that a job may wait for runners.
+const ALLOCATION_TIMEOUT_PARAMETER: &str = "ALLOCATION_TIMEOUT";
+
+/// Query parameter that requests a job to be planned instead of run.
+const DRY_RUN_PARAMETER: &str = "DRY_RUN";
+
+/// Query parameter that requests a report in a given format (`json` or `junit`).
+const FORMAT_PARAMETER: &str = "FORMAT";
+
+/// Header that carries the name of a GitHub event.
+const GITHUB_EVENT_HEADER: &str = "X-GitHub-Event";
+
+/// Header that carries the signature of a GitHub webhook payload.
+const GITHUB_SIGNATURE_HEADER: &str = "X-Hub-Signature-256";
+
 //======================================================================================================================
 // Standalone Functions
 //======================================================================================================================
@@ -65,19 +117,50 @@ fn main() -> Result<()> {
         "CI Orchestrator for Demikernel",
     )?;
 
-    let credentials: Credentials = Credentials::new(args.username(), args.public_key_path(), args.private_key_path());
-    let config: Config = Config::new(args.config_file())?;
-    let web_server: HttpServer = HttpServer::new(&config.addr()?)?;
-    let runners: Vec<Mutex<Runner>> = config.get_workers(&credentials)?;
-    let scheduler: Arc<Scheduler> = Arc::new(Scheduler::new(runners));
+    match args.command() {
+        ProgramCommand::Serve => {},
+        ProgramCommand::Validate(job_paths) => return validate_jobs(job_paths),
+        ProgramCommand::Plan(job_path, parameters) => return plan_job(&args, job_path, parameters),
+    }
+
+    let credentials: Credentials =
+        Credentials::new(args.username()?, args.public_key_path()?, args.private_key_path()?);
+    let config: Config = Config::new(args.config_file()?)?;
+    let authenticator: Option<Authenticator> = config
+        .api_tokens()?
+        .map(|tokens| Authenticator::new(tokens, required_scope));
+    let web_server: HttpServer = HttpServer::new(&config.addr()?, authenticator, config.tls_acceptor()?)?;
+    let runners: Vec<Mutex<Box<dyn Runner>>> = config.get_workers(&credentials)?;
+    let history: HistoryStore = HistoryStore::new(&config.history_path(), config.history_retention()?)?;
+    let scheduler: Arc<Scheduler> = Arc::new(Scheduler::new(
+        runners,
+        &config.artifacts_home(),
+        history,
+        config.notifier()?,
+        config.secrets()?,
+    )?);
+    scheduler.monitor_runners()?;
     let job_home: String = config.jobs_home();
     let env_var_prefix: String = Config::env_var_prefix();
+    let github_webhook: Option<Arc<GithubWebhook>> = config.github_webhook()?.map(Arc::new);
 
     // Request dispatcher.
-    let dispatcher = |request: Request<()>| -> Result<Vec<String>> {
+    let dispatcher = |request: Request<Vec<u8>>| -> Result<Response<HttpBody>> {
         match request.uri().path() {
             // Run a job.
             "/run" => run_job(env_var_prefix, job_home, scheduler, request),
+            // Run the jobs that a GitHub event triggers.
+            "/webhook/github" => handle_github_webhook(env_var_prefix, job_home, scheduler, github_webhook, request),
+            // List all jobs.
+            "/jobs" => list_jobs(scheduler),
+            // Get the status, the report, or the artifacts of a job, or cancel it.
+            path if path.starts_with("/jobs/") => route_job(scheduler, &path["/jobs/".len()..], &request),
+            // Query finished jobs.
+            "/history" => query_history(scheduler, request),
+            // List all runners.
+            "/runners" => list_runners(scheduler),
+            // Stop or resume allocating a runner to new jobs.
+            path if path.starts_with("/runners/") => drain_runner(scheduler, &path["/runners/".len()..], &request),
             // Unsupported.
             unsupported => {
                 let message: String = format!("unsupported trigger (trigger={:?})", unsupported);
@@ -87,22 +170,120 @@ fn main() -> Result<()> {
         }
     };
 
-    web_server.run(dispatcher);
+    web_server.run(dispatcher, is_long_lived);
+
+    Ok(())
+}
+
+/// Checks job files and prints the problems found in them. Fails if any of them has errors.
+fn validate_jobs(job_paths: &[String]) -> Result<()> {
+    let mut num_errors: usize = 0;
+    let mut num_warnings: usize = 0;
+    for job_path in job_paths {
+        let diagnostics: Vec<Diagnostic> = match JobValidator::validate(job_path) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                println!("{}: error: failed to read job file (e={})", job_path, e);
+                num_errors += 1;
+                continue;
+            },
+        };
+        for diagnostic in diagnostics {
+            match diagnostic.severity() {
+                Severity::Error => num_errors += 1,
+                Severity::Warning => num_warnings += 1,
+            }
+            println!("{}", diagnostic);
+        }
+    }
+    println!(
+        "checked {} job files (errors={}, warnings={})",
+        job_paths.len(),
+        num_errors,
+        num_warnings
+    );
+
+    if num_errors > 0 {
+        let msg: String = format!("invalid job files (errors={})", num_errors);
+        log::error!("{}", msg);
+        anyhow::bail!(msg);
+    }
+    Ok(())
+}
+
+/// Prints what running a job file would do, without running it. Workers are placed on the runners of the
+/// configuration file as if all of them were idle.
+fn plan_job(args: &ProgramArguments, job_path: &str, parameters: &[(String, String)]) -> Result<()> {
+    let config: Config = Config::new(args.config_file()?)?;
+    // Runners are not connected to, so credentials are optional.
+    let credentials: Credentials = Credentials::new(
+        args.username().unwrap_or(""),
+        args.public_key_path().unwrap_or(""),
+        args.private_key_path().unwrap_or(""),
+    );
+    let runners: Vec<Mutex<Box<dyn Runner>>> = config.get_workers(&credentials)?;
+    let secrets: SecretStore = config.secrets()?;
+
+    // Build the same environment as a run request would.
+    let job_name: String = match Path::new(job_path).file_name() {
+        Some(file_name) => file_name.to_string_lossy().to_string(),
+        None => job_path.to_string(),
+    };
+    let env_var_prefix: String = Config::env_var_prefix();
+    let mut env: HashMap<String, String> = HashMap::new();
+    env.insert(format!("{}JOB", env_var_prefix), job_name.clone());
+    for (key, value) in parameters {
+        env.insert(format!("{}{}", env_var_prefix, key.to_uppercase()), value.clone());
+    }
 
+    for job in Job::load(job_path, env)? {
+        let name: String = format!("{}{}", job_name, job.variant());
+        for line in Scheduler::plan_offline(&runners, &secrets, &name, job)?.describe() {
+            println!("{}", line);
+        }
+    }
     Ok(())
 }
 
+/// Returns the scope that a request requires. Webhooks are authenticated by their own signatures instead.
+fn required_scope(request: &Request<Vec<u8>>) -> Option<Scope> {
+    match request.uri().path() {
+        "/run" => Some(Scope::Submit),
+        path if path.starts_with("/webhook/") => None,
+        path if path.starts_with("/runners/") => Some(Scope::Admin),
+        path if path.starts_with("/jobs/") && path.ends_with("/cancel") => Some(Scope::Cancel),
+        _ => Some(Scope::View),
+    }
+}
+
+/// Checks if a request keeps its connection open until jobs finish, either to stream their output or to report on them.
+fn is_long_lived(request: &Request<Vec<u8>>) -> bool {
+    if request.uri().path() != "/run" {
+        return false;
+    }
+    match parse_run_parameters(request) {
+        Ok(Some(parameters)) => parameters.contains_key(STREAM_PARAMETER) || parameters.contains_key(FORMAT_PARAMETER),
+        _ => false,
+    }
+}
+
 fn run_job(
     env_var_prefix: String,
     job_home: String,
     scheduler: Arc<Scheduler>,
-    request: Request<()>,
-) -> Result<Vec<String>> {
-    log::trace!("run_job(): uri={}", request.uri());
-    match request.uri().query() {
-        Some(parameters) => {
-            let parameters: HashMap<String, String> = parse_job_parameters(parameters);
-
+    request: Request<Vec<u8>>,
+) -> Result<Response<HttpBody>> {
+    log::trace!("run_job(): method={}, uri={}", request.method(), request.uri());
+    let parameters: Option<HashMap<String, String>> = match parse_run_parameters(&request) {
+        Ok(parameters) => parameters,
+        Err(e) => {
+            let message: String = format!("malformed job submission (e={})", e);
+            log::error!("{}", message);
+            return build_response(StatusCode::BAD_REQUEST, vec![message]);
+        },
+    };
+    match parameters {
+        Some(mut parameters) => {
             if parameters.is_empty() {
                 let message: String = format!("malformed query");
                 log::error!("{}", message);
@@ -118,6 +299,63 @@ fn run_job(
                 },
             };
 
+            // Check if output should be streamed back as the job runs. This is not passed on to the job.
+            let stream: bool = match parameters.remove(STREAM_PARAMETER) {
+                Some(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+                None => false,
+            };
+
+            // Check if scheduling options should be overridden. These are not passed on to the job.
+            let priority: Option<i64> = match parameters.remove(PRIORITY_PARAMETER) {
+                Some(value) => match value.parse::<i64>() {
+                    Ok(priority) => Some(priority),
+                    Err(_) => {
+                        let message: String = format!("malformed priority (priority={:?})", value);
+                        log::error!("{}", message);
+                        return build_response(StatusCode::BAD_REQUEST, vec![message]);
+                    },
+                },
+                None => None,
+            };
+            // Check if a report should be returned once the job finishes. This is not passed on to the job.
+            let format: Option<ReportFormat> = match parameters.remove(FORMAT_PARAMETER) {
+                Some(value) => match value.parse::<ReportFormat>() {
+                    Ok(format) => Some(format),
+                    Err(e) => {
+                        let message: String = format!("malformed report format (e={})", e);
+                        log::error!("{}", message);
+                        return build_response(StatusCode::BAD_REQUEST, vec![message]);
+                    },
+                },
+                None => None,
+            };
+            // Check if the job should be planned instead of run. This is not passed on to the job.
+            let dry_run: bool = match parameters.remove(DRY_RUN_PARAMETER) {
+                Some(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+                None => false,
+            };
+            if dry_run && (stream || format.is_some()) {
+                let message: String = "cannot plan a job and run it at the same time".to_string();
+                log::error!("{}", message);
+                return build_response(StatusCode::BAD_REQUEST, vec![message]);
+            }
+            if stream && format.is_some() {
+                let message: String = "cannot stream output and return a report at the same time".to_string();
+                log::error!("{}", message);
+                return build_response(StatusCode::BAD_REQUEST, vec![message]);
+            }
+            let allocation_timeout: Option<Duration> = match parameters.remove(ALLOCATION_TIMEOUT_PARAMETER) {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(seconds) => Some(Duration::from_secs(seconds)),
+                    Err(_) => {
+                        let message: String = format!("malformed allocation timeout (allocation_timeout={:?})", value);
+                        log::error!("{}", message);
+                        return build_response(StatusCode::BAD_REQUEST, vec![message]);
+                    },
+                },
+                None => None,
+            };
+
             // Pre-append the environment variable prefix to each key in the parameters.
             let mut env: HashMap<String, String> = HashMap::new();
             for (key, value) in parameters {
@@ -126,9 +364,46 @@ fn run_job(
             }
 
             let job_path: String = format!("{}/{}", job_home, job_name);
-            match Job::new(&job_path, env) {
-                Ok(job) => scheduler.run(job),
-                Err(e) => Err(e),
+            let mut jobs: Vec<Job> = Job::load(&job_path, env)?;
+            for job in &mut jobs {
+                if let Some(priority) = priority {
+                    job.set_priority(priority);
+                }
+                if let Some(allocation_timeout) = allocation_timeout {
+                    job.set_allocation_timeout(allocation_timeout);
+                }
+            }
+            if dry_run {
+                let mut lines: Vec<String> = Vec::new();
+                for job in jobs {
+                    let name: String = format!("{}{}", job_name, job.variant());
+                    match scheduler.plan(&name, job) {
+                        Ok(plan) => lines.extend(plan.describe()),
+                        Err(e) => return build_response(StatusCode::BAD_REQUEST, vec![e.to_string()]),
+                    }
+                }
+                build_response(StatusCode::OK, lines)
+            } else if stream {
+                // All job instances share the same stream.
+                let (sink, receiver): (Sender<String>, Receiver<String>) = mpsc::channel();
+                scheduler.submit(name_jobs(&job_name, jobs), Some(sink))?;
+                let response: Response<HttpBody> = Response::builder()
+                    .version(Version::HTTP_11)
+                    .status(StatusCode::OK)
+                    .header("Content-Type", "text/plain")
+                    .body(HttpBody::Stream(receiver))?;
+                Ok(response)
+            } else if let Some(format) = format {
+                let job_ids: Vec<JobId> = scheduler.submit(name_jobs(&job_name, jobs), None)?;
+                // Block until all job instances finish, so that the report covers all of them.
+                let mut records: Vec<JobRecord> = Vec::new();
+                for job_id in job_ids {
+                    records.push(scheduler.wait(job_id)?);
+                }
+                build_report_response(format, format.render(&records)?)
+            } else {
+                let lines: Vec<String> = submit_jobs(&scheduler, name_jobs(&job_name, jobs))?;
+                build_response(StatusCode::ACCEPTED, lines)
             }
         },
         None => {
@@ -139,6 +414,372 @@ fn run_job(
     }
 }
 
+/// Handles `/webhook/github`. Requests must be signed with the secret of the webhook, and events that match no rule
+/// are acknowledged without running any job.
+fn handle_github_webhook(
+    env_var_prefix: String,
+    job_home: String,
+    scheduler: Arc<Scheduler>,
+    github_webhook: Option<Arc<GithubWebhook>>,
+    request: Request<Vec<u8>>,
+) -> Result<Response<HttpBody>> {
+    log::trace!(
+        "handle_github_webhook(): method={}, uri={}",
+        request.method(),
+        request.uri()
+    );
+    let github_webhook: Arc<GithubWebhook> = match github_webhook {
+        Some(github_webhook) => github_webhook,
+        None => return build_response(StatusCode::NOT_FOUND, vec!["github webhook is not enabled".to_string()]),
+    };
+    if request.method() != Method::POST {
+        let message: String = format!("unsupported method (method={})", request.method());
+        log::error!("{}", message);
+        return build_response(StatusCode::METHOD_NOT_ALLOWED, vec![message]);
+    }
+
+    let signature: &str = match request.headers().get(GITHUB_SIGNATURE_HEADER) {
+        Some(value) => value.to_str().unwrap_or(""),
+        None => "",
+    };
+    if !github_webhook.verify(request.body(), signature) {
+        let message: String = "invalid webhook signature".to_string();
+        log::error!("{}", message);
+        return build_response(StatusCode::UNAUTHORIZED, vec![message]);
+    }
+
+    let event_name: &str = match request.headers().get(GITHUB_EVENT_HEADER) {
+        Some(value) => value.to_str().unwrap_or(""),
+        None => "",
+    };
+    let event: WebhookEvent = match github_webhook.parse_event(event_name, request.body()) {
+        Ok(Some(event)) => event,
+        Ok(None) => {
+            log::info!("ignoring github event (event={:?})", event_name);
+            return build_response(StatusCode::OK, vec![format!("ignored event (event={:?})", event_name)]);
+        },
+        Err(e) => return build_response(StatusCode::BAD_REQUEST, vec![e.to_string()]),
+    };
+
+    // Pre-append the environment variable prefix to each key in the parameters.
+    let mut env: HashMap<String, String> = HashMap::new();
+    for (key, value) in event.parameters() {
+        env.insert(format!("{}{}", env_var_prefix, key), value);
+    }
+
+    // Load all matching jobs before submitting any, so that a failed delivery, which GitHub retries, runs nothing.
+    let mut jobs: Vec<(String, Job)> = Vec::new();
+    for job_name in github_webhook.jobs(&event) {
+        let job_path: String = format!("{}/{}", job_home, job_name);
+        jobs.extend(name_jobs(job_name, Job::load(&job_path, env.clone())?));
+    }
+    if jobs.is_empty() {
+        log::info!("no rule matches github event (event={:?})", event);
+        return build_response(StatusCode::OK, vec!["no matching rule".to_string()]);
+    }
+
+    build_response(StatusCode::ACCEPTED, submit_jobs(&scheduler, jobs)?)
+}
+
+/// Handles `/jobs`, which lists jobs that are queued or running. Finished jobs are listed by `/history`.
+fn list_jobs(scheduler: Arc<Scheduler>) -> Result<Response<HttpBody>> {
+    let lines: Vec<String> = scheduler
+        .registry()
+        .list()?
+        .iter()
+        .flat_map(|record| describe_job(&scheduler, record, false))
+        .collect();
+    build_response(StatusCode::OK, lines)
+}
+
+/// Handles `/jobs/<id>`, `/jobs/<id>/report?format=<format>`, `/jobs/<id>/artifacts`,
+/// `/jobs/<id>/artifacts/<path>`, and `/jobs/<id>/cancel`. Cancelling a job changes its state, so it must be
+/// requested with `POST`.
+fn route_job(scheduler: Arc<Scheduler>, path: &str, request: &Request<Vec<u8>>) -> Result<Response<HttpBody>> {
+    log::trace!("route_job(): path={}", path);
+    let query: Option<&str> = request.uri().query();
+    let (job_id, resource): (&str, Option<&str>) = match path.split_once('/') {
+        Some((job_id, resource)) => (job_id, Some(resource)),
+        None => (path, None),
+    };
+    let job_id: JobId = match job_id.parse::<JobId>() {
+        Ok(job_id) => job_id,
+        Err(_) => {
+            let message: String = format!("malformed job id (job_id={:?})", job_id);
+            log::error!("{}", message);
+            return build_response(StatusCode::BAD_REQUEST, vec![message]);
+        },
+    };
+    // Jobs that ran before a restart are only found in the history.
+    let record: JobRecord = match scheduler.registry().get(job_id)? {
+        Some(record) => record,
+        None => match scheduler.history().get(job_id)? {
+            Some(record) => record,
+            None => return build_response(StatusCode::NOT_FOUND, vec![format!("no such job (id={})", job_id)]),
+        },
+    };
+
+    match resource {
+        None => build_response(StatusCode::OK, describe_job(&scheduler, &record, true)),
+        Some("report") => get_report(scheduler, &record, query),
+        Some("artifacts") => list_artifacts(scheduler, job_id),
+        Some("cancel") if request.method() != Method::POST => method_not_allowed(request.method()),
+        Some("cancel") => cancel_job(scheduler, &record),
+        Some(resource) if resource.starts_with("artifacts/") => {
+            get_artifact(scheduler, job_id, &resource["artifacts/".len()..])
+        },
+        Some(resource) => build_response(
+            StatusCode::NOT_FOUND,
+            vec![format!("no such resource (resource={:?})", resource)],
+        ),
+    }
+}
+
+/// Handles `/history?job=<name>&state=<state>&since=<time>&until=<time>&limit=<count>`, where times are expressed in
+/// seconds since the Unix epoch. All parameters are optional.
+fn query_history(scheduler: Arc<Scheduler>, request: Request<Vec<u8>>) -> Result<Response<HttpBody>> {
+    log::trace!("query_history(): uri={}", request.uri());
+    let parameters: HashMap<String, String> = parse_job_parameters(request.uri().query().unwrap_or(""));
+
+    let filter: HistoryFilter = match parse_history_filter(&parameters) {
+        Ok(filter) => filter,
+        Err(e) => {
+            let message: String = format!("malformed history query (e={})", e);
+            log::error!("{}", message);
+            return build_response(StatusCode::BAD_REQUEST, vec![message]);
+        },
+    };
+
+    let lines: Vec<String> = scheduler
+        .history()
+        .query(&filter)?
+        .iter()
+        .map(|record| record.summary())
+        .collect();
+    build_response(StatusCode::OK, lines)
+}
+
+fn parse_history_filter(parameters: &HashMap<String, String>) -> Result<HistoryFilter> {
+    let mut filter: HistoryFilter = HistoryFilter {
+        name: parameters.get("JOB").cloned(),
+        ..Default::default()
+    };
+    if let Some(state) = parameters.get("STATE") {
+        filter.state = Some(state.parse::<JobState>()?);
+    }
+    if let Some(since) = parameters.get("SINCE") {
+        filter.since = Some(UNIX_EPOCH + Duration::from_secs(since.parse::<u64>()?));
+    }
+    if let Some(until) = parameters.get("UNTIL") {
+        filter.until = Some(UNIX_EPOCH + Duration::from_secs(until.parse::<u64>()?));
+    }
+    if let Some(limit) = parameters.get("LIMIT") {
+        filter.limit = Some(limit.parse::<usize>()?);
+    }
+    Ok(filter)
+}
+
+fn list_artifacts(scheduler: Arc<Scheduler>, job_id: JobId) -> Result<Response<HttpBody>> {
+    let lines: Vec<String> = scheduler
+        .artifacts()
+        .list(job_id)?
+        .iter()
+        .map(|(path, size)| format!("path={} size={}", path, size))
+        .collect();
+    build_response(StatusCode::OK, lines)
+}
+
+fn get_artifact(scheduler: Arc<Scheduler>, job_id: JobId, path: &str) -> Result<Response<HttpBody>> {
+    let (file, length): (File, u64) = match scheduler.artifacts().open(job_id, path) {
+        Ok(Some(artifact)) => artifact,
+        Ok(None) => {
+            return build_response(
+                StatusCode::NOT_FOUND,
+                vec![format!("no such artifact (path={:?})", path)],
+            )
+        },
+        Err(e) => return build_response(StatusCode::BAD_REQUEST, vec![e.to_string()]),
+    };
+
+    let response: Response<HttpBody> = Response::builder()
+        .version(Version::HTTP_11)
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/octet-stream")
+        .body(HttpBody::File(file, length))?;
+    Ok(response)
+}
+
+/// Returns the report of a job. Reports of finished jobs are read from the history, if available.
+fn get_report(scheduler: Arc<Scheduler>, record: &JobRecord, query: Option<&str>) -> Result<Response<HttpBody>> {
+    let parameters: HashMap<String, String> = parse_job_parameters(query.unwrap_or(""));
+    let format: ReportFormat = match parameters.get(FORMAT_PARAMETER) {
+        Some(value) => match value.parse::<ReportFormat>() {
+            Ok(format) => format,
+            Err(e) => {
+                let message: String = format!("malformed report format (e={})", e);
+                log::error!("{}", message);
+                return build_response(StatusCode::BAD_REQUEST, vec![message]);
+            },
+        },
+        None => ReportFormat::Json,
+    };
+
+    let report: String = match scheduler.history().report(record.id(), format)? {
+        Some(report) => report,
+        None => format.render(std::slice::from_ref(record))?,
+    };
+    build_report_response(format, report)
+}
+
+fn cancel_job(scheduler: Arc<Scheduler>, record: &JobRecord) -> Result<Response<HttpBody>> {
+    log::trace!("cancel_job(): id={}", record.id());
+    if scheduler.cancel(record.id())? {
+        build_response(
+            StatusCode::ACCEPTED,
+            vec![format!("id={} cancelling=true", record.id())],
+        )
+    } else {
+        build_response(
+            StatusCode::CONFLICT,
+            vec![format!(
+                "job has already finished (id={}, state={})",
+                record.id(),
+                record.state()
+            )],
+        )
+    }
+}
+
+fn list_runners(scheduler: Arc<Scheduler>) -> Result<Response<HttpBody>> {
+    let lines: Vec<String> = scheduler
+        .runner_states()?
+        .iter()
+        .map(|(id, state, busy)| format!("id={} state={} busy={}", id, state, busy))
+        .collect();
+    build_response(StatusCode::OK, lines)
+}
+
+/// Handles `/runners/<id>/drain` and `/runners/<id>/resume`, which must be requested with `POST`.
+fn drain_runner(scheduler: Arc<Scheduler>, path: &str, request: &Request<Vec<u8>>) -> Result<Response<HttpBody>> {
+    log::trace!("drain_runner(): path={}", path);
+    if request.method() != Method::POST {
+        return method_not_allowed(request.method());
+    }
+    let (runner_id, draining): (Option<usize>, bool) = match path.split_once('/') {
+        Some((runner_id, "drain")) => (runner_id.parse::<usize>().ok(), true),
+        Some((runner_id, "resume")) => (runner_id.parse::<usize>().ok(), false),
+        _ => (None, false),
+    };
+    let runner_id: usize = match runner_id {
+        Some(runner_id) => runner_id,
+        None => {
+            let message: String = format!("malformed runner request (path={:?})", path);
+            log::error!("{}", message);
+            return build_response(StatusCode::BAD_REQUEST, vec![message]);
+        },
+    };
+
+    if scheduler.set_draining(runner_id, draining)? {
+        build_response(StatusCode::OK, vec![format!("id={} draining={}", runner_id, draining)])
+    } else {
+        build_response(
+            StatusCode::NOT_FOUND,
+            vec![format!("no such runner (id={})", runner_id)],
+        )
+    }
+}
+
+/// Describes a job, including its position in the queue of jobs that are waiting for runners.
+fn describe_job(scheduler: &Scheduler, record: &JobRecord, detailed: bool) -> Vec<String> {
+    let mut lines: Vec<String> = if detailed {
+        record.details()
+    } else {
+        vec![record.summary()]
+    };
+    if let Some(position) = scheduler.queue_position(record.id()) {
+        lines[0].push_str(&format!(" queue_position={}", position));
+    }
+    lines
+}
+
+/// Rejects a request whose method is not supported by its endpoint.
+fn method_not_allowed(method: &Method) -> Result<Response<HttpBody>> {
+    let message: String = format!("unsupported method (method={})", method);
+    log::error!("{}", message);
+    build_response(StatusCode::METHOD_NOT_ALLOWED, vec![message])
+}
+
+/// Names instances of a job after the job file and their matrix values.
+fn name_jobs(job_name: &str, jobs: Vec<Job>) -> Vec<(String, Job)> {
+    jobs.into_iter()
+        .map(|job| (format!("{}{}", job_name, job.variant()), job))
+        .collect()
+}
+
+/// Submits jobs all at once, and describes each of them in a line.
+fn submit_jobs(scheduler: &Arc<Scheduler>, jobs: Vec<(String, Job)>) -> Result<Vec<String>> {
+    let names: Vec<String> = jobs.iter().map(|(name, _)| name.clone()).collect();
+    let job_ids: Vec<JobId> = scheduler.submit(jobs, None)?;
+    Ok(job_ids
+        .iter()
+        .zip(names)
+        .map(|(job_id, name)| format!("id={} name={}", job_id, name))
+        .collect())
+}
+
+fn build_response(status: StatusCode, lines: Vec<String>) -> Result<Response<HttpBody>> {
+    let response: Response<HttpBody> = Response::builder()
+        .version(Version::HTTP_11)
+        .status(status)
+        .header("Content-Type", "text/plain")
+        .body(HttpBody::Lines(lines))?;
+
+    Ok(response)
+}
+
+fn build_report_response(format: ReportFormat, report: String) -> Result<Response<HttpBody>> {
+    let response: Response<HttpBody> = Response::builder()
+        .version(Version::HTTP_11)
+        .status(StatusCode::OK)
+        .header("Content-Type", format.content_type())
+        .body(HttpBody::Bytes(report.into_bytes()))?;
+
+    Ok(response)
+}
+
+/// Collects the parameters of a job submission. These come from the query and, for `POST` requests, from a JSON
+/// object in the body, whose values take precedence. Returns `None` if the request has neither.
+fn parse_run_parameters(request: &Request<Vec<u8>>) -> Result<Option<HashMap<String, String>>> {
+    let mut parameters: Option<HashMap<String, String>> = request.uri().query().map(parse_job_parameters);
+
+    if request.method() == Method::POST && !request.body().is_empty() {
+        let content_type: &str = match request.headers().get(CONTENT_TYPE) {
+            Some(value) => value.to_str().unwrap_or(""),
+            None => "",
+        };
+        if !content_type.starts_with("application/json") {
+            anyhow::bail!("unsupported content type (content_type={:?})", content_type);
+        }
+
+        let object: serde_json::Map<String, serde_json::Value> = match serde_json::from_slice(request.body()) {
+            Ok(object) => object,
+            Err(e) => anyhow::bail!("malformed json body (e={})", e),
+        };
+        let parameters: &mut HashMap<String, String> = parameters.get_or_insert_with(HashMap::new);
+        for (key, value) in object {
+            let value: String = match value {
+                serde_json::Value::String(value) => value,
+                serde_json::Value::Number(value) => value.to_string(),
+                serde_json::Value::Bool(value) => value.to_string(),
+                _ => anyhow::bail!("unsupported parameter value (key={:?}, value={})", key, value),
+            };
+            parameters.insert(key.to_uppercase(), value);
+        }
+    }
+
+    Ok(parameters)
+}
+
 fn parse_job_parameters(query: &str) -> HashMap<String, String> {
     // Create an empty vector to store the results
     let mut result: HashMap<String, String> = HashMap::new();
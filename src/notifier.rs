@@ -0,0 +1,175 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{config::Config, registry::JobId};
+use anyhow::Result;
+use serde::Serialize;
+use std::{collections::HashMap, time::Duration};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Reporter of commit statuses. Statuses are posted to a GitHub-compatible REST API, which may be GitHub itself or any
+/// server that implements the `POST /repos/<owner>/<repo>/statuses/<sha>` endpoint (e.g. a local mock server).
+pub struct Notifier {
+    /// Base URL of the REST API (e.g. `https://api.github.com`).
+    api_url: String,
+    /// Token used to authenticate against the REST API, if any.
+    token: Option<String>,
+    /// Label that distinguishes statuses of this orchestrator from those of other services.
+    context: String,
+    /// Base URL under which the orchestrator is reachable, used to link statuses to job status pages.
+    public_url: String,
+    agent: ureq::Agent,
+}
+
+/// Commit that a job tests.
+#[derive(Debug, Clone)]
+pub struct CommitTarget {
+    /// Full name of the repository (e.g. `microsoft/demikernel`).
+    repository: String,
+    /// Hash of the commit.
+    sha: String,
+}
+
+/// State of a commit status, as understood by GitHub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitState {
+    Pending,
+    Success,
+    Failure,
+    Error,
+}
+
+/// Body of a commit status request.
+#[derive(Serialize)]
+struct StatusPayload<'a> {
+    state: &'static str,
+    target_url: String,
+    description: String,
+    context: &'a str,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Notifier {
+    /// Maximum amount of time that a request to the REST API may take.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Maximum length of the description of a commit status, as enforced by GitHub.
+    const DESCRIPTION_MAX: usize = 140;
+
+    /// Instantiates a new [Notifier].
+    pub fn new(api_url: &str, token: Option<&str>, context: &str, public_url: &str) -> Self {
+        Self {
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token: token.map(|token| token.to_string()),
+            context: context.to_string(),
+            public_url: public_url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(Self::REQUEST_TIMEOUT).build(),
+        }
+    }
+
+    /// Returns the commit that a job tests, given its environment. Only jobs that were triggered with both a
+    /// repository and a commit hash have one. Both are supplied by whoever submitted the job and end up in the URL of
+    /// an authenticated request, so malformed ones are ignored.
+    pub fn target(env: &HashMap<String, String>) -> Option<CommitTarget> {
+        let prefix: String = Config::env_var_prefix();
+        match (
+            env.get(&format!("{}REPOSITORY", prefix)),
+            env.get(&format!("{}SHA", prefix)),
+        ) {
+            (Some(repository), Some(sha)) if !repository.is_empty() && !sha.is_empty() => {
+                if !Self::is_repository(repository) || !Self::is_sha(sha) {
+                    log::warn!("ignoring malformed commit (repository={:?}, sha={:?})", repository, sha);
+                    return None;
+                }
+                Some(CommitTarget {
+                    repository: repository.to_string(),
+                    sha: sha.to_string(),
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// Checks if a string is the full name of a repository, that is, `<owner>/<name>`.
+    fn is_repository(repository: &str) -> bool {
+        let is_name = |name: &str| {
+            !name.is_empty()
+                && name.chars().any(|c| c != '.')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-')
+        };
+        match repository.split_once('/') {
+            Some((owner, name)) => is_name(owner) && is_name(name),
+            None => false,
+        }
+    }
+
+    /// Checks if a string is the full hash of a commit.
+    fn is_sha(sha: &str) -> bool {
+        sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// Posts the status of a job to the commit that it tests.
+    pub fn notify(&self, target: &CommitTarget, job_id: JobId, state: CommitState, description: &str) -> Result<()> {
+        log::trace!(
+            "notify(): target={:?}, job_id={:?}, state={:?}, description={:?}",
+            target,
+            job_id,
+            state,
+            description
+        );
+        let url: String = format!("{}/repos/{}/statuses/{}", self.api_url, target.repository, target.sha);
+        let payload: StatusPayload = StatusPayload {
+            state: state.name(),
+            target_url: format!("{}/jobs/{}", self.public_url, job_id),
+            description: description.chars().take(Self::DESCRIPTION_MAX).collect(),
+            context: &self.context,
+        };
+
+        let mut request: ureq::Request = self
+            .agent
+            .post(&url)
+            .set("Accept", "application/vnd.github+json")
+            .set("Content-Type", "application/json");
+        if let Some(token) = &self.token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        if let Err(e) = request.send_string(&serde_json::to_string(&payload)?) {
+            let msg: String = format!("failed to post commit status (url={:?}, e={})", url, e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        log::info!(
+            "posted commit status (job_id={:?}, repository={:?}, sha={:?}, state={})",
+            job_id,
+            target.repository,
+            target.sha,
+            state.name()
+        );
+        Ok(())
+    }
+}
+
+impl CommitState {
+    /// Returns the name of the target [CommitState].
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommitState::Pending => "pending",
+            CommitState::Success => "success",
+            CommitState::Failure => "failure",
+            CommitState::Error => "error",
+        }
+    }
+}
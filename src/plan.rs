@@ -0,0 +1,180 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    job::Job,
+    secrets::SecretMask,
+    task::{Task, TaskQueue},
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Runner that a worker would be placed on.
+#[derive(Debug, Clone)]
+pub struct RunnerAssignment {
+    worker: String,
+    runner_id: usize,
+    addr: String,
+    local_addr: String,
+}
+
+/// What would happen if a job ran: the tasks of each worker and the runners on which they would run, along with the
+/// environment that commands would receive. Building a [JobPlan] runs nothing.
+pub struct JobPlan {
+    name: String,
+    priority: i64,
+    allocation_timeout: Option<Duration>,
+    /// Are enough runners idle for the job to start right away?
+    ready: bool,
+    /// Runner on which each worker would run, ordered by worker name.
+    assignments: Vec<RunnerAssignment>,
+    /// Tasks of each worker, in the order in which they would run, ordered by worker name.
+    queues: Vec<(String, TaskQueue)>,
+    barrier_participants: Vec<usize>,
+    /// Environment of commands, with the values of secrets redacted.
+    env: BTreeMap<String, String>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl RunnerAssignment {
+    pub fn new(worker: &str, runner_id: usize, addr: &str, local_addr: &str) -> Self {
+        Self {
+            worker: worker.to_string(),
+            runner_id,
+            addr: addr.to_string(),
+            local_addr: local_addr.to_string(),
+        }
+    }
+}
+
+impl JobPlan {
+    /// Lays out a job whose secrets have been resolved, given the runners that would be assigned to its workers.
+    pub fn new(name: &str, mut job: Job, mut assignments: Vec<RunnerAssignment>, ready: bool) -> Self {
+        assignments.sort_by(|a, b| a.worker.cmp(&b.worker));
+
+        // Commands receive the parameters of the job, the addresses of all workers, and the secrets of the job.
+        let mask: SecretMask = SecretMask::new(job.secrets().values());
+        let mut env: HashMap<String, String> = job.env().clone();
+        for assignment in &assignments {
+            env.insert(Job::worker_addr_var(&assignment.worker), assignment.local_addr.clone());
+        }
+        env.extend(job.secrets().clone());
+        let env: BTreeMap<String, String> = env
+            .into_iter()
+            .filter(|(key, _)| key.to_lowercase() != "job")
+            .map(|(key, value)| {
+                let value: String = mask.apply(&value);
+                (key, value)
+            })
+            .collect();
+
+        let mut queues: Vec<(String, TaskQueue)> = Vec::new();
+        for assignment in &assignments {
+            if let Some(tasks) = job.get_worker_tasks(&assignment.worker) {
+                queues.push((assignment.worker.clone(), tasks));
+            }
+        }
+
+        Self {
+            name: name.to_string(),
+            priority: job.priority(),
+            allocation_timeout: job.allocation_timeout(),
+            ready,
+            assignments,
+            queues,
+            barrier_participants: job.barrier_participants().clone(),
+            env,
+        }
+    }
+
+    /// Describes the target [JobPlan], one line at a time. Lines of each task are prefixed with the worker and the
+    /// action that they belong to, like the output of the job would be.
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines: Vec<String> = vec![format!(
+            "[plan] name={} workers={} barriers={} priority={} allocation_timeout={:?} ready={}",
+            self.name,
+            self.assignments.len(),
+            self.barrier_participants.len(),
+            self.priority,
+            self.allocation_timeout,
+            self.ready
+        )];
+
+        for assignment in &self.assignments {
+            lines.push(format!(
+                "[plan][worker] name={} runner={} addr={} local_addr={}",
+                assignment.worker, assignment.runner_id, assignment.addr, assignment.local_addr
+            ));
+        }
+
+        // A barrier is released once all workers that take part in it have reached it.
+        for (index, participants) in self.barrier_participants.iter().enumerate() {
+            let workers: Vec<&str> = self
+                .queues
+                .iter()
+                .filter(|(_, tasks)| {
+                    tasks
+                        .tasks()
+                        .iter()
+                        .any(|task| matches!(task, Task::Barrier(i) if *i == index))
+                })
+                .map(|(worker, _)| worker.as_str())
+                .collect();
+            lines.push(format!(
+                "[plan][barrier] index={} participants={} workers={:?}",
+                index, participants, workers
+            ));
+        }
+
+        for (worker, tasks) in &self.queues {
+            for task in tasks.tasks() {
+                match task {
+                    Task::Action(action) => {
+                        let prefix: String = format!("[{}][{}]", worker, action.name());
+                        // File transfers do not run commands, so they have no environment.
+                        match action.transfer() {
+                            Some(transfer) => lines.push(format!(
+                                "{} {} source={} destination={} timeout={:?} needs={:?}",
+                                prefix,
+                                transfer.direction(),
+                                transfer.source(),
+                                transfer.destination(),
+                                action.timeout(),
+                                action.needs()
+                            )),
+                            None => {
+                                lines.push(format!(
+                                    "{} commands={:?} timeout={:?} needs={:?} artifacts={:?}",
+                                    prefix,
+                                    action.commands(),
+                                    action.timeout(),
+                                    action.needs(),
+                                    action.artifacts()
+                                ));
+                                for (key, value) in &self.env {
+                                    lines.push(format!("{}[env] {}={}", prefix, key, value));
+                                }
+                            },
+                        }
+                    },
+                    Task::Barrier(index) => lines.push(format!("[{}][barrier] index={}", worker, index)),
+                }
+            }
+        }
+
+        lines
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::action::Action;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Identifier of a job.
+pub type JobId = usize;
+
+/// State of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+/// Record of a submitted job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    /// Identifier of the job.
+    id: JobId,
+    /// Name of the job.
+    name: String,
+    /// Parameters with which the job was submitted.
+    parameters: BTreeMap<String, String>,
+    /// Addresses of the runners that were assigned to each worker of the job.
+    runners: BTreeMap<String, String>,
+    /// State of the job.
+    state: JobState,
+    /// Time at which the job was submitted.
+    submitted_at: SystemTime,
+    /// Time at which the job started running.
+    started_at: Option<SystemTime>,
+    /// Time at which the job finished.
+    finished_at: Option<SystemTime>,
+    /// Actions of the job, once it has finished.
+    actions: Vec<Action>,
+    /// Error that prevented the job from running, if any.
+    error: Option<String>,
+}
+
+/// Registry of submitted jobs. Jobs are kept until they finish and are persisted, after which they are forgotten.
+pub struct JobRegistry {
+    /// Identifier of the next job.
+    next_id: Mutex<JobId>,
+    /// Records of jobs that have not been forgotten yet.
+    jobs: Mutex<BTreeMap<JobId, JobRecord>>,
+    /// Signaled whenever a job finishes.
+    finished: Condvar,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl JobRecord {
+    /// Returns the identifier of the target [JobRecord].
+    pub fn id(&self) -> JobId {
+        self.id
+    }
+
+    /// Returns the name of the target [JobRecord].
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the state of the target [JobRecord].
+    pub fn state(&self) -> JobState {
+        self.state
+    }
+
+    /// Returns the time at which the job of the target [JobRecord] was submitted.
+    pub fn submitted_at(&self) -> SystemTime {
+        self.submitted_at
+    }
+
+    /// Returns the error that prevented the job of the target [JobRecord] from running, if any.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Returns the actions that were run by the job of the target [JobRecord].
+    pub fn actions(&self) -> &Vec<Action> {
+        &self.actions
+    }
+
+    /// Checks if the job of the target [JobRecord] has finished.
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, JobState::Succeeded | JobState::Failed | JobState::Cancelled)
+    }
+
+    /// Returns a one-line summary of the target [JobRecord].
+    pub fn summary(&self) -> String {
+        format!(
+            "id={} name={} state={} submitted_at={} started_at={} finished_at={}",
+            self.id,
+            self.name,
+            self.state,
+            format_time(Some(self.submitted_at)),
+            format_time(self.started_at),
+            format_time(self.finished_at),
+        )
+    }
+
+    /// Returns a detailed description of the target [JobRecord], including per-action results.
+    pub fn details(&self) -> Vec<String> {
+        let mut lines: Vec<String> = vec![self.summary()];
+
+        if let Some(error) = &self.error {
+            lines.push(format!("error={}", error));
+        }
+
+        for (key, value) in &self.parameters {
+            lines.push(format!("[parameter] {}={}", key, value));
+        }
+
+        for (worker, addr) in &self.runners {
+            lines.push(format!("[runner] {}={}", worker, addr));
+        }
+
+        for action in &self.actions {
+            let exit_status: String = match action.exit_status() {
+                Some(exit_status) => format!(
+                    "code={} signal={:?} timed_out={}",
+                    exit_status.code(),
+                    exit_status.signal(),
+                    exit_status.timed_out()
+                ),
+                None => "code=none".to_string(),
+            };
+            lines.push(format!(
+                "[{}][{}] started_at={} duration_ms={} {}",
+                action.runs_on(),
+                action.name(),
+                format_time(action.started_at()),
+                action
+                    .duration()
+                    .map_or("none".to_string(), |d| d.as_millis().to_string()),
+                exit_status
+            ));
+        }
+
+        for action in &self.actions {
+            if let Some(output) = action.output() {
+                lines.extend(output.iter().cloned());
+            }
+        }
+
+        lines
+    }
+}
+
+impl JobRegistry {
+    /// Instantiates a new, empty, [JobRegistry] that hands out identifiers starting from `first_id`.
+    pub fn new(first_id: JobId) -> Self {
+        Self {
+            next_id: Mutex::new(first_id),
+            jobs: Mutex::new(BTreeMap::new()),
+            finished: Condvar::new(),
+        }
+    }
+
+    /// Registers a new job in the queued state and returns its identifier.
+    pub fn register(&self, name: &str, parameters: &HashMap<String, String>) -> Result<JobId> {
+        let id: JobId = match self.next_id.lock() {
+            Ok(mut next_id) => {
+                let id: JobId = *next_id;
+                *next_id += 1;
+                id
+            },
+            Err(e) => {
+                let msg: String = format!("failed to lock job identifier (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let record: JobRecord = JobRecord {
+            id,
+            name: name.to_string(),
+            parameters: parameters.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            runners: BTreeMap::new(),
+            state: JobState::Queued,
+            submitted_at: SystemTime::now(),
+            started_at: None,
+            finished_at: None,
+            actions: Vec::new(),
+            error: None,
+        };
+        self.lock()?.insert(id, record);
+
+        Ok(id)
+    }
+
+    /// Marks a job as running, recording the addresses of the runners that were assigned to each of its workers.
+    pub fn set_running(&self, id: JobId, runners: BTreeMap<String, String>) -> Result<()> {
+        self.update(id, |record| {
+            record.runners = runners;
+            record.state = JobState::Running;
+            record.started_at = Some(SystemTime::now());
+        })
+    }
+
+    /// Marks a job as finished, recording the final state and the actions that were run.
+    pub fn set_finished(&self, id: JobId, state: JobState, actions: Vec<Action>, error: Option<String>) -> Result<()> {
+        self.update(id, |record| {
+            record.state = state;
+            record.finished_at = Some(SystemTime::now());
+            record.actions = actions;
+            record.error = error;
+        })?;
+        self.finished.notify_all();
+        Ok(())
+    }
+
+    /// Blocks until a job finishes and returns its record. Returns `None` if the job finished and was forgotten, in
+    /// which case its record is only found in the history.
+    pub fn wait(&self, id: JobId) -> Result<Option<JobRecord>> {
+        let mut jobs: MutexGuard<'_, BTreeMap<JobId, JobRecord>> = self.lock()?;
+        loop {
+            match jobs.get(&id) {
+                Some(record) if record.is_finished() => return Ok(Some(record.clone())),
+                Some(_) => {},
+                None if self.was_registered(id)? => return Ok(None),
+                None => {
+                    let msg: String = format!("no such job (id={:?})", id);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            }
+
+            jobs = match self.finished.wait(jobs) {
+                Ok(jobs) => jobs,
+                Err(e) => {
+                    let msg: String = format!("failed to wait for job (id={:?}, e={:?})", id, e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+        }
+    }
+
+    /// Forgets a job that has finished, so that its record does not stay in memory once it has been persisted.
+    pub fn forget(&self, id: JobId) -> Result<()> {
+        let mut jobs: MutexGuard<'_, BTreeMap<JobId, JobRecord>> = self.lock()?;
+        match jobs.get(&id) {
+            Some(record) if record.is_finished() => {
+                jobs.remove(&id);
+                Ok(())
+            },
+            Some(record) => {
+                let msg: String = format!("job has not finished (id={:?}, state={})", id, record.state);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Retrieves a snapshot of a job record.
+    pub fn get(&self, id: JobId) -> Result<Option<JobRecord>> {
+        Ok(self.lock()?.get(&id).cloned())
+    }
+
+    /// Retrieves a snapshot of all job records, ordered by identifier.
+    pub fn list(&self) -> Result<Vec<JobRecord>> {
+        Ok(self.lock()?.values().cloned().collect())
+    }
+
+    /// Checks if a job was registered at some point, whether or not it is still in the target [JobRegistry].
+    fn was_registered(&self, id: JobId) -> Result<bool> {
+        match self.next_id.lock() {
+            Ok(next_id) => Ok(id < *next_id),
+            Err(e) => {
+                let msg: String = format!("failed to lock job identifier (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    fn update<F: FnOnce(&mut JobRecord)>(&self, id: JobId, f: F) -> Result<()> {
+        match self.lock()?.get_mut(&id) {
+            Some(record) => {
+                f(record);
+                Ok(())
+            },
+            None => {
+                let msg: String = format!("no such job (id={:?})", id);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, BTreeMap<JobId, JobRecord>>> {
+        match self.jobs.lock() {
+            Ok(jobs) => Ok(jobs),
+            Err(e) => {
+                let msg: String = format!("failed to lock job registry (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl fmt::Display for JobState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: &str = match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Succeeded => "succeeded",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl FromStr for JobState {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "queued" => Ok(JobState::Queued),
+            "running" => Ok(JobState::Running),
+            "succeeded" => Ok(JobState::Succeeded),
+            "failed" => Ok(JobState::Failed),
+            "cancelled" => Ok(JobState::Cancelled),
+            _ => anyhow::bail!("unknown job state (state={:?})", s),
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Formats a point in time as seconds since the Unix epoch.
+fn format_time(time: Option<SystemTime>) -> String {
+    match time.map(|time| time.duration_since(UNIX_EPOCH)) {
+        Some(Ok(duration)) => duration.as_secs().to_string(),
+        _ => "none".to_string(),
+    }
+}
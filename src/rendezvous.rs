@@ -0,0 +1,109 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use anyhow::Result;
+use std::sync::{Condvar, Mutex, MutexGuard};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// A single-use barrier that may be cancelled.
+///
+/// Unlike [std::sync::Barrier], participants that are waiting on a [Rendezvous] are woken up with an error when it is
+/// cancelled, so that a failure in one participant does not leave the others blocked forever.
+pub struct Rendezvous {
+    /// Number of participants.
+    participants: usize,
+    /// State of the rendezvous.
+    state: Mutex<RendezvousState>,
+    /// Condition variable on which participants wait.
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct RendezvousState {
+    /// Number of participants that have arrived.
+    arrived: usize,
+    /// Have all participants arrived?
+    released: bool,
+    /// Has the rendezvous been cancelled?
+    cancelled: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Rendezvous {
+    /// Instantiates a new [Rendezvous] for a given number of participants.
+    pub fn new(participants: usize) -> Self {
+        Self {
+            participants,
+            state: Mutex::new(RendezvousState::default()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    /// Blocks until all participants have arrived. Fails if the target [Rendezvous] is cancelled.
+    pub fn wait(&self) -> Result<()> {
+        let mut state: MutexGuard<'_, RendezvousState> = self.lock()?;
+
+        if state.cancelled {
+            anyhow::bail!("rendezvous was cancelled");
+        }
+
+        state.arrived += 1;
+        if state.arrived >= self.participants {
+            state.released = true;
+            self.condvar.notify_all();
+            return Ok(());
+        }
+
+        while !state.released && !state.cancelled {
+            state = match self.condvar.wait(state) {
+                Ok(state) => state,
+                Err(e) => {
+                    let msg: String = format!("failed to wait on rendezvous (e={:?})", e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+        }
+
+        if state.released {
+            Ok(())
+        } else {
+            anyhow::bail!("rendezvous was cancelled")
+        }
+    }
+
+    /// Cancels the target [Rendezvous], waking up all participants that are waiting on it. This has no effect if all
+    /// participants have already arrived.
+    pub fn cancel(&self) {
+        match self.lock() {
+            Ok(mut state) => {
+                if !state.released {
+                    state.cancelled = true;
+                    self.condvar.notify_all();
+                }
+            },
+            Err(e) => log::warn!("failed to cancel rendezvous (e={:?})", e),
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, RendezvousState>> {
+        match self.state.lock() {
+            Ok(state) => Ok(state),
+            Err(e) => {
+                let msg: String = format!("failed to lock rendezvous (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    action::Action,
+    registry::{JobId, JobRecord},
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::{
+    fmt::Write,
+    str::FromStr,
+    time::{Duration, UNIX_EPOCH},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Format of a job report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+}
+
+/// Report of a set of jobs.
+#[derive(Serialize)]
+struct Report {
+    jobs: Vec<JobReport>,
+}
+
+/// Report of a single job.
+#[derive(Serialize)]
+struct JobReport {
+    id: JobId,
+    name: String,
+    state: String,
+    /// Time at which the job was submitted, in seconds since the Unix epoch.
+    submitted_at: u64,
+    error: Option<String>,
+    tests: usize,
+    failures: usize,
+    duration_ms: u128,
+    testcases: Vec<TestCase>,
+}
+
+/// Report of a single action.
+#[derive(Serialize)]
+struct TestCase {
+    name: String,
+    worker: String,
+    duration_ms: u128,
+    exit_code: Option<i32>,
+    signal: Option<String>,
+    timed_out: bool,
+    passed: bool,
+    stdout: Vec<String>,
+    stderr: Vec<String>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl ReportFormat {
+    /// Returns the content type of reports in the target [ReportFormat].
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "application/json",
+            ReportFormat::Junit => "application/xml",
+        }
+    }
+
+    /// Returns the file extension of reports in the target [ReportFormat].
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Junit => "xml",
+        }
+    }
+
+    /// Renders a report of a set of jobs in the target [ReportFormat].
+    pub fn render(&self, records: &[JobRecord]) -> Result<String> {
+        let report: Report = Report {
+            jobs: records.iter().map(JobReport::new).collect(),
+        };
+        match self {
+            ReportFormat::Json => match serde_json::to_string_pretty(&report) {
+                Ok(json) => Ok(json),
+                Err(e) => {
+                    let msg: String = format!("failed to encode report (e={:?})", e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            },
+            ReportFormat::Junit => Ok(report.to_junit()),
+        }
+    }
+}
+
+impl Report {
+    /// Renders the target [Report] as JUnit XML, with one test suite per job and one test case per action.
+    fn to_junit(&self) -> String {
+        let tests: usize = self.jobs.iter().map(|job| job.tests).sum();
+        let failures: usize = self.jobs.iter().map(|job| job.failures).sum();
+        let errors: usize = self.jobs.iter().filter(|job| job.error.is_some()).count();
+        let duration_ms: u128 = self.jobs.iter().map(|job| job.duration_ms).sum();
+
+        // Writing to a string cannot fail, so results of write!() are ignored.
+        let mut xml: String = String::new();
+        let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            xml,
+            r#"<testsuites tests="{}" failures="{}" errors="{}" time="{}">"#,
+            tests,
+            failures,
+            errors,
+            format_seconds(duration_ms)
+        );
+        for job in &self.jobs {
+            let _ = writeln!(
+                xml,
+                r#"  <testsuite name="{}" id="{}" tests="{}" failures="{}" errors="{}" time="{}">"#,
+                escape(&job.name),
+                job.id,
+                job.tests,
+                job.failures,
+                if job.error.is_some() { 1 } else { 0 },
+                format_seconds(job.duration_ms)
+            );
+            let _ = writeln!(
+                xml,
+                r#"    <properties><property name="state" value="{}"/></properties>"#,
+                escape(&job.state)
+            );
+            // Errors that prevented the job from running are not tied to any action, so report them on their own.
+            if let Some(error) = &job.error {
+                let _ = writeln!(
+                    xml,
+                    r#"    <testcase name="job" classname="{}" time="0"><error message="{}"/></testcase>"#,
+                    escape(&job.name),
+                    escape(error)
+                );
+            }
+            for testcase in &job.testcases {
+                let _ = writeln!(
+                    xml,
+                    r#"    <testcase name="{}" classname="{}.{}" time="{}">"#,
+                    escape(&testcase.name),
+                    escape(&job.name),
+                    escape(&testcase.worker),
+                    format_seconds(testcase.duration_ms)
+                );
+                if !testcase.passed {
+                    let message: String = match (&testcase.exit_code, &testcase.signal, testcase.timed_out) {
+                        (_, _, true) => "timed out".to_string(),
+                        (_, Some(signal), _) => format!("killed by signal {}", signal),
+                        (Some(code), _, _) => format!("exited with code {}", code),
+                        (None, _, _) => "did not complete".to_string(),
+                    };
+                    let _ = writeln!(xml, r#"      <failure message="{}"/>"#, escape(&message));
+                }
+                let _ = writeln!(
+                    xml,
+                    "      <system-out>{}</system-out>",
+                    escape(&testcase.stdout.join("\n"))
+                );
+                let _ = writeln!(
+                    xml,
+                    "      <system-err>{}</system-err>",
+                    escape(&testcase.stderr.join("\n"))
+                );
+                let _ = writeln!(xml, "    </testcase>");
+            }
+            let _ = writeln!(xml, "  </testsuite>");
+        }
+        let _ = writeln!(xml, "</testsuites>");
+
+        xml
+    }
+}
+
+impl JobReport {
+    /// Builds the report of a job out of its record.
+    fn new(record: &JobRecord) -> Self {
+        let testcases: Vec<TestCase> = record.actions().iter().map(TestCase::new).collect();
+        Self {
+            id: record.id(),
+            name: record.name().to_string(),
+            state: record.state().to_string(),
+            submitted_at: record
+                .submitted_at()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs()),
+            error: record.error().map(|error| error.to_string()),
+            tests: testcases.len(),
+            failures: testcases.iter().filter(|testcase| !testcase.passed).count(),
+            duration_ms: testcases.iter().map(|testcase| testcase.duration_ms).sum(),
+            testcases,
+        }
+    }
+}
+
+impl TestCase {
+    /// Builds the report of an action, splitting its output into stdout and stderr.
+    fn new(action: &Action) -> Self {
+        let prefix: String = format!("[{}][{}]", action.runs_on(), action.name());
+        let mut stdout: Vec<String> = Vec::new();
+        let mut stderr: Vec<String> = Vec::new();
+        if let Some(output) = action.output() {
+            for line in output {
+                let line: &str = line.strip_prefix(&prefix).unwrap_or(line);
+                if let Some(line) = line.strip_prefix("[stderr] ") {
+                    stderr.push(line.to_string());
+                } else if let Some(line) = line.strip_prefix("[stdout] ") {
+                    stdout.push(line.to_string());
+                } else if !line.starts_with("[exit] ") {
+                    // The exit status is already reported on its own.
+                    stdout.push(line.to_string());
+                }
+            }
+        }
+
+        Self {
+            name: action.name().to_string(),
+            worker: action.runs_on().to_string(),
+            duration_ms: action.duration().unwrap_or(Duration::ZERO).as_millis(),
+            exit_code: action.exit_status().as_ref().map(|exit_status| exit_status.code()),
+            signal: action
+                .exit_status()
+                .as_ref()
+                .and_then(|exit_status| exit_status.signal().map(|signal| signal.to_string())),
+            timed_out: action
+                .exit_status()
+                .as_ref()
+                .is_some_and(|exit_status| exit_status.timed_out()),
+            passed: action.succeeded(),
+            stdout,
+            stderr,
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ReportFormat::Json),
+            "junit" | "xml" => Ok(ReportFormat::Junit),
+            _ => anyhow::bail!("unknown report format (format={:?})", s),
+        }
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Formats a number of milliseconds as seconds.
+fn format_seconds(duration_ms: u128) -> String {
+    format!("{}.{:03}", duration_ms / 1000, duration_ms % 1000)
+}
+
+/// Escapes a string for use in XML text and attributes. Characters that are not allowed in XML are dropped.
+fn escape(s: &str) -> String {
+    let mut escaped: String = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {},
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,422 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::{Runner, RunnerState};
+use crate::{
+    action::{Action, ExitStatus, FileTransfer},
+    cancellation::Cancellation,
+};
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    fs::{self, Metadata},
+    io::{BufRead, BufReader, Read},
+    os::unix::process::{CommandExt, ExitStatusExt},
+    path::{Path, PathBuf},
+    process::{self, Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Line of output of a command, along with the name of the stream that it was read from.
+type OutputLine = (&'static str, String);
+
+/// Runner that executes actions as processes on the orchestrator host. It is always online, which makes it useful
+/// for jobs that do not need dedicated machines, and for trying out jobs without any.
+pub struct LocalRunner {
+    id: usize,
+    local_addr: String,
+    /// Capabilities of the orchestrator host.
+    labels: HashMap<String, String>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl LocalRunner {
+    /// Interval at which a running command is checked for timeout and cancellation.
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+    /// Amount of time that a killed command has to wind down.
+    const KILL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+    /// Address reported for local runners.
+    const ADDR: &'static str = "localhost";
+    /// Variables of the orchestrator environment that commands inherit. Nothing else is inherited, so that secrets
+    /// that the orchestrator reads from its environment only reach the jobs that ask for them.
+    const INHERITED_ENV_VARS: [&'static str; 3] = ["PATH", "HOME", "LANG"];
+
+    /// Instantiates a new [LocalRunner] object.
+    pub fn new(id: usize, local_addr: &str, labels: HashMap<String, String>) -> Self {
+        Self {
+            id,
+            local_addr: local_addr.to_string(),
+            labels,
+        }
+    }
+
+    /// Spawns a thread that forwards the lines of a stream of the child process to a channel. The channel is
+    /// disconnected once all such threads have reached the end of their streams.
+    fn forward_lines(stream_name: &'static str, stream: impl Read + Send + 'static, tx: Sender<OutputLine>) {
+        thread::spawn(move || {
+            for line in BufReader::new(stream).split(b'\n') {
+                match line {
+                    Ok(line) => {
+                        if tx
+                            .send((stream_name, String::from_utf8_lossy(&line).to_string()))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    },
+                    Err(e) => {
+                        log::warn!("failed to read from child process (stream={}, e={:?})", stream_name, e);
+                        break;
+                    },
+                }
+            }
+        });
+    }
+
+    /// Kills all processes in the process group of a command.
+    fn kill(child: &Child) -> Result<()> {
+        log::warn!("kill: pgid={:?}", child.id());
+        match Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", child.id())])
+            .status()
+        {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => {
+                let msg: String = format!(
+                    "failed to kill process group (pgid={:?}, status={:?})",
+                    child.id(),
+                    status
+                );
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+            Err(e) => {
+                let msg: String = format!("failed to kill process group (pgid={:?}, e={:?})", child.id(), e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Collects the output of a command, handing each line to a callback as soon as it is read, and waits for the
+    /// command to complete. Returns the exit status of the command, unless it had to be given up on, and whether it
+    /// was killed because it timed out.
+    fn do_run(
+        child: &mut Child,
+        rx: Receiver<OutputLine>,
+        deadline: Option<Instant>,
+        cancellation: &Cancellation,
+        output: &mut Vec<String>,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Option<process::ExitStatus>, bool)> {
+        let mut streams_open: bool = true;
+        let mut kill_deadline: Option<Instant> = None;
+        loop {
+            if streams_open {
+                match rx.recv_timeout(Self::POLL_INTERVAL) {
+                    Ok((stream_name, line)) => {
+                        // Skip empty lines.
+                        if !line.is_empty() {
+                            // Pre-append stream name to the line.
+                            let line: String = format!("[{}] {}", stream_name, line);
+                            on_line(&line);
+                            output.push(line);
+                        }
+                    },
+                    Err(RecvTimeoutError::Disconnected) => streams_open = false,
+                    Err(RecvTimeoutError::Timeout) => {},
+                }
+            }
+
+            // Commands that are killed because the job was cancelled did not time out.
+            let timed_out: bool = kill_deadline.is_some() && !cancellation.is_cancelled();
+
+            // The command is done once it has exited and all of its output has been read.
+            if !streams_open {
+                match child.try_wait() {
+                    Ok(Some(status)) => return Ok((Some(status), timed_out)),
+                    Ok(None) => thread::sleep(Self::POLL_INTERVAL),
+                    Err(e) => {
+                        let msg: String = format!("failed to wait for command (e={:?})", e);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                }
+            }
+
+            let expired: bool = match deadline {
+                Some(deadline) => Instant::now() >= deadline,
+                None => false,
+            };
+            match (expired || cancellation.is_cancelled(), kill_deadline) {
+                // The command has timed out or the job was cancelled, so kill the command and give it some time to
+                // wind down.
+                (true, None) => {
+                    if let Err(e) = Self::kill(child) {
+                        log::warn!("failed to kill command (e={:?})", e);
+                    }
+                    kill_deadline = Some(Instant::now() + Self::KILL_GRACE_PERIOD);
+                },
+                // The command did not wind down after being killed, so give up on it.
+                (_, Some(kill_deadline)) if Instant::now() >= kill_deadline => {
+                    log::warn!("giving up on command after kill (pid={:?})", child.id());
+                    return Ok((None, timed_out));
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Returns the name of a signal without its `SIG` prefix, as SSH reports it, or its number if it is uncommon.
+    fn signal_name(signal: i32) -> String {
+        let name: &str = match signal {
+            1 => "HUP",
+            2 => "INT",
+            3 => "QUIT",
+            4 => "ILL",
+            6 => "ABRT",
+            7 => "BUS",
+            8 => "FPE",
+            9 => "KILL",
+            11 => "SEGV",
+            13 => "PIPE",
+            14 => "ALRM",
+            15 => "TERM",
+            _ => return signal.to_string(),
+        };
+        name.to_string()
+    }
+
+    /// Copies a file or directory on the orchestrator host. Directories are copied recursively.
+    fn copy(
+        source: &Path,
+        destination: &Path,
+        label: &str,
+        deadline: Option<Instant>,
+        cancellation: &Cancellation,
+        emit: &mut dyn FnMut(String),
+    ) -> Result<()> {
+        if let Some(deadline) = deadline {
+            if Instant::now() >= deadline {
+                let msg: String = format!("transfer timed out ({})", label);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            }
+        }
+        if cancellation.is_cancelled() {
+            let msg: String = format!("transfer cancelled ({})", label);
+            log::warn!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        let metadata: Metadata = match fs::metadata(source) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                let msg: String = format!("failed to stat file (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        if !metadata.is_dir() {
+            if let Some(parent) = destination.parent() {
+                if !parent.as_os_str().is_empty() {
+                    if let Err(e) = fs::create_dir_all(parent) {
+                        let msg: String = format!("failed to create directory (path={:?}, e={:?})", parent, e);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    }
+                }
+            }
+            // Permissions are preserved by the copy.
+            let size: u64 = match fs::copy(source, destination) {
+                Ok(size) => size,
+                Err(e) => {
+                    let msg: String = format!("failed to copy file ({}, e={:?})", label, e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+            emit(format!(
+                "{} {} -> {} ({} bytes)",
+                label,
+                source.display(),
+                destination.display(),
+                size
+            ));
+            return Ok(());
+        }
+
+        if let Err(e) = fs::create_dir_all(destination) {
+            let msg: String = format!("failed to create directory (path={:?}, e={:?})", destination, e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        let mut entries: Vec<PathBuf> = match fs::read_dir(source) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect(),
+            Err(e) => {
+                let msg: String = format!("failed to read directory (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        entries.sort();
+        for entry in entries {
+            if let Some(name) = entry.file_name() {
+                Self::copy(&entry, &destination.join(name), label, deadline, cancellation, emit)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Runner for LocalRunner {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn addr(&self) -> &str {
+        Self::ADDR
+    }
+
+    fn local_addr(&self) -> &str {
+        &self.local_addr
+    }
+
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// A [LocalRunner] is always online.
+    fn state(&self) -> RunnerState {
+        RunnerState::Online
+    }
+
+    fn probe(&mut self) -> RunnerState {
+        self.state()
+    }
+
+    fn run(
+        &mut self,
+        action: &Action,
+        env: &HashMap<String, String>,
+        cancellation: &Cancellation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Vec<String>, ExitStatus)> {
+        let cmdline: String = action.commands().join(" && ");
+        log::trace!("run: addr={:?}, command={:?}", Self::ADDR, action.commands());
+
+        // Run the command in its own process group, so that we can kill it along with its children on timeout or
+        // cancellation.
+        let mut command: Command = Command::new("sh");
+        command.arg("-c").arg(&cmdline).env_clear();
+        for key in Self::INHERITED_ENV_VARS {
+            if let Some(value) = std::env::var_os(key) {
+                command.env(key, value);
+            }
+        }
+        command
+            .envs(env.iter().filter(|(key, _)| key.to_lowercase() != "job"))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0);
+        let mut child: Child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let msg: String = format!("failed to execute command (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        let deadline: Option<Instant> = action.timeout().map(|timeout| Instant::now() + timeout);
+
+        // Read stdout and stderr in the background, so that neither of them fills up while we wait on the other.
+        let (tx, rx): (Sender<OutputLine>, Receiver<OutputLine>) = mpsc::channel();
+        if let Some(stdout) = child.stdout.take() {
+            Self::forward_lines("stdout", stdout, tx.clone());
+        }
+        if let Some(stderr) = child.stderr.take() {
+            Self::forward_lines("stderr", stderr, tx);
+        }
+
+        let mut output: Vec<String> = Vec::new();
+        let (status, timed_out): (Option<process::ExitStatus>, bool) =
+            Self::do_run(&mut child, rx, deadline, cancellation, &mut output, on_line)?;
+
+        // Report the signal that terminated the command, if any, and the exit code that a shell would report for it.
+        let exit_status: ExitStatus = match status.map(|status| (status.code(), status.signal())) {
+            Some((Some(code), _)) => ExitStatus::new(code, None, timed_out),
+            Some((None, Some(signal))) => ExitStatus::new(128 + signal, Some(Self::signal_name(signal)), timed_out),
+            _ => ExitStatus::new(-1, None, timed_out),
+        };
+        log::trace!("run: addr={:?}, exit_status={:?}", Self::ADDR, exit_status);
+
+        Ok((output, exit_status))
+    }
+
+    /// Transfers are copies on the orchestrator host, since both ends of them are local.
+    fn transfer(
+        &mut self,
+        transfer: &FileTransfer,
+        timeout: Option<Duration>,
+        cancellation: &Cancellation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Vec<String>, ExitStatus)> {
+        log::trace!("transfer: addr={:?}, transfer={:?}", Self::ADDR, transfer);
+
+        let deadline: Option<Instant> = timeout.map(|timeout| Instant::now() + timeout);
+        let label: String = format!("[{}]", transfer.direction());
+        let mut output: Vec<String> = Vec::new();
+        let mut emit = |line: String| {
+            on_line(&line);
+            output.push(line);
+        };
+
+        let result: Result<()> = Self::copy(
+            Path::new(transfer.source()),
+            Path::new(transfer.destination()),
+            &label,
+            deadline,
+            cancellation,
+            &mut emit,
+        );
+
+        // Failed transfers are reported like failed commands, so that their output is kept.
+        let exit_status: ExitStatus = match result {
+            Ok(()) => ExitStatus::new(0, None, false),
+            Err(e) => {
+                let timed_out: bool = match deadline {
+                    Some(deadline) => Instant::now() >= deadline,
+                    None => false,
+                };
+                emit(format!("{} {}", label, e));
+                ExitStatus::new(1, None, timed_out)
+            },
+        };
+        log::trace!("transfer: addr={:?}, exit_status={:?}", Self::ADDR, exit_status);
+
+        Ok((output, exit_status))
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Modules
+//======================================================================================================================
+
+pub mod local;
+pub mod ssh;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    action::{Action, ExitStatus, FileTransfer},
+    cancellation::Cancellation,
+};
+use anyhow::Result;
+use std::{collections::HashMap, fmt, time::Duration};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// State of a runner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunnerState {
+    /// Connected and accepting jobs.
+    Online,
+    /// Unreachable.
+    Offline,
+    /// Connected, but not accepting new jobs.
+    Draining,
+}
+
+//======================================================================================================================
+// Traits
+//======================================================================================================================
+
+/// Executor of actions on behalf of a worker.
+pub trait Runner: Send {
+    /// Retrieves the ID of the target [Runner].
+    fn id(&self) -> usize;
+
+    /// Retrieves the address of the target [Runner].
+    fn addr(&self) -> &str;
+
+    /// Retrieves the local address of the target [Runner], which other workers of a job use to reach it.
+    fn local_addr(&self) -> &str;
+
+    /// Retrieves the capability labels of the target [Runner].
+    fn labels(&self) -> &HashMap<String, String>;
+
+    /// Retrieves the state of the target [Runner].
+    fn state(&self) -> RunnerState;
+
+    /// Checks if the target [Runner] is alive, recovering it if needed, and returns its resulting state.
+    fn probe(&mut self) -> RunnerState;
+
+    /// Runs an [Action] on the target [Runner] and returns its output and exit status. Each line of output is also
+    /// handed to `on_line` as soon as it is read. The command is killed if the job is cancelled while it runs.
+    fn run(
+        &mut self,
+        action: &Action,
+        env: &HashMap<String, String>,
+        cancellation: &Cancellation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Vec<String>, ExitStatus)>;
+
+    /// Performs a file transfer on the target [Runner] and returns its output and exit status. Each line of output is
+    /// also handed to `on_line` as soon as it is produced. The transfer is abandoned if the job is cancelled while it
+    /// runs.
+    fn transfer(
+        &mut self,
+        transfer: &FileTransfer,
+        timeout: Option<Duration>,
+        cancellation: &Cancellation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Vec<String>, ExitStatus)>;
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl fmt::Display for RunnerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: &str = match self {
+            RunnerState::Online => "online",
+            RunnerState::Offline => "offline",
+            RunnerState::Draining => "draining",
+        };
+        write!(f, "{}", s)
+    }
+}
@@ -0,0 +1,703 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::{Runner, RunnerState};
+use crate::{
+    action::{Action, ExitStatus, FileTransfer},
+    cancellation::Cancellation,
+    credentials::Credentials,
+    transfer::FileCopier,
+};
+use anyhow::{Error, Result};
+use ssh2::{Channel, Session, Stream};
+use std::{
+    cmp,
+    collections::HashMap,
+    io::{ErrorKind, Read},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Runner that executes actions on a remote machine over SSH.
+pub struct SshRunner {
+    id: usize,
+    addr: String,
+    local_addr: String,
+    /// Capabilities of the target machine.
+    labels: HashMap<String, String>,
+    /// Credentials used to authenticate with the target machine.
+    credentials: Credentials,
+    /// Session with the target machine, if connected.
+    session: Option<Session>,
+    /// Number of consecutive failed attempts to connect to the target machine.
+    failed_attempts: u32,
+    /// Earliest time at which the next attempt to connect to the target machine should be made.
+    next_attempt: Instant,
+}
+
+/// Buffer that breaks a byte stream into lines.
+#[derive(Default)]
+struct LineBuffer {
+    bytes: Vec<u8>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SshRunner {
+    const KEEP_ALIVE_INTERVAL: u32 = 5;
+    /// Interval (in milliseconds) at which a running command is polled for output.
+    const POLL_INTERVAL: u32 = 500;
+    /// Amount of time (in seconds) that a killed command has to wind down.
+    const KILL_GRACE_PERIOD: u64 = 10;
+    const READ_BUFFER_SIZE: usize = 4096;
+    const SESSION_ID_MARKER: &'static str = "DEMIKERNEL_CI_SESSION_ID=";
+
+    /// Amount of time (in seconds) to wait for a connection to be established.
+    const CONNECT_TIMEOUT: u64 = 10;
+    /// Amount of time (in milliseconds) to wait for the target machine to answer a liveness probe.
+    const PROBE_TIMEOUT: u32 = 10_000;
+    /// Amount of time (in milliseconds) that a blocking operation of a file transfer may stall before the deadline and
+    /// cancellation of the transfer are checked again.
+    const TRANSFER_STALL_TIMEOUT: u32 = 10_000;
+    /// Minimum and maximum amount of time (in seconds) to wait before reconnecting after a failed attempt.
+    const RECONNECT_BACKOFF_MIN: u64 = 1;
+    const RECONNECT_BACKOFF_MAX: u64 = 300;
+
+    /// Instantiates a new [SshRunner] object. The connection to the target machine is established lazily.
+    pub fn new(
+        id: usize,
+        hostname: &str,
+        port: u16,
+        local_addr: &str,
+        labels: HashMap<String, String>,
+        credentials: &Credentials,
+    ) -> Self {
+        Self {
+            id,
+            addr: format!("{}:{}", hostname, port),
+            local_addr: local_addr.to_string(),
+            labels,
+            credentials: credentials.clone(),
+            session: None,
+            failed_attempts: 0,
+            next_attempt: Instant::now(),
+        }
+    }
+
+    /// Connects to the target machine.
+    fn connect(&mut self) -> Result<()> {
+        match Self::open_session(&self.addr, &self.credentials) {
+            Ok(session) => {
+                log::info!("connected (addr={:?})", self.addr);
+                self.session = Some(session);
+                self.failed_attempts = 0;
+                Ok(())
+            },
+            Err(e) => {
+                let backoff: u64 = cmp::min(
+                    Self::RECONNECT_BACKOFF_MIN << cmp::min(self.failed_attempts, 16),
+                    Self::RECONNECT_BACKOFF_MAX,
+                );
+                self.failed_attempts += 1;
+                self.next_attempt = Instant::now() + Duration::from_secs(backoff);
+                Err(e)
+            },
+        }
+    }
+
+    /// Drops the session with the target machine, if any.
+    fn disconnect(&mut self) {
+        if let Some(session) = self.session.take() {
+            log::warn!("disconnecting (addr={:?})", self.addr);
+            if let Err(e) = session.disconnect(None, "disconnecting", None) {
+                log::trace!("failed to disconnect (e={:?})", e);
+            }
+        }
+    }
+
+    /// Opens an authenticated SSH session with a remote machine.
+    fn open_session(addr: &str, credentials: &Credentials) -> Result<Session> {
+        // Create a TCP stream to connect to the server.
+        let socket_addrs: Vec<SocketAddr> = match addr.to_socket_addrs() {
+            Ok(socket_addrs) => socket_addrs.collect(),
+            Err(e) => {
+                let msg: String = format!("failed to resolve address (addr={:?}, e={:?})", addr, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        let mut tcp: Option<TcpStream> = None;
+        for socket_addr in &socket_addrs {
+            match TcpStream::connect_timeout(socket_addr, Duration::from_secs(Self::CONNECT_TIMEOUT)) {
+                Ok(stream) => {
+                    tcp = Some(stream);
+                    break;
+                },
+                Err(e) => log::trace!("failed to connect (addr={:?}, e={:?})", socket_addr, e),
+            }
+        }
+        let tcp: TcpStream = match tcp {
+            Some(tcp) => tcp,
+            None => {
+                let msg: String = format!("failed to connect (addr={:?})", addr);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        if let Err(e) = tcp.set_read_timeout(None) {
+            let msg: String = format!("failed to set read timeout (e={:?})", e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        // Create a new SSH session from the TCP stream.
+        let mut session: Session = match Session::new() {
+            Ok(session) => session,
+            Err(e) => {
+                let msg: String = format!("failed to create session (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        session.set_tcp_stream(tcp);
+        session.set_blocking(true);
+        session.set_compress(true);
+
+        // Do not wait forever on an unresponsive machine while setting up the session.
+        session.set_timeout((Self::CONNECT_TIMEOUT * 1000) as u32);
+        if let Err(e) = session.handshake() {
+            let msg: String = format!("failed to handshake (e={:?})", e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        session.set_keepalive(true, Self::KEEP_ALIVE_INTERVAL);
+
+        // Use username and public key to authenticate.
+        let pubkey: &Path = Path::new(credentials.public_key_path());
+        let privatekey: &Path = Path::new(credentials.private_key_path());
+        let username: &str = credentials.username();
+        match session.userauth_pubkey_file(username, Some(pubkey), privatekey, None) {
+            Ok(()) => {},
+            Err(e) => {
+                let msg: String = format!("failed to authenticate (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+
+        // Check if authentication failed
+        if !session.authenticated() {
+            let msg: String = format!("authentication failed");
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        session.set_timeout(0);
+        if session.timeout() != 0 {
+            let msg: String = "failed to set timeout".to_string();
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        Ok(session)
+    }
+
+    /// Runs a no-op command on the target machine to check that it is still responding.
+    fn check_liveness(&mut self) -> Result<()> {
+        let session: &Session = match &self.session {
+            Some(session) => session,
+            None => anyhow::bail!("not connected"),
+        };
+
+        session.set_timeout(Self::PROBE_TIMEOUT);
+        let result: Result<()> = Self::run_noop(session);
+        session.set_timeout(0);
+
+        result
+    }
+
+    /// Runs a command that does nothing in a session and waits for it to complete.
+    fn run_noop(session: &Session) -> Result<()> {
+        let mut channel: Channel = session.channel_session()?;
+        channel.exec("true")?;
+        channel.wait_eof()?;
+        channel.close()?;
+        channel.wait_close()?;
+        Ok(())
+    }
+
+    /// Opens a session-based channel on the target machine, connecting to it first if needed. If the existing session
+    /// turns out to be broken, the target [SshRunner] reconnects once before giving up.
+    fn open_channel(&mut self) -> Result<Channel> {
+        if let Some(session) = &self.session {
+            match session.channel_session() {
+                Ok(channel) => return Ok(channel),
+                Err(e) => {
+                    log::warn!("failed to open session-based channel, reconnecting (e={:?})", e);
+                    self.disconnect();
+                },
+            }
+        }
+
+        self.connect()?;
+        let session: &Session = match &self.session {
+            Some(session) => session,
+            None => anyhow::bail!("not connected"),
+        };
+        match session.channel_session() {
+            Ok(channel) => Ok(channel),
+            Err(e) => {
+                let msg: String = format!("failed to open session-based channel (e={:?})", e);
+                log::error!("{}", msg);
+                self.disconnect();
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Sets the timeout (in milliseconds) of blocking operations on the session of the target [SshRunner], if any.
+    fn set_timeout(&self, timeout_ms: u32) {
+        if let Some(session) = &self.session {
+            session.set_timeout(timeout_ms);
+        }
+    }
+
+    /// Retrieves the exit status of the command that ran on a closed channel.
+    fn get_exit_status(channel: &Channel, timed_out: bool) -> Result<ExitStatus> {
+        let code: i32 = match channel.exit_status() {
+            Ok(code) => code,
+            Err(e) => {
+                let msg: String = format!("failed to get exit status (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        // The remote end may not report an exit signal, so we do not treat this as an error.
+        let signal: Option<String> = match channel.exit_signal() {
+            Ok(exit_signal) => exit_signal.exit_signal,
+            Err(e) => {
+                let msg: String = format!("failed to get exit signal (e={:?})", e);
+                log::warn!("{}", msg);
+                None
+            },
+        };
+
+        Ok(ExitStatus::new(code, signal, timed_out))
+    }
+
+    /// Reads whatever is available in an inbound stream, waiting at most for the session timeout. Returns `false` on
+    /// end of file or if the stream is broken.
+    fn read_inboud_stream(stream: &mut Stream, bytes: &mut Vec<u8>) -> bool {
+        let mut buf: [u8; Self::READ_BUFFER_SIZE] = [0; Self::READ_BUFFER_SIZE];
+        match stream.read(&mut buf) {
+            Ok(0) => false,
+            Ok(n) => {
+                bytes.extend_from_slice(&buf[..n]);
+                true
+            },
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => false,
+            Err(e) if e.kind() == ErrorKind::TimedOut || e.kind() == ErrorKind::WouldBlock => true,
+            Err(e) => {
+                log::warn!("failed to read from channel (e={:?})", e);
+                false
+            },
+        }
+    }
+
+    /// Handles a line of output. The first line of stdout reports the session ID of the remote shell and it is not
+    /// part of the output of the command.
+    fn process_line(
+        stream_name: &str,
+        line: String,
+        session_id: &mut Option<u32>,
+        output: &mut Vec<String>,
+        on_line: &mut dyn FnMut(&str),
+    ) {
+        if stream_name == "stdout" && session_id.is_none() {
+            if let Some(id) = line.strip_prefix(Self::SESSION_ID_MARKER) {
+                *session_id = id.trim().parse::<u32>().ok();
+                return;
+            }
+        }
+
+        // Skip empty lines.
+        if line.is_empty() {
+            return;
+        }
+
+        // Pre-append stream name to the line.
+        let line: String = format!("[{}] {}", stream_name, line);
+        on_line(&line);
+        output.push(line);
+    }
+
+    /// Kills all processes in the session of a remote shell. This is called while a command is being polled, so the
+    /// session is kept as is: reconnecting would drop the channel of the command that is being killed.
+    fn kill(&self, session_id: u32) -> Result<()> {
+        log::warn!("kill: addr={:?}, session_id={:?}", self.addr, session_id);
+        let session: &Session = match &self.session {
+            Some(session) => session,
+            None => anyhow::bail!("not connected"),
+        };
+
+        // Blocking operations of the session time out at the polling interval, which is too short to open a channel.
+        session.set_timeout(Self::PROBE_TIMEOUT);
+        let result: Result<()> = Self::run_kill(session, session_id);
+        session.set_timeout(Self::POLL_INTERVAL);
+
+        result
+    }
+
+    /// Runs a command that kills all processes in the session of a remote shell.
+    fn run_kill(session: &Session, session_id: u32) -> Result<()> {
+        let mut channel: Channel = match session.channel_session() {
+            Ok(channel) => channel,
+            Err(e) => {
+                let msg: String = format!("failed to open session-based channel (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let result: Result<()> = match channel.exec(&format!("pkill -KILL -s {}", session_id)) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let msg: String = format!("failed to kill remote session (session_id={:?}, e={:?})", session_id, e);
+                log::error!("{}", msg);
+                Err(anyhow::anyhow!(msg))
+            },
+        };
+
+        if let Err(e) = channel.close() {
+            log::warn!("failed to close channel (e={:?})", e);
+        }
+
+        result
+    }
+
+    /// Executes a command line and collects its output, handing each line to a callback as soon as it is read.
+    /// Returns whether the command was killed because it timed out.
+    fn do_run(
+        &mut self,
+        channel: &mut Channel,
+        cmdline: &str,
+        deadline: Option<Instant>,
+        cancellation: &Cancellation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Vec<String>, bool)> {
+        // Execute the command and check if we succeeded.
+        match channel.exec(&cmdline) {
+            // We succeed to execute the command.
+            Ok(()) => {
+                let mut output: Vec<String> = Vec::default();
+                let mut stdout_buffer: LineBuffer = LineBuffer::default();
+                let mut stderr_buffer: LineBuffer = LineBuffer::default();
+                let mut session_id: Option<u32> = None;
+                let mut kill_deadline: Option<Instant> = None;
+
+                loop {
+                    // Process stdout.
+                    let mut stdout_stream: Stream = channel.stream(0);
+                    let stdout_open: bool = Self::read_inboud_stream(&mut stdout_stream, &mut stdout_buffer.bytes);
+                    for line in stdout_buffer.take_lines() {
+                        Self::process_line("stdout", line, &mut session_id, &mut output, on_line);
+                    }
+
+                    // Process stderr.
+                    let mut stderr_stream: Stream = channel.stderr();
+                    let stderr_open: bool = Self::read_inboud_stream(&mut stderr_stream, &mut stderr_buffer.bytes);
+                    for line in stderr_buffer.take_lines() {
+                        Self::process_line("stderr", line, &mut session_id, &mut output, on_line);
+                    }
+
+                    if channel.eof() || (!stdout_open && !stderr_open) {
+                        break;
+                    }
+
+                    let expired: bool = match deadline {
+                        Some(deadline) => Instant::now() >= deadline,
+                        None => false,
+                    };
+                    match (expired || cancellation.is_cancelled(), kill_deadline) {
+                        // The command has timed out or the job was cancelled, so kill the command and give it some
+                        // time to wind down.
+                        (true, None) => {
+                            match session_id {
+                                Some(session_id) => {
+                                    if let Err(e) = self.kill(session_id) {
+                                        log::warn!("failed to kill command (e={:?})", e);
+                                    }
+                                },
+                                None => log::warn!("cannot kill command, session id is unknown"),
+                            }
+                            kill_deadline = Some(Instant::now() + Duration::from_secs(Self::KILL_GRACE_PERIOD));
+                        },
+                        // The command did not wind down after being killed, so give up on it.
+                        (_, Some(kill_deadline)) if Instant::now() >= kill_deadline => {
+                            log::warn!("giving up on command after kill (cmdline={:?})", cmdline);
+                            break;
+                        },
+                        _ => {},
+                    }
+                }
+
+                // Process incomplete lines.
+                if let Some(line) = stdout_buffer.take_remainder() {
+                    Self::process_line("stdout", line, &mut session_id, &mut output, on_line);
+                }
+                if let Some(line) = stderr_buffer.take_remainder() {
+                    Self::process_line("stderr", line, &mut session_id, &mut output, on_line);
+                }
+
+                // Commands that are killed because the job was cancelled did not time out.
+                Ok((output, kill_deadline.is_some() && !cancellation.is_cancelled()))
+            },
+            // We did not succeeded to run the command.
+            Err(e) => {
+                // Log an error message and return an error.
+                let msg: String = format!("failed to execute command (e={:?})", e);
+                log::error!("{}", msg);
+                Err(anyhow::anyhow!(msg))
+            },
+        }
+    }
+}
+
+impl LineBuffer {
+    /// Removes all complete lines from the target [LineBuffer].
+    fn take_lines(&mut self) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        while let Some(pos) = self.bytes.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.bytes.drain(..=pos).collect();
+            lines.push(String::from_utf8_lossy(&line[..pos]).to_string());
+        }
+        lines
+    }
+
+    /// Removes the incomplete line that is left in the target [LineBuffer], if any.
+    fn take_remainder(&mut self) -> Option<String> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let line: Vec<u8> = self.bytes.drain(..).collect();
+        Some(String::from_utf8_lossy(&line).to_string())
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Runner for SshRunner {
+    /// Retrieves the ID of the target [SshRunner].
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Retrieves the address of the target [SshRunner].
+    fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Retrieves the local address of the target [SshRunner].
+    fn local_addr(&self) -> &str {
+        &self.local_addr
+    }
+
+    /// Retrieves the capability labels of the target [SshRunner].
+    fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    /// Retrieves the state of the target [SshRunner]. A [SshRunner] is online if it has a session with the target machine.
+    fn state(&self) -> RunnerState {
+        match self.session {
+            Some(_) => RunnerState::Online,
+            None => RunnerState::Offline,
+        }
+    }
+
+    /// Checks if the target machine is alive, reconnecting to it if needed, and returns the resulting state of the
+    /// target [SshRunner]. Reconnection attempts back off exponentially while the target machine is unreachable.
+    fn probe(&mut self) -> RunnerState {
+        if self.session.is_some() {
+            if let Err(e) = self.check_liveness() {
+                log::warn!("runner is not responding (addr={:?}, e={:?})", self.addr, e);
+                self.disconnect();
+            }
+        }
+
+        if self.session.is_none() && Instant::now() >= self.next_attempt {
+            if let Err(e) = self.connect() {
+                log::warn!(
+                    "failed to reconnect (addr={:?}, failed_attempts={:?}, e={:?})",
+                    self.addr,
+                    self.failed_attempts,
+                    e
+                );
+            }
+        }
+
+        self.state()
+    }
+
+    /// Runs an [Action] on the target [SshRunner] and returns its output and exit status. Each line of output is also
+    /// handed to `on_line` as soon as it is read. The command is killed if the job is cancelled while it runs.
+    fn run(
+        &mut self,
+        action: &Action,
+        env: &HashMap<String, String>,
+        cancellation: &Cancellation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Vec<String>, ExitStatus)> {
+        let commands: &Vec<String> = action.commands();
+        let mut cmdline: String = String::new();
+
+        log::trace!("run: addr={:?}, command={:?}", self.addr, commands);
+
+        // Concatenate all commands.
+        for command in commands {
+            cmdline.push_str(command);
+
+            // Do not concatenate if last command.
+            // Note that it is safe to call expect() because we are iterating
+            // over the commands list, and thus it cannot be empty.
+            if command != commands.last().expect("commands list cannot be empty") {
+                cmdline.push_str(" &&");
+            }
+        }
+
+        // Open a session-based channel for running a command.
+        let mut channel: Channel = self.open_channel()?;
+
+        // Set environment variables.
+        for (key, value) in env {
+            if key.to_lowercase() != "job" {
+                if let Err(e) = channel.setenv(key, value) {
+                    let msg: String = format!("failed to set environment variable (key={:?}, e={:?})", key, e);
+                    log::warn!("{}", msg);
+                }
+            }
+        }
+
+        // Prepend a command that reports the session ID of the remote shell, so that we can kill it on timeout or
+        // cancellation.
+        let cmdline: String = format!("echo {}$$; {}", Self::SESSION_ID_MARKER, cmdline);
+        let deadline: Option<Instant> = action.timeout().map(|timeout| Instant::now() + timeout);
+
+        //==========================================================================
+        // NOTE: from this point on, we must close the channel before returning.
+        //==========================================================================
+
+        // Poll the channel instead of blocking on it, so that we can enforce the deadline.
+        self.set_timeout(Self::POLL_INTERVAL);
+
+        // Execute the command and parse result.
+        let result: Result<(Vec<String>, bool), Error> =
+            self.do_run(&mut channel, &cmdline, deadline, cancellation, on_line);
+
+        // Close the session-based channel and check if we succeeded.
+        match channel.close() {
+            // We succeed to close the session-based channel.
+            Ok(()) => {
+                // Wait for the channel to close and check if we succeeded.
+                if let Err(e) = channel.wait_close() {
+                    // We failed, thus log a warning message and keep going.
+                    let msg: String = format!(
+                        "failed to wait for channel to close (e={:?}, eof={:?})",
+                        e,
+                        channel.eof()
+                    );
+                    log::warn!("{}", msg);
+                }
+            },
+            // We failed to close the session-based channel.
+            Err(e) => {
+                // Log a warning message and keep going.
+                let msg: String = format!("failed to close channel (e={:?})", e);
+                log::warn!("{}", msg);
+            },
+        }
+
+        self.set_timeout(0);
+
+        // A failure at this point means that the session is likely broken, so drop it and reconnect later on.
+        let exit_status: Result<ExitStatus> = match &result {
+            Ok((_, timed_out)) => Self::get_exit_status(&channel, *timed_out),
+            Err(_) => Err(anyhow::anyhow!("failed to run command")),
+        };
+        if exit_status.is_err() {
+            self.disconnect();
+        }
+        let (output, _): (Vec<String>, bool) = result?;
+        let exit_status: ExitStatus = exit_status?;
+        log::trace!("run: addr={:?}, exit_status={:?}", self.addr, exit_status);
+
+        Ok((output, exit_status))
+    }
+
+    /// Performs a file transfer on the target [SshRunner] and returns its output and exit status. Each line of output is
+    /// also handed to `on_line` as soon as it is produced.
+    fn transfer(
+        &mut self,
+        transfer: &FileTransfer,
+        timeout: Option<Duration>,
+        cancellation: &Cancellation,
+        on_line: &mut dyn FnMut(&str),
+    ) -> Result<(Vec<String>, ExitStatus)> {
+        log::trace!("transfer: addr={:?}, transfer={:?}", self.addr, transfer);
+
+        if self.session.is_none() {
+            self.connect()?;
+        }
+        let session: &Session = match &self.session {
+            Some(session) => session,
+            None => anyhow::bail!("not connected"),
+        };
+
+        let deadline: Option<Instant> = timeout.map(|timeout| Instant::now() + timeout);
+        let mut output: Vec<String> = Vec::new();
+        let mut emit = |line: String| {
+            on_line(&line);
+            output.push(line);
+        };
+
+        // Bound blocking operations, so that a stalled transfer still notices its deadline and cancellation.
+        session.set_timeout(Self::TRANSFER_STALL_TIMEOUT);
+        let (result, timed_out): (Result<()>, bool) = match FileCopier::new(session, deadline, cancellation, &mut emit)
+        {
+            Ok(mut copier) => {
+                let result: Result<()> = copier.copy(transfer);
+                (result, copier.timed_out())
+            },
+            Err(e) => (Err(e), false),
+        };
+        session.set_timeout(0);
+
+        // Failed transfers are reported like failed commands, so that their output is kept.
+        let exit_status: ExitStatus = match result {
+            Ok(()) => ExitStatus::new(0, None, false),
+            Err(e) => {
+                emit(format!("[{}] {}", transfer.direction(), e));
+                ExitStatus::new(1, None, timed_out)
+            },
+        };
+        log::trace!("transfer: addr={:?}, exit_status={:?}", self.addr, exit_status);
+
+        Ok((output, exit_status))
+    }
+}
@@ -5,13 +5,30 @@
 // Imports
 //======================================================================================================================
 
-use crate::{config::Config, job::Job, runner::Runner, task::Task, worker::Worker};
+use crate::{
+    action::Action,
+    artifacts::ArtifactStore,
+    cancellation::Cancellation,
+    dependencies::DependencyTracker,
+    history::HistoryStore,
+    job::Job,
+    notifier::{CommitState, CommitTarget, Notifier},
+    plan::{JobPlan, RunnerAssignment},
+    registry::{JobId, JobRecord, JobRegistry, JobState},
+    rendezvous::Rendezvous,
+    report::ReportFormat,
+    runner::{Runner, RunnerState},
+    secrets::SecretStore,
+    task::Task,
+    worker::Worker,
+};
 use anyhow::Result;
 use std::{
-    collections::HashMap,
-    sync::{Arc, Barrier, Mutex},
-    thread::{self, sleep, ScopedJoinHandle},
-    time::Duration,
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+    sync::{mpsc::Sender, Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, ScopedJoinHandle},
+    time::{Duration, Instant},
 };
 
 //======================================================================================================================
@@ -19,7 +36,58 @@ use std::{
 //======================================================================================================================
 
 pub struct Scheduler {
-    runners: Mutex<Vec<Mutex<Runner>>>,
+    runners: Mutex<RunnerPool>,
+    /// Signaled whenever runners are returned to the pool or the head of the wait queue changes.
+    runners_available: Condvar,
+    /// Identifiers and labels of all runners, including those that are busy.
+    fleet: Vec<(usize, HashMap<String, String>)>,
+    /// Remote and local addresses of all runners.
+    addrs: HashMap<usize, (String, String)>,
+    registry: JobRegistry,
+    /// Store of artifacts that are collected from jobs.
+    artifacts: ArtifactStore,
+    /// Store of finished jobs.
+    history: HistoryStore,
+    /// Reporter of commit statuses, if enabled.
+    notifier: Option<Notifier>,
+    /// Secrets that jobs may reference.
+    secrets: SecretStore,
+    /// Handles through which jobs that are queued or running are cancelled.
+    cancellations: Mutex<HashMap<JobId, Arc<Cancellation>>>,
+}
+
+/// Runners allocated to a job, along with a map from runner identifiers to the names of the workers that they serve.
+type Allocation = (Vec<Mutex<Box<dyn Runner>>>, HashMap<usize, String>);
+
+/// Pool of idle runners, along with the queue of jobs that are waiting for them.
+struct RunnerPool {
+    /// Idle runners.
+    idle: Vec<Mutex<Box<dyn Runner>>>,
+    /// Jobs waiting for runners, in the order in which they are served.
+    waiting: Vec<Ticket>,
+    /// Sequence number of the next ticket.
+    next_sequence: u64,
+    /// Last known state of each runner, including those that are busy.
+    health: HashMap<usize, RunnerState>,
+    /// Runners that should not be allocated to new jobs.
+    draining: HashSet<usize>,
+}
+
+/// Place of a job in the wait queue.
+struct Ticket {
+    job_id: JobId,
+    priority: i64,
+    sequence: u64,
+}
+
+/// Outcome of a job.
+struct JobOutcome {
+    /// Did all actions of the job complete successfully?
+    passed: bool,
+    /// Did all actions of the job get to run? Actions are skipped once the job is cancelled.
+    complete: bool,
+    /// Actions that were run.
+    actions: Vec<Action>,
 }
 
 //======================================================================================================================
@@ -27,31 +95,492 @@ pub struct Scheduler {
 //======================================================================================================================
 
 impl Scheduler {
-    const SLEEP_INTERVAL: u64 = 500;
+    /// Interval at which the health of idle runners is checked.
+    const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+    pub fn new(
+        runners: Vec<Mutex<Box<dyn Runner>>>,
+        artifacts_home: &str,
+        history: HistoryStore,
+        notifier: Option<Notifier>,
+        secrets: SecretStore,
+    ) -> Result<Self> {
+        let fleet: Vec<(usize, HashMap<String, String>)> = Self::describe_runners(&runners);
+        let addrs: HashMap<usize, (String, String)> = Self::runner_addrs(&runners);
+        let health: HashMap<usize, RunnerState> = fleet.iter().map(|(id, _)| (*id, RunnerState::Offline)).collect();
+        // Do not reuse identifiers of jobs that ran before a restart, even if they were pruned from the history or
+        // never made it there.
+        let artifacts: ArtifactStore = ArtifactStore::new(artifacts_home);
+        let first_id: JobId = history.next_id()?.max(artifacts.next_id()?);
+        Ok(Self {
+            runners: Mutex::new(RunnerPool {
+                idle: runners,
+                waiting: Vec::new(),
+                next_sequence: 0,
+                health,
+                draining: HashSet::new(),
+            }),
+            runners_available: Condvar::new(),
+            fleet,
+            addrs,
+            registry: JobRegistry::new(first_id),
+            artifacts,
+            history,
+            notifier,
+            secrets,
+            cancellations: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the position (starting at one) of a job in the queue of jobs that are waiting for runners, or `None`
+    /// if the job is not waiting.
+    pub fn queue_position(&self, job_id: JobId) -> Option<usize> {
+        match self.lock_runners() {
+            Ok(pool) => pool
+                .waiting
+                .iter()
+                .position(|ticket| ticket.job_id == job_id)
+                .map(|position| position + 1),
+            Err(_) => None,
+        }
+    }
+
+    /// Spawns a thread that periodically checks the health of idle runners, reconnecting to them as needed. The first
+    /// check happens before returning, so that jobs are not rejected for lack of runners that have yet to be probed.
+    pub fn monitor_runners(self: &Arc<Self>) -> Result<()> {
+        self.check_runners();
+
+        let scheduler: Arc<Scheduler> = self.clone();
+        let builder: thread::Builder = thread::Builder::new().name("runner-monitor".to_string());
+        if let Err(e) = builder.spawn(move || loop {
+            thread::sleep(Self::HEALTH_CHECK_INTERVAL);
+            scheduler.check_runners();
+        }) {
+            let msg: String = format!("failed to spawn runner monitor thread (e={:?})", e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        Ok(())
+    }
+
+    /// Probes all idle runners. Runners are taken out of the pool one at a time while they are probed, so that jobs
+    /// are not held back by unresponsive machines, and the other runners remain available meanwhile.
+    fn check_runners(&self) {
+        for (id, _) in &self.fleet {
+            // Busy runners are not probed, since they are known to be working.
+            let (position, runner): (usize, Mutex<Box<dyn Runner>>) = match self.lock_runners() {
+                Ok(mut pool) => match Self::find_runner(&pool.idle, *id) {
+                    Some(position) => (position, pool.idle.remove(position)),
+                    None => continue,
+                },
+                Err(_) => return,
+            };
+
+            let state: Option<RunnerState> = match runner.lock() {
+                Ok(mut runner) => Some(runner.probe()),
+                Err(e) => {
+                    log::warn!("failed to lock runner (e={:?})", e);
+                    None
+                },
+            };
+
+            match self.lock_runners() {
+                Ok(mut pool) => {
+                    if let Some(state) = state {
+                        if pool.health.insert(*id, state) != Some(state) {
+                            log::info!("runner changed state (id={:?}, state={})", id, state);
+                        }
+                    }
+                    // Keep the order of idle runners, so that placement does not depend on when runners were probed.
+                    let position: usize = position.min(pool.idle.len());
+                    pool.idle.insert(position, runner);
+                },
+                Err(e) => log::warn!("failed to return probed runner (id={:?}, e={:?})", id, e),
+            }
+
+            // Wake up jobs that are waiting for runners.
+            self.runners_available.notify_all();
+        }
+    }
+
+    /// Returns the position of a runner among a list of runners, if it is there.
+    fn find_runner(runners: &[Mutex<Box<dyn Runner>>], id: usize) -> Option<usize> {
+        runners
+            .iter()
+            .position(|runner| runner.lock().map(|runner| runner.id() == id).unwrap_or(false))
+    }
+
+    /// Returns the identifiers and labels of the runners that may take new jobs, whether or not they are busy. Runners
+    /// that are offline or draining are skipped, since they cannot take new jobs until they are brought back.
+    fn serviceable_runners(&self) -> Result<Vec<(usize, HashMap<String, String>)>> {
+        let pool: MutexGuard<'_, RunnerPool> = self.lock_runners()?;
+        Ok(self
+            .fleet
+            .iter()
+            .filter(|(id, _)| pool.health.get(id) == Some(&RunnerState::Online) && !pool.draining.contains(id))
+            .cloned()
+            .collect())
+    }
+
+    /// Returns the identifier, state, and whether or not it is busy, of each runner.
+    pub fn runner_states(&self) -> Result<Vec<(usize, RunnerState, bool)>> {
+        let pool: MutexGuard<'_, RunnerPool> = self.lock_runners()?;
+        let idle: HashSet<usize> = Self::describe_runners(&pool.idle)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+
+        let mut states: Vec<(usize, RunnerState, bool)> = Vec::new();
+        for (id, _) in &self.fleet {
+            let state: RunnerState = match pool.health.get(id) {
+                Some(RunnerState::Online) if pool.draining.contains(id) => RunnerState::Draining,
+                Some(state) => *state,
+                None => RunnerState::Offline,
+            };
+            states.push((*id, state, !idle.contains(id)));
+        }
+
+        Ok(states)
+    }
+
+    /// Sets whether or not a runner is draining. A draining runner finishes the job that it is running, if any, but it
+    /// is not allocated to new jobs. Returns `false` if there is no such runner.
+    pub fn set_draining(&self, runner_id: usize, draining: bool) -> Result<bool> {
+        if !self.fleet.iter().any(|(id, _)| *id == runner_id) {
+            return Ok(false);
+        }
+
+        let mut pool: MutexGuard<'_, RunnerPool> = self.lock_runners()?;
+        if draining {
+            pool.draining.insert(runner_id);
+        } else {
+            pool.draining.remove(&runner_id);
+            self.runners_available.notify_all();
+        }
+        log::info!("runner draining (id={:?}, draining={:?})", runner_id, draining);
+
+        Ok(true)
+    }
+
+    /// Returns the registry of jobs of the target [Scheduler].
+    pub fn registry(&self) -> &JobRegistry {
+        &self.registry
+    }
+
+    /// Blocks until a job finishes and returns its record, which is looked up in the history if the job has already
+    /// been forgotten by the registry.
+    pub fn wait(&self, job_id: JobId) -> Result<JobRecord> {
+        if let Some(record) = self.registry.wait(job_id)? {
+            return Ok(record);
+        }
+        match self.history.get(job_id)? {
+            Some(record) => Ok(record),
+            None => {
+                let msg: String = format!("no such job (id={:?})", job_id);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Returns the store of finished jobs of the target [Scheduler].
+    pub fn history(&self) -> &HistoryStore {
+        &self.history
+    }
+
+    /// Returns the store of artifacts of the target [Scheduler].
+    pub fn artifacts(&self) -> &ArtifactStore {
+        &self.artifacts
+    }
+
+    /// Submits jobs, given along with their names, for asynchronous execution and returns their identifiers in the
+    /// same order. If a sink is supplied, output lines of the jobs are sent to it as soon as they are produced. Fails
+    /// if any job references unknown secrets, in which case none of them is submitted.
+    pub fn submit(self: &Arc<Self>, jobs: Vec<(String, Job)>, sink: Option<Sender<String>>) -> Result<Vec<JobId>> {
+        let mut resolved: Vec<(String, Job)> = Vec::new();
+        for (job_name, mut job) in jobs {
+            job.set_secrets(self.secrets.resolve(job.secret_names())?);
+            resolved.push((job_name, job));
+        }
+
+        let mut job_ids: Vec<JobId> = Vec::new();
+        for (job_name, job) in resolved {
+            job_ids.push(self.start(&job_name, job, sink.clone())?);
+        }
+        Ok(job_ids)
+    }
+
+    /// Registers a job whose secrets have been resolved, and starts executing it in the background.
+    fn start(self: &Arc<Self>, job_name: &str, job: Job, sink: Option<Sender<String>>) -> Result<JobId> {
+        let job_id: JobId = self.registry.register(job_name, job.env())?;
+        Worker::send_line(
+            &sink,
+            format!("[job] id={} name={} state={}", job_id, job_name, JobState::Queued),
+        );
+        let cancellation: Arc<Cancellation> = Arc::new(Cancellation::new());
+        self.lock_cancellations()?.insert(job_id, cancellation.clone());
+        let scheduler: Arc<Scheduler> = self.clone();
+        let builder: thread::Builder = thread::Builder::new().name(format!("job-{}", job_id));
+        if let Err(e) = builder.spawn(move || scheduler.execute(job_id, job, sink, cancellation)) {
+            let msg: String = format!("failed to spawn job thread (e={:?})", e);
+            log::error!("{}", msg);
+            self.lock_cancellations()?.remove(&job_id);
+            self.registry
+                .set_finished(job_id, JobState::Failed, Vec::new(), Some(msg.clone()))?;
+            anyhow::bail!(msg);
+        }
+
+        log::info!("submitted job (id={:?}, name={:?})", job_id, job_name);
+        Ok(job_id)
+    }
+
+    /// Cancels a job that is queued or running. Its runners are returned to the pool once in-flight commands have been
+    /// killed. Returns `false` if there is no such job, or if it has already finished.
+    pub fn cancel(&self, job_id: JobId) -> Result<bool> {
+        let cancellation: Arc<Cancellation> = match self.lock_cancellations()?.get(&job_id) {
+            Some(cancellation) => cancellation.clone(),
+            None => return Ok(false),
+        };
+        cancellation.cancel();
+        log::info!("cancelling job (id={:?})", job_id);
+
+        // Wake up the job if it is waiting for runners. The lock is held so that the wake up is not missed.
+        let _pool: MutexGuard<'_, RunnerPool> = self.lock_runners()?;
+        self.runners_available.notify_all();
+
+        Ok(true)
+    }
+
+    /// Plans a job without running it. Workers are placed on idle runners if possible, as if the job were submitted
+    /// now, and on busy runners otherwise. Fails if the job references unknown secrets, or if no set of runners that
+    /// are online and not draining can satisfy it.
+    pub fn plan(&self, job_name: &str, mut job: Job) -> Result<JobPlan> {
+        job.set_secrets(self.secrets.resolve(job.secret_names())?);
+        let candidates: Vec<(usize, HashMap<String, String>)> = {
+            let pool: MutexGuard<'_, RunnerPool> = self.lock_runners()?;
+            Self::available_runners(&pool.idle, &pool.draining)
+        };
+        let (placement, ready): (HashMap<usize, String>, bool) =
+            match Self::build_placement(&candidates, job.requirements()) {
+                Some(placement) => (placement, true),
+                None => (self.place_job_on_serviceable_runners(&job)?, false),
+            };
+        Ok(JobPlan::new(
+            job_name,
+            job,
+            Self::assign_runners(&placement, &self.addrs),
+            ready,
+        ))
+    }
+
+    /// Plans a job on runners that are not managed by a [Scheduler], as if all of them were idle. Runners are not
+    /// connected to.
+    pub fn plan_offline(
+        runners: &Vec<Mutex<Box<dyn Runner>>>,
+        secrets: &SecretStore,
+        job_name: &str,
+        mut job: Job,
+    ) -> Result<JobPlan> {
+        job.set_secrets(secrets.resolve(job.secret_names())?);
+        let placement: HashMap<usize, String> = Self::place_job(&Self::describe_runners(runners), &job)?;
+        Ok(JobPlan::new(
+            job_name,
+            job,
+            Self::assign_runners(&placement, &Self::runner_addrs(runners)),
+            true,
+        ))
+    }
+
+    /// Places the workers of a job on the runners that may take new jobs, whether or not they are busy. Fails if these
+    /// runners cannot satisfy the job, in which case it would otherwise wait until they are brought back.
+    fn place_job_on_serviceable_runners(&self, job: &Job) -> Result<HashMap<usize, String>> {
+        match Self::build_placement(&self.serviceable_runners()?, job.requirements()) {
+            Some(placement) => Ok(placement),
+            None => {
+                let msg: String = format!(
+                    "no runners that are online and not draining can satisfy job requirements (requirements={:?})",
+                    job.requirements()
+                );
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Places the workers of a job on a set of runners. Fails if the runners cannot satisfy the job.
+    fn place_job(runners: &Vec<(usize, HashMap<String, String>)>, job: &Job) -> Result<HashMap<usize, String>> {
+        match Self::build_placement(runners, job.requirements()) {
+            Some(placement) => Ok(placement),
+            None => {
+                let msg: String = format!(
+                    "no runners can satisfy job requirements (requirements={:?})",
+                    job.requirements()
+                );
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Describes the runner that a placement assigns to each worker.
+    fn assign_runners(
+        placement: &HashMap<usize, String>,
+        addrs: &HashMap<usize, (String, String)>,
+    ) -> Vec<RunnerAssignment> {
+        let mut assignments: Vec<RunnerAssignment> = Vec::new();
+        for (runner_id, worker_name) in placement {
+            if let Some((addr, local_addr)) = addrs.get(runner_id) {
+                assignments.push(RunnerAssignment::new(worker_name, *runner_id, addr, local_addr));
+            }
+        }
+        assignments
+    }
+
+    /// Runs a job and records its outcome in the registry.
+    fn execute(&self, job_id: JobId, job: Job, sink: Option<Sender<String>>, cancellation: Arc<Cancellation>) {
+        // Jobs that test a commit report their status back to it.
+        let target: Option<CommitTarget> = Notifier::target(job.env());
+        self.notify(
+            &target,
+            job_id,
+            CommitState::Pending,
+            &format!("job {} is running", job_id),
+        );
+
+        let result: Result<()> = match self.run(job_id, job, sink.clone(), &cancellation) {
+            // Jobs that were cancelled keep the actions that they ran before being cancelled. Jobs that were cancelled
+            // too late to skip or kill any of their actions finished normally.
+            Ok(outcome) if cancellation.is_cancelled() && !(outcome.passed && outcome.complete) => {
+                log::info!("job cancelled (id={:?})", job_id);
+                Worker::send_line(&sink, format!("[job] id={} state={}", job_id, JobState::Cancelled));
+                self.notify(
+                    &target,
+                    job_id,
+                    CommitState::Error,
+                    &format!("job {} cancelled", job_id),
+                );
+                self.registry
+                    .set_finished(job_id, JobState::Cancelled, outcome.actions, None)
+            },
+            Err(e) if cancellation.is_cancelled() => {
+                log::info!("job cancelled (id={:?}, e={:?})", job_id, e);
+                Worker::send_line(&sink, format!("[job] id={} state={}", job_id, JobState::Cancelled));
+                self.notify(
+                    &target,
+                    job_id,
+                    CommitState::Error,
+                    &format!("job {} cancelled", job_id),
+                );
+                self.registry
+                    .set_finished(job_id, JobState::Cancelled, Vec::new(), Some(e.to_string()))
+            },
+            Ok(outcome) => {
+                let state: JobState = if outcome.passed {
+                    JobState::Succeeded
+                } else {
+                    JobState::Failed
+                };
+                log::info!("job finished (id={:?}, state={})", job_id, state);
+                Worker::send_line(&sink, format!("[job] id={} state={}", job_id, state));
+                let commit_state: CommitState = if outcome.passed {
+                    CommitState::Success
+                } else {
+                    CommitState::Failure
+                };
+                self.notify(&target, job_id, commit_state, &format!("job {} {}", job_id, state));
+                self.registry.set_finished(job_id, state, outcome.actions, None)
+            },
+            Err(e) => {
+                log::error!("job failed (id={:?}, e={:?})", job_id, e);
+                self.notify(
+                    &target,
+                    job_id,
+                    CommitState::Error,
+                    &format!("job {} failed: {}", job_id, e),
+                );
+                Worker::send_line(
+                    &sink,
+                    format!("[job] id={} state={} error={}", job_id, JobState::Failed, e),
+                );
+                self.registry
+                    .set_finished(job_id, JobState::Failed, Vec::new(), Some(e.to_string()))
+            },
+        };
+
+        if let Err(e) = result {
+            log::error!("failed to record job outcome (id={:?}, e={:?})", job_id, e);
+        }
+        match self.lock_cancellations() {
+            Ok(mut cancellations) => {
+                cancellations.remove(&job_id);
+            },
+            Err(e) => log::warn!("failed to forget cancellation handle (id={:?}, e={:?})", job_id, e),
+        }
 
-    pub fn new(runners: Vec<Mutex<Runner>>) -> Self {
-        Self {
-            runners: Mutex::new(runners),
+        // Persist the outcome of the job. Once it is in the history, the job no longer needs to be kept in memory.
+        match self.registry.get(job_id) {
+            Ok(Some(record)) => {
+                match self.history.append(&record) {
+                    Ok(()) => {
+                        if let Err(e) = self.registry.forget(job_id) {
+                            log::warn!("failed to forget job (id={:?}, e={:?})", job_id, e);
+                        }
+                    },
+                    Err(e) => log::error!("failed to record job in history (id={:?}, e={:?})", job_id, e),
+                }
+                for format in [ReportFormat::Json, ReportFormat::Junit] {
+                    let result: Result<()> = format
+                        .render(std::slice::from_ref(&record))
+                        .and_then(|report| self.history.save_report(job_id, format, &report));
+                    if let Err(e) = result {
+                        log::error!("failed to store job report (id={:?}, e={:?})", job_id, e);
+                    }
+                }
+            },
+            Ok(None) => log::warn!("no such job (id={:?})", job_id),
+            Err(e) => log::error!("failed to retrieve job record (id={:?}, e={:?})", job_id, e),
         }
     }
 
-    pub fn run(&self, job: Job) -> Result<Vec<String>> {
+    fn run(
+        &self,
+        job_id: JobId,
+        job: Job,
+        sink: Option<Sender<String>>,
+        cancellation: &Cancellation,
+    ) -> Result<JobOutcome> {
         // Schedule tasks.
         let mut schedule: Vec<Worker> = {
-            let barriers: Arc<Vec<Barrier>> = Self::create_barriers(&job.barrier_participants());
-            let num_workers: usize = job.num_workers();
-            let runners: Vec<Mutex<Runner>> = loop {
-                if let Ok(runners) = self.allocate_runners(num_workers) {
-                    break runners;
-                }
+            let barriers: Arc<Vec<Rendezvous>> = Self::create_barriers(job.barrier_participants());
+            let requirements: HashMap<String, HashMap<String, String>> = job.requirements().clone();
 
-                sleep(Duration::from_millis(Self::SLEEP_INTERVAL));
-            };
-            let placement: HashMap<usize, String> = self.build_placement(&runners, job.get_task_names());
-            Self::schedule_tasks(job, runners, placement, barriers)
+            // Fail fast if no set of runners that may take new jobs can satisfy the job.
+            self.place_job_on_serviceable_runners(&job)?;
+
+            let (runners, placement): Allocation = self.allocate_runners(
+                job_id,
+                &requirements,
+                job.priority(),
+                job.allocation_timeout(),
+                cancellation,
+            )?;
+            let mut assigned: BTreeMap<String, String> = BTreeMap::new();
+            for runner in &runners {
+                if let Ok(runner) = runner.lock() {
+                    if let Some(worker_name) = placement.get(&runner.id()) {
+                        assigned.insert(worker_name.to_string(), runner.addr().to_string());
+                    }
+                }
+            }
+            if let Err(e) = self.registry.set_running(job_id, assigned) {
+                log::warn!("failed to mark job as running (id={:?}, e={:?})", job_id, e);
+            }
+            let artifacts_dir: PathBuf = self.artifacts.job_dir(job_id);
+            Self::schedule_tasks(job, runners, placement, barriers, sink, artifacts_dir, cancellation)
         };
 
-        thread::scope(|s| {
+        let passed: bool = thread::scope(|s| {
             let mut threads = Vec::new();
             log::trace!("spawning {} threads", schedule.len());
 
@@ -59,44 +588,47 @@ impl Scheduler {
                 let scheduler_worker: &Worker = &schedule[i];
 
                 let thread: ScopedJoinHandle<Result<(), anyhow::Error>> = s.spawn(move || -> Result<()> {
-                    while let Some(job_entry) = scheduler_worker.pop_task()? {
-                        match job_entry {
-                            Task::Action(mut task) => {
-                                scheduler_worker.run(&mut task)?;
-                                scheduler_worker.push_task(task)?;
-                                continue;
-                            },
-                            Task::Barrier(_) => {
-                                scheduler_worker.wait_others()?;
-                            },
-                        }
+                    let result: Result<()> = Self::run_worker(scheduler_worker, cancellation);
+
+                    // Wake up other workers that may be waiting for this one.
+                    if result.is_err() {
+                        scheduler_worker.abort();
                     }
 
-                    Ok(())
+                    result
                 });
 
                 log::trace!("spawned thread (id={:?})", thread.thread().id());
                 threads.push(thread);
             }
 
+            let mut passed: bool = true;
             for t in threads {
-                if let Err(e) = t.join() {
-                    log::error!("failed to join thread (error={:?})", e);
+                match t.join() {
+                    Ok(Ok(())) => {},
+                    Ok(Err(e)) => {
+                        log::error!("worker failed (error={:?})", e);
+                        passed = false;
+                    },
+                    Err(e) => {
+                        log::error!("failed to join thread (error={:?})", e);
+                        passed = false;
+                    },
                 }
             }
+            passed
         });
 
-        // Collect outputs.
-        let output: Vec<String> = {
-            let mut job_output: Vec<String> = Vec::new();
+        // Collect actions, and check if any of them was skipped.
+        let complete: bool = schedule.iter().all(|worker| !worker.has_pending_actions());
+        let actions: Vec<Action> = {
+            let mut job_actions: Vec<Action> = Vec::new();
             for scheduler_worker in &schedule {
-                if let Ok(worker_output) = scheduler_worker.collect_output() {
-                    for line in &worker_output {
-                        job_output.push(line.to_string());
-                    }
+                if let Ok(mut worker_actions) = scheduler_worker.collect_actions() {
+                    job_actions.append(&mut worker_actions);
                 }
             }
-            job_output
+            job_actions
         };
 
         // Return workers to the list of idle workers.
@@ -111,8 +643,14 @@ impl Scheduler {
                             continue;
                         },
                     };
-                    match self.runners.lock() {
-                        Ok(mut runners) => runners.push(worker),
+                    match self.lock_runners() {
+                        Ok(mut pool) => {
+                            // The runner may have lost its connection while running the job.
+                            if let Ok(runner) = worker.lock() {
+                                pool.health.insert(runner.id(), runner.state());
+                            }
+                            pool.idle.push(worker);
+                        },
                         Err(e) => {
                             let msg: String = format!("failed to lock list of runners (e={:?})", e);
                             log::warn!("{}", &msg);
@@ -126,69 +664,305 @@ impl Scheduler {
             }
         }
 
-        Ok(output)
+        // Wake up jobs that are waiting for runners.
+        self.runners_available.notify_all();
+
+        Ok(JobOutcome {
+            passed,
+            complete,
+            actions,
+        })
+    }
+
+    /// Posts the status of a job to the commit that it tests, if any. Failures to do so do not affect the job.
+    fn notify(&self, target: &Option<CommitTarget>, job_id: JobId, state: CommitState, description: &str) {
+        if let (Some(notifier), Some(target)) = (&self.notifier, target) {
+            if let Err(e) = notifier.notify(target, job_id, state, description) {
+                log::warn!("failed to notify commit status (id={:?}, e={:?})", job_id, e);
+            }
+        }
+    }
+
+    /// Runs all tasks that are scheduled on a worker. Remaining tasks are skipped if the job is cancelled.
+    fn run_worker(scheduler_worker: &Worker, cancellation: &Cancellation) -> Result<()> {
+        while !cancellation.is_cancelled() {
+            let job_entry: Task = match scheduler_worker.pop_task()? {
+                Some(job_entry) => job_entry,
+                None => break,
+            };
+            match job_entry {
+                Task::Action(mut task) => {
+                    // Record the task before checking if it succeeded, so that we do not lose its output.
+                    let result: Result<()> = scheduler_worker.run(&mut task, cancellation);
+                    scheduler_worker.push_task(task)?;
+                    result?;
+                },
+                Task::Barrier(barrier_index) => {
+                    scheduler_worker.wait_others(barrier_index)?;
+                },
+            }
+        }
+
+        Ok(())
     }
 
-    fn create_barriers(barrier_participants: &Vec<usize>) -> Arc<Vec<Barrier>> {
+    fn create_barriers(barrier_participants: &Vec<usize>) -> Arc<Vec<Rendezvous>> {
         let mut barriers = Vec::new();
         for num_participants in barrier_participants {
-            barriers.push(Barrier::new(*num_participants));
+            barriers.push(Rendezvous::new(*num_participants));
         }
         Arc::new(barriers)
     }
 
-    fn build_placement(
-        &self,
-        runners: &Vec<Mutex<Runner>>,
-        mut task_queue_keys: Vec<String>,
-    ) -> HashMap<usize, String> {
-        assert_eq!(
-            runners.len(),
-            task_queue_keys.len(),
-            "number of runners must match number of task queues"
-        );
+    /// Returns the identifiers and labels of a list of runners.
+    fn describe_runners(runners: &Vec<Mutex<Box<dyn Runner>>>) -> Vec<(usize, HashMap<String, String>)> {
+        let mut descriptions: Vec<(usize, HashMap<String, String>)> = Vec::new();
+        for runner in runners {
+            match runner.lock() {
+                Ok(runner) => descriptions.push((runner.id(), runner.labels().clone())),
+                Err(e) => log::warn!("failed to lock runner (e={:?})", e),
+            }
+        }
+        descriptions
+    }
 
-        let mut worker_names: HashMap<usize, String> = HashMap::new();
+    /// Returns the remote and local addresses of a list of runners, keyed by runner identifier.
+    fn runner_addrs(runners: &Vec<Mutex<Box<dyn Runner>>>) -> HashMap<usize, (String, String)> {
+        let mut addrs: HashMap<usize, (String, String)> = HashMap::new();
         for runner in runners {
-            if let Ok(runner) = &runner.lock() {
-                worker_names.insert(runner.id(), task_queue_keys.pop().unwrap());
+            match runner.lock() {
+                Ok(runner) => {
+                    addrs.insert(
+                        runner.id(),
+                        (runner.addr().to_string(), runner.local_addr().to_string()),
+                    );
+                },
+                Err(e) => log::warn!("failed to lock runner (e={:?})", e),
+            }
+        }
+        addrs
+    }
+
+    /// Assigns a distinct runner to each worker, such that every runner has all labels that are required by its
+    /// worker. Returns a map from runner identifiers to worker names, or `None` if there is no such assignment.
+    fn build_placement(
+        runners: &Vec<(usize, HashMap<String, String>)>,
+        requirements: &HashMap<String, HashMap<String, String>>,
+    ) -> Option<HashMap<usize, String>> {
+        // Visit workers in a deterministic order.
+        let mut worker_names: Vec<&String> = requirements.keys().collect();
+        worker_names.sort();
+
+        // Find a matching between workers and runners using augmenting paths.
+        let mut assignment: Vec<Option<usize>> = vec![None; runners.len()];
+        for worker in 0..worker_names.len() {
+            let mut visited: Vec<bool> = vec![false; runners.len()];
+            if !Self::assign_worker(
+                worker,
+                &worker_names,
+                runners,
+                requirements,
+                &mut assignment,
+                &mut visited,
+            ) {
+                return None;
+            }
+        }
+
+        let mut placement: HashMap<usize, String> = HashMap::new();
+        for (runner, worker) in assignment.iter().enumerate() {
+            if let Some(worker) = worker {
+                placement.insert(runners[runner].0, worker_names[*worker].to_string());
             }
         }
 
-        worker_names
+        Some(placement)
     }
 
-    fn allocate_runners(&self, num_workers: usize) -> Result<Vec<Mutex<Runner>>> {
-        log::trace!("allocate_runners(): num_workers={}", num_workers);
-        // Attempt to lock the list of runners and check if we succeeded.
-        match self.runners.lock() {
-            // We succeeded to lock the list of runners.
-            Ok(mut guard) => {
-                if guard.len() < num_workers {
-                    let msg: String = format!(
-                        "not enough runners available (have={}, need={})",
-                        guard.len(),
-                        num_workers
-                    );
-                    log::error!("{}", &msg);
-                    return Err(anyhow::anyhow!("{}", &msg));
-                }
+    /// Attempts to assign a runner to a worker, possibly reassigning other workers.
+    fn assign_worker(
+        worker: usize,
+        worker_names: &Vec<&String>,
+        runners: &Vec<(usize, HashMap<String, String>)>,
+        requirements: &HashMap<String, HashMap<String, String>>,
+        assignment: &mut Vec<Option<usize>>,
+        visited: &mut Vec<bool>,
+    ) -> bool {
+        let required: &HashMap<String, String> = &requirements[worker_names[worker]];
+        for runner in 0..runners.len() {
+            let labels: &HashMap<String, String> = &runners[runner].1;
+            let satisfies: bool = required.iter().all(|(key, value)| labels.get(key) == Some(value));
+            if !satisfies || visited[runner] {
+                continue;
+            }
+            visited[runner] = true;
 
-                let mut workers: Vec<Mutex<Runner>> = Vec::new();
-                while let Some(worker) = guard.pop() {
-                    workers.push(worker);
+            let reassigned: bool = match assignment[runner] {
+                Some(other) => Self::assign_worker(other, worker_names, runners, requirements, assignment, visited),
+                None => true,
+            };
+            if reassigned {
+                assignment[runner] = Some(worker);
+                return true;
+            }
+        }
 
-                    // Finished allocating all workers.
-                    if workers.len() == num_workers {
-                        break;
-                    }
+        false
+    }
+
+    /// Waits in queue for idle runners that satisfy the requirements of a job, and allocates them. Jobs are served in
+    /// order of priority and then in order of arrival, and only the job at the head of the queue may allocate runners,
+    /// so that large jobs are not starved by smaller ones. Returns the allocated runners along with their placement.
+    /// Fails if the job is cancelled while it waits.
+    fn allocate_runners(
+        &self,
+        job_id: JobId,
+        requirements: &HashMap<String, HashMap<String, String>>,
+        priority: i64,
+        timeout: Option<Duration>,
+        cancellation: &Cancellation,
+    ) -> Result<Allocation> {
+        log::trace!(
+            "allocate_runners(): job_id={:?}, requirements={:?}, priority={:?}, timeout={:?}",
+            job_id,
+            requirements,
+            priority,
+            timeout
+        );
+        let deadline: Option<Instant> = timeout.map(|timeout| Instant::now() + timeout);
+
+        // Enter the wait queue.
+        let mut pool: MutexGuard<'_, RunnerPool> = self.lock_runners()?;
+        let sequence: u64 = pool.next_sequence;
+        pool.next_sequence += 1;
+        let position: usize = pool
+            .waiting
+            .iter()
+            .position(|ticket| ticket.priority < priority)
+            .unwrap_or(pool.waiting.len());
+        pool.waiting.insert(
+            position,
+            Ticket {
+                job_id,
+                priority,
+                sequence,
+            },
+        );
+
+        loop {
+            if cancellation.is_cancelled() {
+                pool.waiting.retain(|ticket| ticket.sequence != sequence);
+                self.runners_available.notify_all();
+                let msg: String = format!("cancelled while waiting for runners (job_id={:?})", job_id);
+                log::warn!("{}", msg);
+                anyhow::bail!(msg);
+            }
+
+            // Only the head of the queue may allocate runners.
+            if pool.waiting.first().map(|ticket| ticket.sequence) == Some(sequence) {
+                let RunnerPool {
+                    idle,
+                    waiting,
+                    draining,
+                    ..
+                } = &mut *pool;
+                if let Some(allocation) = Self::take_runners(idle, draining, requirements) {
+                    waiting.remove(0);
+                    self.runners_available.notify_all();
+                    return Ok(allocation);
                 }
+            }
+
+            let wait_result: Result<MutexGuard<'_, RunnerPool>, String> = match deadline {
+                Some(deadline) => {
+                    let now: Instant = Instant::now();
+                    if now >= deadline {
+                        pool.waiting.retain(|ticket| ticket.sequence != sequence);
+                        self.runners_available.notify_all();
+                        let msg: String = format!("timed out waiting for runners (job_id={:?})", job_id);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    }
+                    self.runners_available
+                        .wait_timeout(pool, deadline - now)
+                        .map(|(pool, _)| pool)
+                        .map_err(|e| e.to_string())
+                },
+                None => self.runners_available.wait(pool).map_err(|e| e.to_string()),
+            };
 
-                Ok(workers)
+            pool = match wait_result {
+                Ok(pool) => pool,
+                Err(e) => {
+                    let msg: String = format!("failed to wait for runners (e={:?})", e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+        }
+    }
+
+    /// Takes idle runners that satisfy the requirements of a job out of the pool, if possible. Runners that are
+    /// offline or draining are skipped.
+    fn take_runners(
+        idle: &mut Vec<Mutex<Box<dyn Runner>>>,
+        draining: &HashSet<usize>,
+        requirements: &HashMap<String, HashMap<String, String>>,
+    ) -> Option<Allocation> {
+        let candidates: Vec<(usize, HashMap<String, String>)> = Self::available_runners(idle, draining);
+        let placement: HashMap<usize, String> = Self::build_placement(&candidates, requirements)?;
+
+        let mut runners: Vec<Mutex<Box<dyn Runner>>> = Vec::new();
+        let mut i: usize = 0;
+        while i < idle.len() {
+            let placed: bool = match idle[i].lock() {
+                Ok(runner) => placement.contains_key(&runner.id()),
+                Err(_) => false,
+            };
+            if placed {
+                runners.push(idle.remove(i));
+            } else {
+                i += 1;
+            }
+        }
+
+        Some((runners, placement))
+    }
+
+    /// Returns the identifiers and labels of the idle runners that may be allocated to jobs. Runners that are offline or
+    /// draining are skipped.
+    fn available_runners(
+        idle: &Vec<Mutex<Box<dyn Runner>>>,
+        draining: &HashSet<usize>,
+    ) -> Vec<(usize, HashMap<String, String>)> {
+        let mut candidates: Vec<(usize, HashMap<String, String>)> = Vec::new();
+        for runner in idle.iter() {
+            match runner.lock() {
+                Ok(runner) if runner.state() == RunnerState::Online && !draining.contains(&runner.id()) => {
+                    candidates.push((runner.id(), runner.labels().clone()))
+                },
+                Ok(_) => {},
+                Err(e) => log::warn!("failed to lock runner (e={:?})", e),
+            }
+        }
+        candidates
+    }
+
+    fn lock_cancellations(&self) -> Result<MutexGuard<'_, HashMap<JobId, Arc<Cancellation>>>> {
+        match self.cancellations.lock() {
+            Ok(cancellations) => Ok(cancellations),
+            Err(e) => {
+                let msg: String = format!("failed to lock cancellation handles (e={:?})", e);
+                log::error!("{}", &msg);
+                Err(anyhow::anyhow!("{}", &msg))
             },
-            // We failed to lock the list of runners.
+        }
+    }
+
+    fn lock_runners(&self) -> Result<MutexGuard<'_, RunnerPool>> {
+        match self.runners.lock() {
+            Ok(pool) => Ok(pool),
             Err(e) => {
-                // Log an error message and return an error.
                 let msg: String = format!("failed to lock list of runners (e={:?})", e);
                 log::error!("{}", &msg);
                 Err(anyhow::anyhow!("{}", &msg))
@@ -198,10 +972,17 @@ impl Scheduler {
 
     fn schedule_tasks(
         mut job: Job,
-        mut runners: Vec<Mutex<Runner>>,
+        mut runners: Vec<Mutex<Box<dyn Runner>>>,
         placement: HashMap<usize, String>,
-        barriers: Arc<Vec<Barrier>>,
+        barriers: Arc<Vec<Rendezvous>>,
+        sink: Option<Sender<String>>,
+        artifacts_dir: PathBuf,
+        cancellation: &Cancellation,
     ) -> Vec<Worker> {
+        let dependencies: Arc<DependencyTracker> = Arc::new(DependencyTracker::new());
+        // Wake up workers that are blocked on each other if the job is cancelled.
+        cancellation.attach(barriers.clone(), dependencies.clone());
+
         // Check if the number of required runners matches the number of allocated runners.
         assert_eq!(
             runners.len(),
@@ -229,9 +1010,7 @@ impl Scheduler {
                     .expect("numbers of allocated runners should match the number of required workers")
                     .to_string();
 
-                let env_var_prefix: String = Config::env_var_prefix();
-                let key: String = format!("{}{}", env_var_prefix, worker_name.to_uppercase());
-                job.append_env(key, local_addr);
+                job.append_env(Job::worker_addr_var(&worker_name), local_addr);
             }
         }
 
@@ -241,8 +1020,16 @@ impl Scheduler {
             let worker_name: &String = placement
                 .get(&runner_id)
                 .expect("numbers of allocated runners should match the number of required workers");
-            let runner: Arc<Mutex<Runner>> = Arc::new(runner);
-            let worker: Worker = match Worker::new(runner, &worker_name, &mut job, barriers.clone()) {
+            let runner: Arc<Mutex<Box<dyn Runner>>> = Arc::new(runner);
+            let worker: Worker = match Worker::new(
+                runner,
+                worker_name,
+                &mut job,
+                barriers.clone(),
+                dependencies.clone(),
+                sink.clone(),
+                artifacts_dir.clone(),
+            ) {
                 Ok(worker) => worker,
                 Err(e) => {
                     let msg: String = format!("failed to create worker (e={:?})", e);
@@ -0,0 +1,172 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::config::Config;
+use anyhow::Result;
+use std::{collections::HashMap, fs, os::unix::fs::PermissionsExt};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Store of secrets that jobs may reference by name. Secrets are never recorded as job parameters, so they do not
+/// show up in the registry, in reports, or in the history.
+#[derive(Default)]
+pub struct SecretStore {
+    secrets: HashMap<String, String>,
+}
+
+/// Redacts the values of secrets from lines of output.
+#[derive(Default)]
+pub struct SecretMask {
+    /// Values to redact, longest first, so that a value that contains another one is redacted as a whole.
+    values: Vec<String>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl SecretStore {
+    /// Instantiates a new, empty, [SecretStore].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a secret whose value is read from an environment variable of the orchestrator.
+    pub fn insert_from_env(&mut self, name: &str, var: &str) -> Result<()> {
+        match std::env::var(var) {
+            Ok(value) => self.insert(name, value),
+            Err(e) => {
+                let msg: String = format!("failed to read secret from environment (name={:?}, e={:?})", name, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Adds a secret whose value is read from a file. The file must be readable only by the owner, so that other
+    /// users of the orchestrator host cannot read the secret. A trailing newline is stripped.
+    pub fn insert_from_file(&mut self, name: &str, path: &str) -> Result<()> {
+        let mode: u32 = match fs::metadata(path) {
+            Ok(metadata) => metadata.permissions().mode(),
+            Err(e) => {
+                let msg: String = format!("failed to stat secret file (name={:?}, e={:?})", name, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        if mode & 0o077 != 0 {
+            let msg: String = format!(
+                "secret file must be accessible only by its owner (name={:?}, path={:?}, mode={:o})",
+                name,
+                path,
+                mode & 0o777
+            );
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        match fs::read_to_string(path) {
+            Ok(value) => self.insert(name, value.trim_end_matches(['\r', '\n']).to_string()),
+            Err(e) => {
+                let msg: String = format!("failed to read secret file (name={:?}, e={:?})", name, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    fn insert(&mut self, name: &str, value: String) -> Result<()> {
+        // Short values would be redacted wherever they happen to show up in output, mangling it.
+        if value.trim().len() < SecretMask::MIN_LENGTH {
+            let msg: String = format!(
+                "secret is too short to be redacted from output (name={:?}, min_length={})",
+                name,
+                SecretMask::MIN_LENGTH
+            );
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+        if value.lines().any(Self::is_unmasked_line) {
+            log::warn!(
+                "some lines of multi-line secret are too short to be redacted on their own (name={:?})",
+                name
+            );
+        }
+        if self.secrets.insert(name.to_string(), value).is_some() {
+            let msg: String = format!("duplicate secret (name={:?})", name);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+        log::info!("loaded secret (name={:?})", name);
+        Ok(())
+    }
+
+    /// Checks if a line of a multi-line secret is left out of a [SecretMask], for being too short.
+    fn is_unmasked_line(line: &str) -> bool {
+        let line: &str = line.trim();
+        !line.is_empty() && line.len() < SecretMask::MIN_LENGTH
+    }
+
+    /// Resolves the secrets that a job references. Returns a map from environment variable names to values.
+    pub fn resolve(&self, names: &[String]) -> Result<HashMap<String, String>> {
+        let mut env: HashMap<String, String> = HashMap::new();
+        for name in names {
+            match self.secrets.get(name) {
+                Some(value) => {
+                    env.insert(
+                        format!("{}{}", Config::env_var_prefix(), name.to_uppercase()),
+                        value.clone(),
+                    );
+                },
+                None => {
+                    let msg: String = format!("unknown secret (name={:?})", name);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            }
+        }
+        Ok(env)
+    }
+}
+
+impl SecretMask {
+    /// Replacement for the values of secrets.
+    const MASK: &'static str = "***";
+    /// Minimum length of the values that are redacted.
+    pub const MIN_LENGTH: usize = 4;
+
+    /// Instantiates a new [SecretMask] that redacts the given values. Output is redacted line by line, so each line
+    /// of a multi-line value is also redacted on its own. Values shorter than [SecretMask::MIN_LENGTH] are ignored.
+    pub fn new<'a>(values: impl Iterator<Item = &'a String>) -> Self {
+        let mut values: Vec<String> = values
+            .flat_map(|value| {
+                let lines: Vec<String> = if value.contains('\n') {
+                    value.lines().map(|line| line.trim().to_string()).collect()
+                } else {
+                    Vec::new()
+                };
+                std::iter::once(value.clone()).chain(lines)
+            })
+            .filter(|value| value.len() >= Self::MIN_LENGTH)
+            .collect();
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+        Self { values }
+    }
+
+    /// Redacts the values of secrets from a line.
+    pub fn apply(&self, line: &str) -> String {
+        let mut line: String = line.to_string();
+        for value in &self.values {
+            if line.contains(value.as_str()) {
+                line = line.replace(value.as_str(), Self::MASK);
+            }
+        }
+        line
+    }
+}
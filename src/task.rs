@@ -14,7 +14,8 @@ use std::collections::VecDeque;
 
 #[derive(Debug)]
 pub enum Task {
-    Action(Action),
+    Action(Box<Action>),
+    /// Barrier, identified by its index in the job.
     Barrier(usize),
 }
 
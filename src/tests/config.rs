@@ -0,0 +1,130 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::{sshd::FakeSshd, ScratchDir};
+use crate::{
+    config::Config,
+    runner::{Runner, RunnerState},
+    web::auth::{Authenticator, Scope},
+};
+use anyhow::Result;
+use http::Request;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Mutex};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+#[test]
+fn get_workers_parses_ssh_and_local_workers() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let dir: ScratchDir = ScratchDir::new("config")?;
+    let config_path: String = dir.write(
+        "config.yaml",
+        &format!(
+            "workers:
+  - hostname: 127.0.0.1
+    port: {}
+    local-address: 10.0.0.1
+    labels:
+      nic: mlx5
+      cores: 16
+  - type: local
+",
+            sshd.port()
+        ),
+    )?;
+
+    let workers: Vec<Mutex<Box<dyn Runner>>> = Config::new(&config_path)?.get_workers(sshd.credentials())?;
+    assert_eq!(workers.len(), 2);
+
+    let mut ssh = workers[0].lock().unwrap();
+    assert_eq!(ssh.id(), 0);
+    assert_eq!(ssh.addr(), format!("127.0.0.1:{}", sshd.port()));
+    assert_eq!(ssh.local_addr(), "10.0.0.1");
+    assert_eq!(
+        ssh.labels(),
+        &HashMap::from([
+            ("nic".to_string(), "mlx5".to_string()),
+            ("cores".to_string(), "16".to_string()),
+        ])
+    );
+    // Workers are connected to lazily.
+    assert_eq!(ssh.state(), RunnerState::Offline);
+    assert_eq!(ssh.probe(), RunnerState::Online);
+
+    let local = workers[1].lock().unwrap();
+    assert_eq!(local.id(), 1);
+    assert_eq!(local.local_addr(), "127.0.0.1");
+    assert!(local.labels().is_empty());
+    assert_eq!(local.state(), RunnerState::Online);
+    Ok(())
+}
+
+#[test]
+fn get_workers_rejects_malformed_workers() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let dir: ScratchDir = ScratchDir::new("config")?;
+    for worker in [
+        "type: docker",
+        "port: 22\n    local-address: 10.0.0.1",
+        "hostname: 127.0.0.1\n    local-address: 10.0.0.1",
+        "hostname: 127.0.0.1\n    port: 22",
+        "type: local\n    labels: [nic]",
+    ] {
+        let config_path: String = dir.write("config.yaml", &format!("workers:\n  - {}\n", worker))?;
+        let config: Config = Config::new(&config_path)?;
+        assert!(config.get_workers(sshd.credentials()).is_err(), "{:?}", worker);
+    }
+    Ok(())
+}
+
+#[test]
+fn api_tokens_identify_clients_by_certificate() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("config")?;
+    let certificate: &[u8] = b"certificate of the client";
+    let tokens: String = format!(
+        "tokens:
+  - name: ci
+    sha256: {}
+    scopes: [submit]
+  - name: bot
+    certificate-sha256: {}
+    scopes: [submit]
+",
+        hex::encode(Sha256::digest(b"bearer")),
+        hex::encode(Sha256::digest(certificate))
+    );
+
+    // Certificates are only verified if there is an authority to verify them against.
+    let config_path: String = dir.write("config.yaml", &tokens)?;
+    assert!(Config::new(&config_path)?.api_tokens().is_err());
+
+    let config_path: String = dir.write(
+        "config.yaml",
+        &format!(
+            "server:\n  - tls:\n      certificate: cert.pem\n      key: key.pem\n      client-ca: ca.pem\n{}",
+            tokens
+        ),
+    )?;
+    let tokens = Config::new(&config_path)?.api_tokens()?.expect("tokens are declared");
+    let authenticator: Authenticator = Authenticator::new(tokens, |_| Some(Scope::Submit));
+
+    let anonymous: Request<Vec<u8>> = Request::builder().uri("/run").body(Vec::new())?;
+    assert!(authenticator.authorize(&anonymous, Some(certificate)).is_ok());
+    assert!(authenticator.authorize(&anonymous, Some(b"other certificate")).is_err());
+    assert!(authenticator.authorize(&anonymous, None).is_err());
+    // Bearer tokens are still accepted, but cannot be passed off as certificates.
+    assert!(authenticator.authorize(&anonymous, Some(b"bearer")).is_err());
+    let bearer: Request<Vec<u8>> = Request::builder()
+        .uri("/run")
+        .header("Authorization", "Bearer bearer")
+        .body(Vec::new())?;
+    assert!(authenticator.authorize(&bearer, None).is_ok());
+    Ok(())
+}
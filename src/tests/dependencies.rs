@@ -0,0 +1,67 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::dependencies::DependencyTracker;
+use anyhow::Result;
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Waits on some actions of a [DependencyTracker] in the background.
+fn spawn_waiter(tracker: &Arc<DependencyTracker>, needs: &[&str]) -> JoinHandle<Result<()>> {
+    let tracker: Arc<DependencyTracker> = tracker.clone();
+    let needs: Vec<String> = needs.iter().map(|name| name.to_string()).collect();
+    thread::spawn(move || tracker.wait_for(&needs))
+}
+
+#[test]
+fn dependencies_release_once_all_needs_succeed() {
+    let tracker: Arc<DependencyTracker> = Arc::new(DependencyTracker::new());
+    let waiter: JoinHandle<Result<()>> = spawn_waiter(&tracker, &["build", "deploy"]);
+
+    tracker.complete("build", true);
+    thread::sleep(Duration::from_millis(100));
+    assert!(!waiter.is_finished());
+
+    tracker.complete("deploy", true);
+    assert!(waiter.join().expect("waiter should not panic").is_ok());
+    // Actions that need nothing do not wait.
+    assert!(tracker.wait_for(&[]).is_ok());
+}
+
+#[test]
+fn dependencies_fail_when_a_need_fails() {
+    let tracker: Arc<DependencyTracker> = Arc::new(DependencyTracker::new());
+    let waiter: JoinHandle<Result<()>> = spawn_waiter(&tracker, &["build", "deploy"]);
+
+    tracker.complete("deploy", false);
+
+    let error: String = waiter
+        .join()
+        .expect("waiter should not panic")
+        .expect_err("failed dependency should be reported")
+        .to_string();
+    assert!(error.contains("\"deploy\""), "{}", error);
+}
+
+#[test]
+fn dependencies_wake_up_waiters_when_cancelled() {
+    let tracker: Arc<DependencyTracker> = Arc::new(DependencyTracker::new());
+    let waiter: JoinHandle<Result<()>> = spawn_waiter(&tracker, &["build"]);
+
+    thread::sleep(Duration::from_millis(100));
+    tracker.cancel();
+
+    assert!(waiter.join().expect("waiter should not panic").is_err());
+    assert!(tracker.wait_for(&[]).is_err());
+}
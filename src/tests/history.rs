@@ -0,0 +1,28 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::ScratchDir;
+use crate::{history::HistoryStore, report::ReportFormat};
+use anyhow::Result;
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+#[test]
+fn reports_are_kept_apart_from_other_stores() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("history")?;
+    let nightly: HistoryStore = HistoryStore::new(&dir.path().join("nightly.jsonl").to_string_lossy(), None)?;
+    let pull_requests: HistoryStore = HistoryStore::new(&dir.path().join("pr.jsonl").to_string_lossy(), None)?;
+
+    nightly.save_report(0, ReportFormat::Json, "{\"jobs\":[]}")?;
+
+    assert!(dir.path().join("nightly.jsonl.reports").is_dir());
+    assert_eq!(nightly.report(0, ReportFormat::Json)?.as_deref(), Some("{\"jobs\":[]}"));
+    assert!(pull_requests.report(0, ReportFormat::Json)?.is_none());
+    Ok(())
+}
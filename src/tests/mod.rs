@@ -0,0 +1,83 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//! End-to-end tests. Runners connect to [sshd::FakeSshd], an in-process stand-in for an SSH server that runs commands
+//! on the local host, so the tests need neither remote machines nor a system-wide sshd.
+
+//======================================================================================================================
+// Modules
+//======================================================================================================================
+
+mod config;
+mod dependencies;
+mod history;
+mod notifier;
+mod rendezvous;
+mod report;
+mod runner;
+mod scheduler;
+mod sshd;
+mod validator;
+mod web;
+mod webhook;
+mod wire;
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use anyhow::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Directory that is removed when it goes out of scope.
+pub struct ScratchDir {
+    path: PathBuf,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl ScratchDir {
+    /// Creates a new, empty, [ScratchDir] in the temporary directory of the system.
+    pub fn new(name: &str) -> Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path: PathBuf = std::env::temp_dir().join(format!(
+            "demikernel-ci-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes a file into the target [ScratchDir] and returns its path.
+    pub fn write(&self, name: &str, contents: &str) -> Result<String> {
+        let path: PathBuf = self.path.join(name);
+        fs::write(&path, contents)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::notifier::Notifier;
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Stand-in for the REST API of GitHub, which records the requests that it receives and accepts all of them.
+pub struct MockApi {
+    port: u16,
+    requests: Receiver<MockRequest>,
+}
+
+/// Request received by a [MockApi].
+#[derive(Debug)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub authorization: Option<String>,
+    pub body: serde_json::Value,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl MockApi {
+    /// Starts a new [MockApi] on an ephemeral port of the loopback interface.
+    pub fn start() -> Result<Self> {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0")?;
+        let port: u16 = listener.local_addr()?.port();
+        let (tx, rx): (Sender<MockRequest>, Receiver<MockRequest>) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let request: Option<MockRequest> = stream.ok().and_then(|stream| Self::serve(stream).ok());
+                if let Some(request) = request {
+                    if tx.send(request).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        Ok(Self { port, requests: rx })
+    }
+
+    /// Returns the base URL of the target [MockApi].
+    pub fn url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// Returns the requests received so far.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.requests.try_iter().collect()
+    }
+
+    /// Reads a single request and answers it. Connections are closed after each request.
+    fn serve(stream: TcpStream) -> Result<MockRequest> {
+        let mut reader: BufReader<&TcpStream> = BufReader::new(&stream);
+        let mut line: String = String::new();
+        reader.read_line(&mut line)?;
+        let mut parts = line.split_whitespace();
+        let (method, path): (String, String) = match (parts.next(), parts.next()) {
+            (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+            _ => anyhow::bail!("malformed request line (line={:?})", line),
+        };
+
+        let mut length: usize = 0;
+        let mut authorization: Option<String> = None;
+        loop {
+            let mut header: String = String::new();
+            reader.read_line(&mut header)?;
+            let header: &str = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                match name.to_lowercase().as_str() {
+                    "content-length" => length = value.trim().parse()?,
+                    "authorization" => authorization = Some(value.trim().to_string()),
+                    _ => {},
+                }
+            }
+        }
+        let mut body: Vec<u8> = vec![0; length];
+        reader.read_exact(&mut body)?;
+
+        (&stream).write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}")?;
+        Ok(MockRequest {
+            method,
+            path,
+            authorization,
+            body: serde_json::from_slice(&body)?,
+        })
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+fn env(repository: &str, sha: &str) -> HashMap<String, String> {
+    HashMap::from([
+        ("DEMIKERNEL_REPOSITORY".to_string(), repository.to_string()),
+        ("DEMIKERNEL_SHA".to_string(), sha.to_string()),
+    ])
+}
+
+#[test]
+fn target_rejects_malformed_commits() {
+    let sha: &str = "0123456789abcdef0123456789ABCDEF01234567";
+    assert!(Notifier::target(&env("microsoft/demikernel", sha)).is_some());
+    assert!(Notifier::target(&env("my-org/my_repo.rs", sha)).is_some());
+
+    for (repository, sha) in [
+        ("a/b/../../..", sha),
+        ("../..", sha),
+        ("microsoft", sha),
+        ("microsoft/demikernel?x=1", sha),
+        ("microsoft/", sha),
+        ("microsoft/demikernel", "main"),
+        ("microsoft/demikernel", "../../user"),
+        ("microsoft/demikernel", &sha[1..]),
+    ] {
+        assert!(
+            Notifier::target(&env(repository, sha)).is_none(),
+            "repository={:?}, sha={:?}",
+            repository,
+            sha
+        );
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::rendezvous::Rendezvous;
+use anyhow::Result;
+use std::{
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Waits on a [Rendezvous] in the background.
+fn spawn_waiter(rendezvous: &Arc<Rendezvous>) -> JoinHandle<Result<()>> {
+    let rendezvous: Arc<Rendezvous> = rendezvous.clone();
+    thread::spawn(move || rendezvous.wait())
+}
+
+#[test]
+fn rendezvous_releases_once_all_participants_arrive() -> Result<()> {
+    let rendezvous: Arc<Rendezvous> = Arc::new(Rendezvous::new(3));
+    let waiters: Vec<JoinHandle<Result<()>>> = (0..2).map(|_| spawn_waiter(&rendezvous)).collect();
+
+    thread::sleep(Duration::from_millis(100));
+    assert!(waiters.iter().all(|waiter| !waiter.is_finished()));
+
+    rendezvous.wait()?;
+    for waiter in waiters {
+        assert!(waiter.join().expect("waiter should not panic").is_ok());
+    }
+    // Cancelling a released rendezvous has no effect.
+    rendezvous.cancel();
+    Ok(())
+}
+
+#[test]
+fn rendezvous_wakes_up_participants_when_cancelled() {
+    let rendezvous: Arc<Rendezvous> = Arc::new(Rendezvous::new(2));
+    let waiter: JoinHandle<Result<()>> = spawn_waiter(&rendezvous);
+
+    thread::sleep(Duration::from_millis(100));
+    rendezvous.cancel();
+
+    assert!(waiter.join().expect("waiter should not panic").is_err());
+    // Participants that arrive late do not wait for participants that will never come.
+    assert!(rendezvous.wait().is_err());
+}
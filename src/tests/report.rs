@@ -0,0 +1,55 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    action::{Action, ExitStatus},
+    registry::{JobId, JobRecord, JobRegistry, JobState},
+    report::ReportFormat,
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+#[test]
+fn junit_report_escapes_markup_and_drops_control_characters() -> Result<()> {
+    let registry: JobRegistry = JobRegistry::new(0);
+    let job_id: JobId = registry.register("a<b>&\"c\"", &HashMap::new())?;
+
+    let mut action: Action = Action::new(
+        "it's",
+        vec!["true".to_string()],
+        "client",
+        None,
+        Vec::new(),
+        HashMap::new(),
+    );
+    action.set_output(vec![
+        "[client][it's][stdout] <tag attr=\"x\"> & more".to_string(),
+        "[client][it's][stderr] bell\u{7} escape\u{1b}[0m".to_string(),
+        "[client][it's][exit] code=1".to_string(),
+    ]);
+    action.set_exit_status(ExitStatus::new(1, None, false));
+    registry.set_finished(job_id, JobState::Failed, vec![action], Some("broke <here>".to_string()))?;
+
+    let record: JobRecord = match registry.get(job_id)? {
+        Some(record) => record,
+        None => anyhow::bail!("job is not registered"),
+    };
+    let xml: String = ReportFormat::Junit.render(&[record])?;
+
+    assert!(xml.contains(r#"<testsuite name="a&lt;b&gt;&amp;&quot;c&quot;" id="0""#));
+    assert!(xml.contains(r#"<error message="broke &lt;here&gt;"/>"#));
+    assert!(xml.contains(r#"<testcase name="it&apos;s" classname="a&lt;b&gt;&amp;&quot;c&quot;.client""#));
+    assert!(xml.contains(r#"<failure message="exited with code 1"/>"#));
+    assert!(xml.contains("<system-out>&lt;tag attr=&quot;x&quot;&gt; &amp; more</system-out>"));
+    assert!(xml.contains("<system-err>bell escape[0m</system-err>"));
+    assert!(!xml.contains("[exit]"));
+    Ok(())
+}
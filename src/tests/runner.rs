@@ -0,0 +1,319 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::{sshd::FakeSshd, ScratchDir};
+use crate::{
+    action::{Action, ExitStatus, FileTransfer, TransferDirection},
+    cancellation::Cancellation,
+    runner::{local::LocalRunner, ssh::SshRunner, Runner, RunnerState},
+};
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    fs,
+    net::TcpListener,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+fn connect(sshd: &FakeSshd) -> SshRunner {
+    SshRunner::new(
+        0,
+        "127.0.0.1",
+        sshd.port(),
+        "127.0.0.1",
+        HashMap::new(),
+        sshd.credentials(),
+    )
+}
+
+fn action(commands: &[&str], timeout: Option<Duration>) -> Action {
+    let commands: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+    Action::new("test", commands, "worker", timeout, Vec::new(), HashMap::new())
+}
+
+/// Runs an [Action] and returns its output, the lines that were streamed, and its exit status.
+fn run(
+    runner: &mut dyn Runner,
+    action: &Action,
+    env: &HashMap<String, String>,
+    cancellation: &Cancellation,
+) -> Result<(Vec<String>, Vec<String>, ExitStatus)> {
+    let mut streamed: Vec<String> = Vec::new();
+    let (output, exit_status): (Vec<String>, ExitStatus) =
+        runner.run(action, env, cancellation, &mut |line: &str| {
+            streamed.push(line.to_string())
+        })?;
+    Ok((output, streamed, exit_status))
+}
+
+#[test]
+fn probe_connects_to_server() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let mut runner: SshRunner = connect(&sshd);
+
+    assert_eq!(runner.state(), RunnerState::Offline);
+    assert_eq!(runner.probe(), RunnerState::Online);
+    assert_eq!(runner.probe(), RunnerState::Online);
+    Ok(())
+}
+
+#[test]
+fn probe_reports_unreachable_server() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    // Grab a port on which nothing listens.
+    let port: u16 = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    let mut runner: SshRunner = SshRunner::new(0, "127.0.0.1", port, "127.0.0.1", HashMap::new(), sshd.credentials());
+
+    assert_eq!(runner.probe(), RunnerState::Offline);
+    Ok(())
+}
+
+#[test]
+fn run_collects_output() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let mut runner: SshRunner = connect(&sshd);
+
+    let (output, streamed, exit_status): (Vec<String>, Vec<String>, ExitStatus) = run(
+        &mut runner,
+        &action(&["echo hello", "echo oops >&2", "echo", "printf partial"], None),
+        &HashMap::new(),
+        &Cancellation::new(),
+    )?;
+
+    assert!(exit_status.success(), "{:?}", exit_status);
+    assert_eq!(output, streamed);
+    assert!(output.contains(&"[stdout] hello".to_string()), "{:?}", output);
+    assert!(output.contains(&"[stderr] oops".to_string()), "{:?}", output);
+    assert!(output.contains(&"[stdout] partial".to_string()), "{:?}", output);
+    // Empty lines and the session marker are not part of the output.
+    assert_eq!(output.len(), 3, "{:?}", output);
+    Ok(())
+}
+
+#[test]
+fn run_propagates_environment() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let mut runner: SshRunner = connect(&sshd);
+    let env: HashMap<String, String> = HashMap::from([
+        ("DEMIKERNEL_BRANCH".to_string(), "dev".to_string()),
+        ("DEMIKERNEL_SPACES".to_string(), "a b  c".to_string()),
+        ("job".to_string(), "hidden".to_string()),
+    ]);
+
+    let (output, _, exit_status): (Vec<String>, Vec<String>, ExitStatus) = run(
+        &mut runner,
+        &action(
+            &[
+                "echo branch=$DEMIKERNEL_BRANCH",
+                "echo \"spaces=$DEMIKERNEL_SPACES\"",
+                "echo job=${job:-unset}",
+            ],
+            None,
+        ),
+        &env,
+        &Cancellation::new(),
+    )?;
+
+    assert!(exit_status.success(), "{:?}", exit_status);
+    assert_eq!(
+        output,
+        vec!["[stdout] branch=dev", "[stdout] spaces=a b  c", "[stdout] job=unset"]
+    );
+    Ok(())
+}
+
+#[test]
+fn run_reports_failures() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let mut runner: SshRunner = connect(&sshd);
+
+    // Commands are chained, so the ones that follow a failed command do not run.
+    let (output, _, exit_status): (Vec<String>, Vec<String>, ExitStatus) = run(
+        &mut runner,
+        &action(&["echo first", "exit 3", "echo never"], None),
+        &HashMap::new(),
+        &Cancellation::new(),
+    )?;
+
+    assert_eq!(exit_status.code(), 3);
+    assert!(!exit_status.success());
+    assert!(!exit_status.timed_out());
+    assert_eq!(output, vec!["[stdout] first"]);
+
+    // The session survives failed commands.
+    let (_, _, exit_status): (Vec<String>, Vec<String>, ExitStatus) = run(
+        &mut runner,
+        &action(&["true"], None),
+        &HashMap::new(),
+        &Cancellation::new(),
+    )?;
+    assert!(exit_status.success(), "{:?}", exit_status);
+    Ok(())
+}
+
+#[test]
+fn run_kills_commands_that_time_out() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let mut runner: SshRunner = connect(&sshd);
+
+    let start: Instant = Instant::now();
+    let (output, _, exit_status): (Vec<String>, Vec<String>, ExitStatus) = run(
+        &mut runner,
+        &action(&["echo started", "sleep 60"], Some(Duration::from_secs(1))),
+        &HashMap::new(),
+        &Cancellation::new(),
+    )?;
+
+    assert!(start.elapsed() < Duration::from_secs(30), "{:?}", start.elapsed());
+    assert!(exit_status.timed_out(), "{:?}", exit_status);
+    assert_eq!(exit_status.signal(), Some("KILL"));
+    assert_eq!(output, vec!["[stdout] started"]);
+    Ok(())
+}
+
+#[test]
+fn run_kills_commands_of_cancelled_jobs() -> Result<()> {
+    let sshd: FakeSshd = FakeSshd::start()?;
+    let mut runner: SshRunner = connect(&sshd);
+    let cancellation: Arc<Cancellation> = Arc::new(Cancellation::new());
+
+    let canceller: Arc<Cancellation> = cancellation.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(1));
+        canceller.cancel();
+    });
+    let start: Instant = Instant::now();
+    let (_, _, exit_status): (Vec<String>, Vec<String>, ExitStatus) = run(
+        &mut runner,
+        &action(&["sleep 60"], None),
+        &HashMap::new(),
+        &cancellation,
+    )?;
+
+    assert!(start.elapsed() < Duration::from_secs(30), "{:?}", start.elapsed());
+    assert!(!exit_status.success());
+    // Cancelled commands did not time out.
+    assert!(!exit_status.timed_out(), "{:?}", exit_status);
+    Ok(())
+}
+
+#[test]
+fn local_run_hides_orchestrator_environment() -> Result<()> {
+    let mut runner: LocalRunner = LocalRunner::new(0, "127.0.0.1", HashMap::new());
+    // Secrets may be read from the environment of the orchestrator, but only jobs that ask for them receive them.
+    std::env::set_var("DEMIKERNEL_TEST_UNREFERENCED_SECRET", "hunter2");
+    let env: HashMap<String, String> = HashMap::from([("DEMIKERNEL_BRANCH".to_string(), "dev".to_string())]);
+
+    let (output, _, exit_status): (Vec<String>, Vec<String>, ExitStatus) = run(
+        &mut runner,
+        &action(
+            &[
+                "echo secret=${DEMIKERNEL_TEST_UNREFERENCED_SECRET:-unset}",
+                "echo branch=$DEMIKERNEL_BRANCH",
+                "env",
+            ],
+            None,
+        ),
+        &env,
+        &Cancellation::new(),
+    )?;
+
+    assert!(exit_status.success(), "{:?}", exit_status);
+    assert!(output.contains(&"[stdout] secret=unset".to_string()), "{:?}", output);
+    assert!(output.contains(&"[stdout] branch=dev".to_string()), "{:?}", output);
+    assert!(output.iter().all(|line| !line.contains("hunter2")), "{:?}", output);
+    Ok(())
+}
+
+#[test]
+fn local_transfer_stops_once_job_is_cancelled() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("runner")?;
+    let source: String = dir.write("source", "contents")?;
+    let destination: String = dir.path().join("destination").to_string_lossy().to_string();
+    let transfer: FileTransfer = FileTransfer::new(TransferDirection::Upload, &source, &destination);
+    let mut runner: LocalRunner = LocalRunner::new(0, "127.0.0.1", HashMap::new());
+
+    let cancellation: Cancellation = Cancellation::new();
+    cancellation.cancel();
+    let (output, exit_status): (Vec<String>, ExitStatus) =
+        runner.transfer(&transfer, None, &cancellation, &mut |_: &str| {})?;
+
+    assert!(!exit_status.success());
+    assert!(!exit_status.timed_out(), "{:?}", exit_status);
+    assert!(output.iter().any(|line| line.contains("cancelled")), "{:?}", output);
+    assert!(!dir.path().join("destination").exists());
+    Ok(())
+}
+
+#[test]
+fn local_transfer_copies_files_and_directories() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("runner")?;
+    fs::create_dir_all(dir.path().join("build/include"))?;
+    let file: String = dir.write("build/demikernel.so", "library")?;
+    dir.write("build/include/demi.h", "header")?;
+    let mut runner: LocalRunner = LocalRunner::new(0, "127.0.0.1", HashMap::new());
+
+    let uploaded: String = dir
+        .path()
+        .join("remote/lib/demikernel.so")
+        .to_string_lossy()
+        .to_string();
+    let transfer: FileTransfer = FileTransfer::new(TransferDirection::Upload, &file, &uploaded);
+    let mut streamed: Vec<String> = Vec::new();
+    let (output, exit_status): (Vec<String>, ExitStatus) =
+        runner.transfer(&transfer, None, &Cancellation::new(), &mut |line: &str| {
+            streamed.push(line.to_string())
+        })?;
+    assert!(exit_status.success(), "{:?}: {:?}", exit_status, output);
+    assert_eq!(output, streamed);
+    assert_eq!(fs::read_to_string(&uploaded)?, "library");
+
+    let source: String = dir.path().join("build").to_string_lossy().to_string();
+    let downloaded: String = dir.path().join("local/build").to_string_lossy().to_string();
+    let transfer: FileTransfer = FileTransfer::new(TransferDirection::Download, &source, &downloaded);
+    let (output, exit_status): (Vec<String>, ExitStatus) =
+        runner.transfer(&transfer, None, &Cancellation::new(), &mut |_: &str| {})?;
+    assert!(exit_status.success(), "{:?}: {:?}", exit_status, output);
+    assert!(output.iter().all(|line| line.starts_with("[download]")), "{:?}", output);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("local/build/demikernel.so"))?,
+        "library"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("local/build/include/demi.h"))?,
+        "header"
+    );
+    Ok(())
+}
+
+#[test]
+fn local_transfer_reports_missing_sources() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("runner")?;
+    let source: String = dir.path().join("missing").to_string_lossy().to_string();
+    let destination: String = dir.path().join("destination").to_string_lossy().to_string();
+    let transfer: FileTransfer = FileTransfer::new(TransferDirection::Upload, &source, &destination);
+    let mut runner: LocalRunner = LocalRunner::new(0, "127.0.0.1", HashMap::new());
+
+    let (output, exit_status): (Vec<String>, ExitStatus) =
+        runner.transfer(&transfer, None, &Cancellation::new(), &mut |_: &str| {})?;
+
+    assert!(!exit_status.success());
+    assert!(!exit_status.timed_out(), "{:?}", exit_status);
+    assert!(
+        output.iter().any(|line| line.contains("failed to stat file")),
+        "{:?}",
+        output
+    );
+    assert!(!dir.path().join("destination").exists());
+    Ok(())
+}
@@ -0,0 +1,709 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::{
+    notifier::{MockApi, MockRequest},
+    sshd::FakeSshd,
+    ScratchDir,
+};
+use crate::{
+    action::Action,
+    history::HistoryStore,
+    job::Job,
+    notifier::Notifier,
+    registry::{JobId, JobRecord, JobState},
+    runner::{local::LocalRunner, ssh::SshRunner, Runner},
+    scheduler::Scheduler,
+    secrets::SecretStore,
+};
+use anyhow::Result;
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    os::unix::fs::PermissionsExt,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Scheduler whose runners are connected to a [FakeSshd].
+struct Fixture {
+    scheduler: Arc<Scheduler>,
+    dir: ScratchDir,
+    _sshd: FakeSshd,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Fixture {
+    /// Value of the secret that jobs may reference as `token`.
+    const TOKEN: &'static str = "s3cr3t-t0k3n";
+    /// Value of the secret that jobs may reference as `key`, which spans several lines.
+    const KEY: &'static str = "-----BEGIN KEY-----\nbWFjaGluZS1rZXk=\n";
+
+    fn new(num_runners: usize) -> Result<Self> {
+        Self::with_notifier(num_runners, None)
+    }
+
+    /// Instantiates a new [Fixture] whose scheduler reports the status of jobs through a [Notifier].
+    fn with_notifier(num_runners: usize, notifier: Option<Notifier>) -> Result<Self> {
+        let sshd: FakeSshd = FakeSshd::start()?;
+        let dir: ScratchDir = ScratchDir::new("scheduler")?;
+
+        let runners: Vec<Mutex<Box<dyn Runner>>> = (0..num_runners)
+            .map(|id| {
+                let runner: SshRunner = SshRunner::new(
+                    id,
+                    "127.0.0.1",
+                    sshd.port(),
+                    "127.0.0.1",
+                    HashMap::new(),
+                    sshd.credentials(),
+                );
+                Mutex::new(Box::new(runner) as Box<dyn Runner>)
+            })
+            .collect();
+
+        let token_path: String = dir.write("token", Self::TOKEN)?;
+        fs::set_permissions(&token_path, fs::Permissions::from_mode(0o600))?;
+        let mut secrets: SecretStore = SecretStore::new();
+        secrets.insert_from_file("token", &token_path)?;
+        let key_path: String = dir.write("key", Self::KEY)?;
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))?;
+        secrets.insert_from_file("key", &key_path)?;
+
+        let history: HistoryStore = HistoryStore::new(&dir.path().join("history.jsonl").to_string_lossy(), None)?;
+        let scheduler: Arc<Scheduler> = Arc::new(Scheduler::new(
+            runners,
+            &dir.path().join("artifacts").to_string_lossy(),
+            history,
+            notifier,
+            secrets,
+        )?);
+        scheduler.monitor_runners()?;
+
+        Ok(Self {
+            scheduler,
+            dir,
+            _sshd: sshd,
+        })
+    }
+
+    /// Runs a job to completion and returns its record along with the lines that were streamed while it ran.
+    fn run(&self, job: &str, parameters: HashMap<String, String>) -> Result<(JobRecord, Vec<String>)> {
+        let job_path: String = self.dir.write("job.yaml", job)?;
+        let job: Job = Job::load(&job_path, parameters)?.remove(0);
+
+        let (tx, rx): (Sender<String>, Receiver<String>) = mpsc::channel();
+        let job_id: JobId = self
+            .scheduler
+            .submit(vec![("job.yaml".to_string(), job)], Some(tx))?
+            .remove(0);
+        let record: JobRecord = self.scheduler.wait(job_id)?;
+
+        Ok((record, rx.try_iter().collect()))
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Instantiates a [Scheduler] whose runners run commands on the local host, one per set of labels, and whose
+/// artifacts are stored in a directory.
+fn local_scheduler(
+    dir: &ScratchDir,
+    history: HistoryStore,
+    labels: Vec<HashMap<String, String>>,
+) -> Result<Arc<Scheduler>> {
+    let runners: Vec<Mutex<Box<dyn Runner>>> = labels
+        .into_iter()
+        .enumerate()
+        .map(|(id, labels)| Mutex::new(Box::new(LocalRunner::new(id, "127.0.0.1", labels)) as Box<dyn Runner>))
+        .collect();
+    let scheduler: Arc<Scheduler> = Arc::new(Scheduler::new(
+        runners,
+        &dir.path().join("artifacts").to_string_lossy(),
+        history,
+        None,
+        SecretStore::new(),
+    )?);
+    scheduler.monitor_runners()?;
+    Ok(scheduler)
+}
+
+/// Converts pairs of strings into a map of labels.
+fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn output<'a>(record: &'a JobRecord, name: &str) -> &'a Vec<String> {
+    let action: &Action = record
+        .actions()
+        .iter()
+        .find(|action| action.name() == name)
+        .unwrap_or_else(|| panic!("no such action (name={:?})", name));
+    action.output().as_ref().expect("action should have run")
+}
+
+#[test]
+fn job_runs_end_to_end() -> Result<()> {
+    let fixture: Fixture = Fixture::new(2)?;
+    let parameters: HashMap<String, String> = HashMap::from([
+        (
+            "DEMIKERNEL_DIR".to_string(),
+            fixture.dir.path().to_string_lossy().to_string(),
+        ),
+        ("DEMIKERNEL_GREETING".to_string(), "hello".to_string()),
+    ]);
+
+    // The consumer may only read the file after the barrier, by which time the producer has written it.
+    let (record, streamed): (JobRecord, Vec<String>) = fixture.run(
+        "job:
+  - action: produce
+    runs-on: producer
+    commands:
+      - sleep 1
+      - echo $DEMIKERNEL_GREETING > $DEMIKERNEL_DIR/ready
+  - action: wait
+    runs-on: consumer
+    commands:
+      - echo waiting
+  - barrier: ready
+  - action: consume
+    runs-on: consumer
+    commands:
+      - cat $DEMIKERNEL_DIR/ready
+",
+        parameters,
+    )?;
+
+    assert_eq!(record.state(), JobState::Succeeded, "{:?}", record.error());
+    assert_eq!(
+        output(&record, "consume"),
+        &vec![
+            "[consumer][consume][stdout] hello".to_string(),
+            "[consumer][consume][exit] code=0, signal=None, timed_out=false".to_string(),
+        ]
+    );
+    assert!(
+        streamed.contains(&"[consumer][wait][stdout] waiting".to_string()),
+        "{:?}",
+        streamed
+    );
+    assert!(
+        streamed.contains(&"[consumer][consume][stdout] hello".to_string()),
+        "{:?}",
+        streamed
+    );
+    Ok(())
+}
+
+#[test]
+fn job_fails_when_an_action_fails() -> Result<()> {
+    let fixture: Fixture = Fixture::new(1)?;
+
+    let (record, _): (JobRecord, Vec<String>) = fixture.run(
+        "job:
+  - action: fail
+    runs-on: worker
+    commands:
+      - echo failing >&2
+      - exit 7
+  - action: skipped
+    runs-on: worker
+    commands:
+      - echo unreachable
+",
+        HashMap::new(),
+    )?;
+
+    assert_eq!(record.state(), JobState::Failed);
+    assert_eq!(
+        output(&record, "fail"),
+        &vec![
+            "[worker][fail][stderr] failing".to_string(),
+            "[worker][fail][exit] code=7, signal=None, timed_out=false".to_string(),
+        ]
+    );
+    assert!(record
+        .actions()
+        .iter()
+        .all(|action| action.name() != "skipped" || action.output().is_none()));
+    Ok(())
+}
+
+#[test]
+fn job_fails_when_a_worker_fails_before_a_barrier() -> Result<()> {
+    let fixture: Fixture = Fixture::new(2)?;
+
+    // Were the barrier not cancelled, the server would wait forever for the client.
+    let (record, _): (JobRecord, Vec<String>) = fixture.run(
+        "job:
+  - action: serve
+    runs-on: server
+    commands: [\"true\"]
+  - action: connect
+    runs-on: client
+    commands: [\"false\"]
+  - barrier: done
+  - action: shutdown
+    runs-on: server
+    commands: [echo unreachable]
+  - action: disconnect
+    runs-on: client
+    commands: [echo unreachable]
+",
+        HashMap::new(),
+    )?;
+
+    assert_eq!(record.state(), JobState::Failed);
+    assert!(record
+        .actions()
+        .iter()
+        .all(|action| action.name() != "shutdown" || action.output().is_none()));
+    Ok(())
+}
+
+#[test]
+fn job_skips_actions_whose_needs_failed() -> Result<()> {
+    let fixture: Fixture = Fixture::new(2)?;
+
+    let (record, _): (JobRecord, Vec<String>) = fixture.run(
+        "job:
+  - action: consume
+    runs-on: consumer
+    needs: produce
+    commands: [echo unreachable]
+  - action: produce
+    runs-on: producer
+    commands: [\"false\"]
+",
+        HashMap::new(),
+    )?;
+
+    assert_eq!(record.state(), JobState::Failed);
+    assert!(record
+        .actions()
+        .iter()
+        .all(|action| action.name() != "consume" || action.output().is_none()));
+    Ok(())
+}
+
+#[test]
+fn job_receives_secrets_and_output_is_redacted() -> Result<()> {
+    let fixture: Fixture = Fixture::new(1)?;
+
+    let (record, streamed): (JobRecord, Vec<String>) = fixture.run(
+        "secrets: [token]
+job:
+  - action: use-token
+    runs-on: worker
+    commands:
+      - echo token=$DEMIKERNEL_TOKEN
+      - test \"$DEMIKERNEL_TOKEN\" = s3cr3t-t0k3n
+",
+        HashMap::new(),
+    )?;
+
+    assert_eq!(record.state(), JobState::Succeeded, "{:?}", record.error());
+    assert_eq!(output(&record, "use-token")[0], "[worker][use-token][stdout] token=***");
+    assert!(
+        streamed.iter().all(|line| !line.contains(Fixture::TOKEN)),
+        "{:?}",
+        streamed
+    );
+    Ok(())
+}
+
+#[test]
+fn output_is_redacted_of_multi_line_secrets() -> Result<()> {
+    let fixture: Fixture = Fixture::new(1)?;
+
+    let (record, streamed): (JobRecord, Vec<String>) = fixture.run(
+        "secrets: [key]
+job:
+  - action: use-key
+    runs-on: worker
+    commands:
+      - printf '%s\\n' \"$DEMIKERNEL_KEY\"
+",
+        HashMap::new(),
+    )?;
+
+    assert_eq!(record.state(), JobState::Succeeded, "{:?}", record.error());
+    assert_eq!(
+        output(&record, "use-key")[..2],
+        ["[worker][use-key][stdout] ***", "[worker][use-key][stdout] ***"]
+    );
+    for line in Fixture::KEY.lines() {
+        assert!(streamed.iter().all(|output| !output.contains(line)), "{:?}", streamed);
+    }
+    Ok(())
+}
+
+#[test]
+fn job_reports_commit_status() -> Result<()> {
+    let api: MockApi = MockApi::start()?;
+    let notifier: Notifier = Notifier::new(&api.url(), Some("gh-token"), "demikernel-ci", "https://ci.example.com");
+    let fixture: Fixture = Fixture::with_notifier(1, Some(notifier))?;
+    let sha: &str = "0123456789abcdef0123456789abcdef01234567";
+    let parameters: HashMap<String, String> = HashMap::from([
+        ("DEMIKERNEL_REPOSITORY".to_string(), "microsoft/demikernel".to_string()),
+        ("DEMIKERNEL_SHA".to_string(), sha.to_string()),
+    ]);
+
+    let (record, _): (JobRecord, Vec<String>) = fixture.run(
+        "job:\n  - action: test\n    runs-on: worker\n    commands: [\"true\"]\n",
+        parameters,
+    )?;
+    assert_eq!(record.state(), JobState::Succeeded, "{:?}", record.error());
+
+    let requests: Vec<MockRequest> = api.requests();
+    assert_eq!(requests.len(), 2, "{:?}", requests);
+    for (request, state) in requests.iter().zip(["pending", "success"]) {
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, format!("/repos/microsoft/demikernel/statuses/{}", sha));
+        assert_eq!(request.authorization.as_deref(), Some("Bearer gh-token"));
+        assert_eq!(request.body["state"], state, "{:?}", request);
+        assert_eq!(request.body["context"], "demikernel-ci");
+        assert_eq!(
+            request.body["target_url"],
+            format!("https://ci.example.com/jobs/{}", record.id())
+        );
+    }
+
+    // Commits that cannot be addressed safely are not reported to.
+    let (record, _): (JobRecord, Vec<String>) = fixture.run(
+        "job:\n  - action: test\n    runs-on: worker\n    commands: [\"true\"]\n",
+        HashMap::from([
+            ("DEMIKERNEL_REPOSITORY".to_string(), "a/b/../../..".to_string()),
+            ("DEMIKERNEL_SHA".to_string(), sha.to_string()),
+        ]),
+    )?;
+    assert_eq!(record.state(), JobState::Succeeded, "{:?}", record.error());
+    assert!(api.requests().is_empty());
+    Ok(())
+}
+
+#[test]
+fn job_identifiers_are_not_reused() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("scheduler")?;
+    // Artifacts of a job that never made it to the history, which is pruned of everything that is not recent.
+    fs::create_dir_all(dir.path().join("artifacts").join("7"))?;
+    let history: HistoryStore = HistoryStore::new(
+        &dir.path().join("history.jsonl").to_string_lossy(),
+        Some(Duration::from_secs(u64::MAX)),
+    )?;
+    let scheduler: Arc<Scheduler> = local_scheduler(&dir, history, vec![HashMap::new()])?;
+    let job_path: String = dir.write(
+        "job.yaml",
+        "job:\n  - action: test\n    runs-on: worker\n    commands: [\"true\"]\n",
+    )?;
+    let job: Job = Job::load(&job_path, HashMap::new())?.remove(0);
+
+    let job_id: JobId = scheduler.submit(vec![("job.yaml".to_string(), job)], None)?.remove(0);
+    assert_eq!(job_id, 8);
+    assert_eq!(scheduler.wait(job_id)?.state(), JobState::Succeeded);
+    Ok(())
+}
+
+#[test]
+fn finished_jobs_are_only_kept_in_history() -> Result<()> {
+    let fixture: Fixture = Fixture::new(1)?;
+
+    let (record, _): (JobRecord, Vec<String>) = fixture.run(
+        "job:\n  - action: test\n    runs-on: worker\n    commands: [echo done]\n",
+        HashMap::new(),
+    )?;
+
+    // The job is forgotten by the registry right after it is written to the history.
+    let deadline: Instant = Instant::now() + Duration::from_secs(10);
+    while fixture.scheduler.registry().get(record.id())?.is_some() {
+        assert!(Instant::now() < deadline, "job was not forgotten (id={})", record.id());
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        fixture
+            .scheduler
+            .history()
+            .get(record.id())?
+            .map(|record| record.state()),
+        Some(JobState::Succeeded)
+    );
+    assert_eq!(fixture.scheduler.wait(record.id())?.state(), JobState::Succeeded);
+    assert!(fixture.scheduler.wait(record.id() + 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn job_collects_artifacts_of_local_runners() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("scheduler")?;
+    let history: HistoryStore = HistoryStore::new(&dir.path().join("history.jsonl").to_string_lossy(), None)?;
+    let scheduler: Arc<Scheduler> = local_scheduler(&dir, history, vec![HashMap::new()])?;
+
+    let work_dir: String = dir.path().join("work").to_string_lossy().to_string();
+    let job_path: String = dir.write(
+        "job.yaml",
+        &format!(
+            "job:
+  - action: build
+    runs-on: worker
+    commands:
+      - mkdir -p {work_dir}/logs && echo built > {work_dir}/logs/build.log
+    artifacts: [{work_dir}/logs/build.log, {work_dir}/missing.log]
+  - action: deploy
+    runs-on: worker
+    upload:
+      source: {work_dir}/logs
+      destination: {work_dir}/deployed
+",
+            work_dir = work_dir
+        ),
+    )?;
+    let job: Job = Job::load(&job_path, HashMap::new())?.remove(0);
+
+    let job_id: JobId = scheduler.submit(vec![("job.yaml".to_string(), job)], None)?.remove(0);
+    let record: JobRecord = scheduler.wait(job_id)?;
+
+    assert_eq!(record.state(), JobState::Succeeded, "{:?}", record.error());
+    let build: &Vec<String> = output(&record, "build");
+    assert!(
+        build.contains(&format!(
+            "[worker][build][artifact] failed to collect {}/missing.log",
+            work_dir
+        )),
+        "{:?}",
+        build
+    );
+    assert!(
+        output(&record, "deploy")
+            .iter()
+            .any(|line| line.starts_with("[worker][deploy][upload]")),
+        "{:?}",
+        output(&record, "deploy")
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("work/deployed/build.log"))?,
+        "built\n"
+    );
+
+    assert_eq!(
+        scheduler.artifacts().list(job_id)?,
+        vec![("worker/build/build.log".to_string(), 6)]
+    );
+    let (mut file, length): (fs::File, u64) = scheduler
+        .artifacts()
+        .open(job_id, "worker/build/build.log")?
+        .expect("artifact should have been collected");
+    let mut contents: String = String::new();
+    file.read_to_string(&mut contents)?;
+    assert_eq!((contents.as_str(), length), ("built\n", 6));
+    assert!(scheduler
+        .artifacts()
+        .open(job_id, "worker/build/missing.log")?
+        .is_none());
+    Ok(())
+}
+
+#[test]
+fn workers_are_placed_on_runners_with_their_labels() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("scheduler")?;
+    let history: HistoryStore = HistoryStore::new(&dir.path().join("history.jsonl").to_string_lossy(), None)?;
+    let scheduler: Arc<Scheduler> = local_scheduler(
+        &dir,
+        history,
+        vec![labels(&[("nic", "mlx5")]), labels(&[("nic", "e1000"), ("gpu", "a100")])],
+    )?;
+    let load = |name: &str, job: &str| -> Result<Job> {
+        let job_path: String = dir.write(name, job)?;
+        Ok(Job::load(&job_path, HashMap::new())?.remove(0))
+    };
+
+    // The client could run anywhere, but it must leave the only runner with an mlx5 card to the server.
+    let job: Job = load(
+        "job.yaml",
+        "job:
+  - action: connect
+    runs-on: client
+    commands: [connect]
+  - action: serve
+    runs-on: server
+    labels:
+      nic: mlx5
+    commands: [serve]
+",
+    )?;
+    let lines: Vec<String> = scheduler.plan("job.yaml", job)?.describe();
+    assert!(
+        lines.contains(&"[plan][worker] name=client runner=1 addr=localhost local_addr=127.0.0.1".to_string()),
+        "{:?}",
+        lines
+    );
+    assert!(
+        lines.contains(&"[plan][worker] name=server runner=0 addr=localhost local_addr=127.0.0.1".to_string()),
+        "{:?}",
+        lines
+    );
+
+    // Jobs that no runner can satisfy are rejected, instead of waiting for runners forever.
+    let unsatisfiable: &str = "job:
+  - action: train
+    runs-on: trainer
+    labels:
+      gpu: h100
+    commands: [train]
+";
+    assert!(scheduler.plan("job.yaml", load("job.yaml", unsatisfiable)?).is_err());
+    let job_id: JobId = scheduler
+        .submit(vec![("job.yaml".to_string(), load("job.yaml", unsatisfiable)?)], None)?
+        .remove(0);
+    let record: JobRecord = scheduler.wait(job_id)?;
+    assert_eq!(record.state(), JobState::Failed);
+    assert!(
+        record.error().is_some_and(|error| error.contains("no runners")),
+        "{:?}",
+        record.error()
+    );
+
+    // Neither can draining runners take new jobs.
+    let trainer: &str = "job:
+  - action: train
+    runs-on: trainer
+    labels:
+      gpu: a100
+    commands: [\"true\"]
+";
+    assert!(scheduler.set_draining(1, true)?);
+    let job_id: JobId = scheduler
+        .submit(vec![("job.yaml".to_string(), load("job.yaml", trainer)?)], None)?
+        .remove(0);
+    assert_eq!(scheduler.wait(job_id)?.state(), JobState::Failed);
+    assert!(scheduler.set_draining(1, false)?);
+    let job_id: JobId = scheduler
+        .submit(vec![("job.yaml".to_string(), load("job.yaml", trainer)?)], None)?
+        .remove(0);
+    assert_eq!(scheduler.wait(job_id)?.state(), JobState::Succeeded);
+    Ok(())
+}
+
+#[test]
+fn jobs_wait_for_runners_by_priority() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("scheduler")?;
+    let history: HistoryStore = HistoryStore::new(&dir.path().join("history.jsonl").to_string_lossy(), None)?;
+    let scheduler: Arc<Scheduler> = local_scheduler(&dir, history, vec![HashMap::new()])?;
+    let order_path: String = dir.path().join("order").to_string_lossy().to_string();
+    let submit = |name: &str, header: &str, command: &str| -> Result<JobId> {
+        let job_path: String = dir.write(
+            name,
+            &format!(
+                "{}job:\n  - action: run\n    runs-on: worker\n    commands: [\"{}\"]\n",
+                header, command
+            ),
+        )?;
+        let job: Job = Job::load(&job_path, HashMap::new())?.remove(0);
+        Ok(scheduler.submit(vec![(name.to_string(), job)], None)?.remove(0))
+    };
+    let wait_until_queued = |job_id: JobId| {
+        let deadline: Instant = Instant::now() + Duration::from_secs(10);
+        while scheduler.queue_position(job_id).is_none() {
+            assert!(Instant::now() < deadline, "job was not queued (id={})", job_id);
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    // Keep the only runner busy while other jobs line up for it.
+    let busy: JobId = submit("busy.yaml", "", "sleep 2")?;
+    let deadline: Instant = Instant::now() + Duration::from_secs(10);
+    while scheduler.registry().get(busy)?.map(|record| record.state()) != Some(JobState::Running) {
+        assert!(Instant::now() < deadline, "job did not start (id={})", busy);
+        thread::sleep(Duration::from_millis(10));
+    }
+    let low: JobId = submit("low.yaml", "", &format!("echo low >> {}", order_path))?;
+    wait_until_queued(low);
+    let timeout: JobId = submit("timeout.yaml", "allocation-timeout: 1\n", "true")?;
+    wait_until_queued(timeout);
+    let high: JobId = submit("high.yaml", "priority: 10\n", &format!("echo high >> {}", order_path))?;
+    wait_until_queued(high);
+
+    // Jobs of higher priority go first, and jobs of the same priority are served in the order they were submitted.
+    assert_eq!(scheduler.queue_position(high), Some(1));
+    assert_eq!(scheduler.queue_position(low), Some(2));
+    assert_eq!(scheduler.queue_position(timeout), Some(3));
+
+    let record: JobRecord = scheduler.wait(timeout)?;
+    assert_eq!(record.state(), JobState::Failed);
+    assert!(
+        record
+            .error()
+            .is_some_and(|error| error.contains("timed out waiting for runners")),
+        "{:?}",
+        record.error()
+    );
+    for job_id in [busy, low, high] {
+        assert_eq!(scheduler.wait(job_id)?.state(), JobState::Succeeded);
+    }
+    assert_eq!(fs::read_to_string(&order_path)?, "high\nlow\n");
+    Ok(())
+}
+
+#[test]
+fn plan_lays_out_job_without_running_it() -> Result<()> {
+    let fixture: Fixture = Fixture::new(2)?;
+    let job_path: String = fixture.dir.write(
+        "job.yaml",
+        "secrets: [token]
+job:
+  - action: serve
+    runs-on: server
+    commands:
+      - run-server
+  - barrier: ready
+  - action: connect
+    runs-on: client
+    commands:
+      - run-client $DEMIKERNEL_SERVER
+",
+    )?;
+    let parameters: HashMap<String, String> = HashMap::from([("DEMIKERNEL_BRANCH".to_string(), "dev".to_string())]);
+    let job: Job = Job::load(&job_path, parameters)?.remove(0);
+
+    let lines: Vec<String> = fixture.scheduler.plan("job.yaml", job)?.describe();
+
+    assert!(
+        lines[0].starts_with("[plan] name=job.yaml workers=2 barriers=1"),
+        "{:?}",
+        lines
+    );
+    // The client joins the barrier only after it is inserted, so it does not take part in it.
+    for expected in [
+        "[plan][barrier] index=0 participants=1 workers=[\"server\"]",
+        "[server][serve] commands=[\"run-server\"] timeout=None needs=[] artifacts=[]",
+        "[server][barrier] index=0",
+        "[client][connect][env] DEMIKERNEL_BRANCH=dev",
+        "[client][connect][env] DEMIKERNEL_SERVER=127.0.0.1",
+        "[client][connect][env] DEMIKERNEL_TOKEN=***",
+    ] {
+        assert!(lines.contains(&expected.to_string()), "{:?}", lines);
+    }
+    assert!(lines.iter().all(|line| !line.contains(Fixture::TOKEN)), "{:?}", lines);
+    assert!(fixture.scheduler.registry().list()?.is_empty());
+    Ok(())
+}
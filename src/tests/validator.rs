@@ -0,0 +1,327 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::ScratchDir;
+use crate::{
+    job::Job,
+    validator::{Diagnostic, JobValidator, Severity},
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Validates a job file and returns its diagnostics, with the path of the file stripped.
+fn validate(job: &str) -> Result<Vec<String>> {
+    let dir: ScratchDir = ScratchDir::new("validator")?;
+    let job_path: String = dir.write("job.yaml", job)?;
+    let diagnostics: Vec<Diagnostic> = JobValidator::validate(&job_path)?;
+    Ok(diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_string()[job_path.len() + 1..].to_string())
+        .collect())
+}
+
+#[test]
+fn validate_accepts_well_formed_jobs() -> Result<()> {
+    let diagnostics: Vec<String> = validate(
+        "matrix:
+  nic-type: [mlx5, e1000]
+secrets: token
+timeout: 60
+job:
+  - action: build
+    runs-on: server
+    commands:
+      - make NIC=$DEMIKERNEL_NIC_TYPE TOKEN=${DEMIKERNEL_TOKEN}
+  - action: connect
+    runs-on: client
+    commands:
+      - echo $DEMIKERNEL_SHA
+  - barrier: ready
+  - action: ping
+    runs-on: client
+    needs: build
+    commands:
+      - ping -c 1 $DEMIKERNEL_SERVER
+",
+    )?;
+
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    Ok(())
+}
+
+#[test]
+fn validate_reports_mistakes_with_lines() -> Result<()> {
+    let diagnostics: Vec<String> = validate(
+        "timout: 5
+job:
+  - action: build
+    runs-on: server
+    comands:
+      - make
+  - action: test
+    runs-on: server
+    needs: [build, missing]
+    commands: []
+  - action: print
+    runs-on: client
+    commands:
+      - echo $DEMIKERNEL_client $DEMIKERNEL_CLIENT
+  - unknown: entry
+",
+    )?;
+
+    for expected in [
+        "1: error: unknown entry (entry=\"timout\", scope=job file)",
+        "3: error: missing commands entry (action=\"build\")",
+        "5: error: unknown entry (entry=\"comands\", action=\"build\")",
+        "9: error: unknown dependency (action=\"test\", needs=\"missing\")",
+        "10: error: empty commands entry (action=\"test\")",
+        "15: error: unknown job entry, expected an action or a barrier",
+    ] {
+        assert!(diagnostics.contains(&expected.to_string()), "{:?}", diagnostics);
+    }
+    // Worker addresses are exported in upper case.
+    assert!(
+        diagnostics
+            .iter()
+            .any(|d| d.starts_with("14: error:") && d.contains("DEMIKERNEL_client")),
+        "{:?}",
+        diagnostics
+    );
+    assert!(
+        diagnostics.iter().all(|d| !d.contains("DEMIKERNEL_CLIENT")),
+        "{:?}",
+        diagnostics
+    );
+    Ok(())
+}
+
+#[test]
+fn validate_warns_about_barrier_placement() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("validator")?;
+    let job_path: String = dir.write(
+        "job.yaml",
+        "job:
+  - barrier: start
+  - action: server
+    runs-on: server
+    commands: [run-server]
+  - barrier: ready
+  - action: client
+    runs-on: client
+    commands: [run-client]
+  - barrier: done
+",
+    )?;
+
+    let diagnostics: Vec<Diagnostic> = JobValidator::validate(&job_path)?;
+    // Barriers are allowed anywhere, so all of these are warnings.
+    assert!(diagnostics
+        .iter()
+        .all(|diagnostic| diagnostic.severity() == Severity::Warning));
+    let lines: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.to_string()[job_path.len() + 1..].to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "2: warning: barrier has no participants, since no action runs before it",
+            "3: warning: worker first runs after the barrier on line 2, so it does not wait for it (worker=\"server\")",
+            "6: warning: barrier has a single participant (worker=\"server\")",
+            "7: warning: worker first runs after the barrier on line 6, so it does not wait for it (worker=\"client\")",
+            "10: warning: barrier has no effect, since no action runs after it",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn load_rejects_empty_commands() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("validator")?;
+    let job_path: String = dir.write(
+        "job.yaml",
+        "job:\n  - action: empty\n    runs-on: worker\n    commands: []\n",
+    )?;
+
+    assert!(Job::load(&job_path, HashMap::new()).is_err());
+    Ok(())
+}
+
+#[test]
+fn action_names_repeat_unless_needed() -> Result<()> {
+    let job: &str = "job:
+  - action: build
+    runs-on: server
+    commands: [make]
+  - action: build
+    runs-on: client
+    commands: [make]
+";
+    let dir: ScratchDir = ScratchDir::new("validator")?;
+    let job_path: String = dir.write("job.yaml", job)?;
+    assert!(validate(job)?.is_empty());
+    assert!(Job::load(&job_path, HashMap::new()).is_ok());
+
+    let job: String = format!(
+        "{}  - action: test\n    runs-on: client\n    needs: build\n    commands: [make test]\n",
+        job
+    );
+    let job_path: String = dir.write("needed.yaml", &job)?;
+    assert_eq!(
+        validate(&job)?,
+        vec!["5: error: duplicate action name, which another action needs (name=\"build\", previous_line=2)"]
+    );
+    assert!(Job::load(&job_path, HashMap::new()).is_err());
+    Ok(())
+}
+
+#[test]
+fn load_rejects_dependency_cycles() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("validator")?;
+    for (name, job) in [
+        (
+            "explicit.yaml",
+            "job:
+  - action: server
+    runs-on: server
+    needs: client
+    commands: [run-server]
+  - action: client
+    runs-on: client
+    needs: server
+    commands: [run-client]
+",
+        ),
+        // Actions of a worker run in order, so an action cannot need one that its worker runs later on.
+        (
+            "implicit.yaml",
+            "job:
+  - action: build
+    runs-on: server
+    needs: test
+    commands: [make]
+  - action: test
+    runs-on: server
+    commands: [make test]
+",
+        ),
+        // Actions after a barrier run after every action that comes before it.
+        (
+            "barrier.yaml",
+            "job:
+  - action: hello
+    runs-on: client
+    commands: [echo hello]
+  - action: build
+    runs-on: server
+    needs: connect
+    commands: [make]
+  - barrier: ready
+  - action: connect
+    runs-on: client
+    commands: [connect]
+",
+        ),
+    ] {
+        let job_path: String = dir.write(name, job)?;
+        let error: String = match Job::load(&job_path, HashMap::new()) {
+            Ok(_) => panic!("cycle should be rejected (job={})", name),
+            Err(e) => e.to_string(),
+        };
+        assert!(error.contains("dependency cycle"), "{}: {}", name, error);
+    }
+
+    let job_path: String = dir.write(
+        "acyclic.yaml",
+        "job:
+  - action: serve
+    runs-on: server
+    needs: build
+    commands: [run-server]
+  - action: build
+    runs-on: client
+    commands: [make]
+",
+    )?;
+    assert!(Job::load(&job_path, HashMap::new()).is_ok());
+    Ok(())
+}
+
+#[test]
+fn load_expands_matrix_into_job_instances() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("validator")?;
+    let job_path: String = dir.write(
+        "job.yaml",
+        "matrix:
+  nic-type: [mlx5, e1000]
+  size: [64, 1024]
+job:
+  - action: test
+    runs-on: worker
+    commands: [make test]
+",
+    )?;
+    let parameters: HashMap<String, String> = HashMap::from([("DEMIKERNEL_BRANCH".to_string(), "dev".to_string())]);
+
+    let jobs: Vec<Job> = Job::load(&job_path, parameters)?;
+
+    let variants: Vec<String> = jobs.iter().map(|job| job.variant()).collect();
+    assert_eq!(
+        variants,
+        vec![
+            "[nic-type=mlx5,size=64]",
+            "[nic-type=mlx5,size=1024]",
+            "[nic-type=e1000,size=64]",
+            "[nic-type=e1000,size=1024]",
+        ]
+    );
+    for job in &jobs {
+        assert_eq!(job.env().get("DEMIKERNEL_BRANCH").map(String::as_str), Some("dev"));
+    }
+    assert_eq!(
+        jobs[1].env().get("DEMIKERNEL_NIC_TYPE").map(String::as_str),
+        Some("mlx5")
+    );
+    assert_eq!(jobs[1].env().get("DEMIKERNEL_SIZE").map(String::as_str), Some("1024"));
+
+    // Job files without a matrix have a single instance, with no variant.
+    let job_path: String = dir.write(
+        "single.yaml",
+        "job:\n  - action: test\n    runs-on: worker\n    commands: [make test]\n",
+    )?;
+    let jobs: Vec<Job> = Job::load(&job_path, HashMap::new())?;
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].variant(), "");
+    Ok(())
+}
+
+#[test]
+fn load_rejects_malformed_matrix() -> Result<()> {
+    let dir: ScratchDir = ScratchDir::new("validator")?;
+    for matrix in [
+        "matrix: [mlx5, e1000]",
+        "matrix:\n  nic-type: []",
+        "matrix:\n  nic-type: mlx5",
+        "matrix:\n  nic type: [mlx5]",
+        "matrix:\n  nic-type: [[mlx5]]",
+    ] {
+        let job_path: String = dir.write(
+            "job.yaml",
+            &format!(
+                "{}\njob:\n  - action: test\n    runs-on: worker\n    commands: [make test]\n",
+                matrix
+            ),
+        )?;
+        assert!(Job::load(&job_path, HashMap::new()).is_err(), "{}", matrix);
+    }
+    Ok(())
+}
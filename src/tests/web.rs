@@ -0,0 +1,279 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    required_scope,
+    web::{
+        auth::{ApiToken, Authenticator, Scope, TokenKind},
+        body::HttpBody,
+        server::HttpServer,
+    },
+};
+use anyhow::Result;
+use http::{Request, Response, StatusCode, Version};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpStream},
+    thread,
+    time::Duration,
+};
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Starts an [HttpServer] on an ephemeral port of the loopback interface, and returns that port. Requests to `/run`
+/// are long-lived: they take a few seconds to be answered. Requests to `/echo` are answered with their body.
+fn start_server() -> Result<u16> {
+    start_server_with(None)
+}
+
+/// Starts an [HttpServer] like [start_server] does, but that authorizes requests like the orchestrator does.
+fn start_server_with(authenticator: Option<Authenticator>) -> Result<u16> {
+    let server: HttpServer = HttpServer::new("127.0.0.1:0", authenticator, None)?;
+    let port: u16 = server.listener.local_addr()?.port();
+    let dispatcher = |request: Request<Vec<u8>>| -> Result<Response<HttpBody>> {
+        let line: String = match request.uri().path() {
+            "/run" => {
+                thread::sleep(Duration::from_secs(3));
+                "ok".to_string()
+            },
+            "/echo" => String::from_utf8_lossy(request.body()).to_string(),
+            _ => "ok".to_string(),
+        };
+        Ok(Response::builder()
+            .version(Version::HTTP_11)
+            .status(StatusCode::OK)
+            .body(HttpBody::Lines(vec![line]))?)
+    };
+    thread::spawn(move || server.run(dispatcher, |request| request.uri().path() == "/run"));
+    Ok(port)
+}
+
+/// Sends a request for a path, without waiting for the response.
+fn send(port: u16, path: &str) -> Result<TcpStream> {
+    let mut stream: TcpStream = TcpStream::connect(("127.0.0.1", port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path)?;
+    Ok(stream)
+}
+
+/// Sends raw bytes as a request and reads the whole response, which ends when the server closes the connection.
+fn exchange(port: u16, request: &[u8]) -> Result<String> {
+    let mut stream: TcpStream = TcpStream::connect(("127.0.0.1", port))?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.write_all(request)?;
+    stream.shutdown(Shutdown::Write)?;
+    let mut response: Vec<u8> = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(String::from_utf8_lossy(&response).to_string())
+}
+
+/// Builds a bearer token that grants some scopes.
+fn token(name: &str, secret: &str, scopes: &[Scope]) -> Result<ApiToken> {
+    let hash: String = hex::encode(Sha256::digest(secret.as_bytes()));
+    ApiToken::new(
+        name,
+        TokenKind::Bearer,
+        &hash,
+        scopes.iter().copied().collect::<HashSet<Scope>>(),
+    )
+}
+
+/// Sends a request for a path with an `Authorization` header, if any, and reads the whole response.
+fn exchange_with_token(port: u16, method: &str, path: &str, authorization: Option<&str>) -> Result<String> {
+    let header: String = match authorization {
+        Some(authorization) => format!("Authorization: {}\r\n", authorization),
+        None => String::new(),
+    };
+    let request: String = format!("{} {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n", method, path, header);
+    exchange(port, request.as_bytes())
+}
+
+/// Reads the status line of a response.
+fn status_line(stream: &TcpStream) -> Result<String> {
+    let mut line: String = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(line.trim_end().to_string())
+}
+
+#[test]
+fn server_bounds_long_lived_connections() -> Result<()> {
+    let port: u16 = start_server()?;
+
+    let streams: Vec<TcpStream> = (0..HttpServer::STREAM_MAX)
+        .map(|_| send(port, "/run"))
+        .collect::<Result<Vec<TcpStream>>>()?;
+    thread::sleep(Duration::from_millis(500));
+
+    // Long-lived requests are turned away once their allowance is used up, but other requests are still served.
+    assert_eq!(status_line(&send(port, "/run")?)?, "HTTP/1.1 503 Service Unavailable");
+    assert_eq!(status_line(&send(port, "/jobs")?)?, "HTTP/1.1 200 OK");
+    for stream in &streams {
+        assert_eq!(status_line(stream)?, "HTTP/1.1 200 OK");
+    }
+    Ok(())
+}
+
+#[test]
+fn parser_reads_bodies() -> Result<()> {
+    let port: u16 = start_server()?;
+
+    let response: String = exchange(port, b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello")?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nhello\r\n"));
+
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3;ext=1\r\nhel\r\n2\r\nlo\r\n0\r\nX-Trailer: 1\r\n\r\n",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with("\r\n\r\nhello\r\n"));
+    Ok(())
+}
+
+#[test]
+fn parser_rejects_oversized_requests() -> Result<()> {
+    let port: u16 = start_server()?;
+    let long: String = "a".repeat(9 * 1024);
+
+    // Request line.
+    let request: String = format!("GET /{} HTTP/1.1\r\n\r\n", long);
+    let response: String = exchange(port, request.as_bytes())?;
+    assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+
+    // Header line.
+    let request: String = format!("GET /jobs HTTP/1.1\r\nX-Long: {}\r\n\r\n", long);
+    let response: String = exchange(port, request.as_bytes())?;
+    assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+
+    // Number of headers.
+    let headers: String = (0..101).map(|i| format!("X-Header-{}: {}\r\n", i, i)).collect();
+    let request: String = format!("GET /jobs HTTP/1.1\r\n{}\r\n", headers);
+    let response: String = exchange(port, request.as_bytes())?;
+    assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+
+    // Announced body.
+    let response: String = exchange(port, b"POST /echo HTTP/1.1\r\nContent-Length: 2000000\r\n\r\n")?;
+    assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+    Ok(())
+}
+
+#[test]
+fn parser_rejects_overflowing_chunk_sizes() -> Result<()> {
+    let port: u16 = start_server()?;
+
+    // Chunk sizes that add up past the maximum body size must not wrap around.
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\nfffffffffffffffe\r\n",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 413 Payload Too Large\r\n"));
+
+    // Chunk sizes that do not even fit in a word.
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n10000000000000000\r\n",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    Ok(())
+}
+
+#[test]
+fn parser_rejects_truncated_bodies() -> Result<()> {
+    let port: u16 = start_server()?;
+
+    let response: String = exchange(port, b"POST /echo HTTP/1.1\r\nContent-Length: 10\r\n\r\nhello")?;
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\na\r\nhello",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    // The last chunk is missing.
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    Ok(())
+}
+
+#[test]
+fn parser_rejects_ambiguous_body_lengths() -> Result<()> {
+    let port: u16 = start_server()?;
+
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nContent-Length: 5\r\nContent-Length: 6\r\n\r\nhello!",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+
+    let response: String = exchange(
+        port,
+        b"POST /echo HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n0\r\n\r\n",
+    )?;
+    assert!(response.starts_with("HTTP/1.1 501 Not Implemented\r\n"));
+    Ok(())
+}
+
+#[test]
+fn server_authorizes_requests_with_bearer_tokens() -> Result<()> {
+    let tokens: Vec<ApiToken> = vec![
+        token("viewer", "view-secret", &[Scope::View])?,
+        token("admin", "admin-secret", &[Scope::Admin])?,
+    ];
+    let port: u16 = start_server_with(Some(Authenticator::new(tokens, required_scope)))?;
+
+    // Missing, malformed, and unknown tokens are challenged.
+    for authorization in [
+        None,
+        Some("Basic dmlldy1zZWNyZXQ="),
+        Some("Bearer wrong-secret"),
+        Some("view-secret"),
+    ] {
+        let response: String = exchange_with_token(port, "GET", "/jobs", authorization)?;
+        assert!(
+            response.starts_with("HTTP/1.1 401 Unauthorized\r\n"),
+            "{:?}",
+            authorization
+        );
+        assert!(
+            response.contains("\r\nwww-authenticate: Bearer\r\n"),
+            "{:?}",
+            authorization
+        );
+    }
+
+    // Known tokens are accepted, provided they grant the scope of the endpoint.
+    let response: String = exchange_with_token(port, "GET", "/jobs", Some("Bearer view-secret "))?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    let response: String = exchange_with_token(port, "GET", "/jobs/0/cancel", Some("Bearer view-secret"))?;
+    assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    assert!(!response.contains("www-authenticate"));
+    let response: String = exchange_with_token(port, "GET", "/run?job=build", Some("Bearer view-secret"))?;
+    assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+
+    // Admin tokens grant every scope.
+    let response: String = exchange_with_token(port, "GET", "/jobs/0/cancel", Some("Bearer admin-secret"))?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // Webhooks are authenticated by their signature instead.
+    let response: String = exchange_with_token(port, "POST", "/webhook/github", None)?;
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    Ok(())
+}
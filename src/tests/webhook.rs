@@ -0,0 +1,109 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::webhook::{GithubWebhook, WebhookEvent, WebhookEventKind, WebhookRule};
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Computes the value of the `X-Hub-Signature-256` header for a payload.
+fn sign(secret: &str, payload: &[u8]) -> Result<String> {
+    let mut mac: Hmac<Sha256> = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+    mac.update(payload);
+    Ok(format!("sha256={}", hex::encode(mac.finalize().into_bytes())))
+}
+
+/// Builds the payload of a push to a branch.
+fn push_payload(branch: &str) -> String {
+    format!(
+        r#"{{"ref":"refs/heads/{}","after":"abc123","repository":{{"full_name":"demikernel/demikernel"}}}}"#,
+        branch
+    )
+}
+
+/// Builds the payload of a pull request that merges a head branch into a base branch.
+fn pull_request_payload(head: &str, base: &str) -> String {
+    format!(
+        r#"{{"action":"opened","number":42,"pull_request":{{"head":{{"ref":"{}","sha":"abc123"}},"base":{{"ref":"{}","sha":"def456"}}}},"repository":{{"full_name":"demikernel/demikernel"}}}}"#,
+        head, base
+    )
+}
+
+/// Parses an event that is expected to trigger jobs.
+fn parse(webhook: &GithubWebhook, event: &str, payload: &str) -> Result<WebhookEvent> {
+    match webhook.parse_event(event, payload.as_bytes())? {
+        Some(event) => Ok(event),
+        None => anyhow::bail!("event was ignored (event={:?})", event),
+    }
+}
+
+#[test]
+fn webhook_verifies_signatures() -> Result<()> {
+    let webhook: GithubWebhook = GithubWebhook::new("secret", Vec::new());
+    let payload: String = push_payload("main");
+    let signature: String = sign("secret", payload.as_bytes())?;
+
+    assert!(webhook.verify(payload.as_bytes(), &signature));
+    // Tampered payload.
+    assert!(!webhook.verify(push_payload("dev").as_bytes(), &signature));
+    // Signed with another secret.
+    assert!(!webhook.verify(payload.as_bytes(), &sign("other", payload.as_bytes())?));
+    // Malformed signatures.
+    assert!(!webhook.verify(payload.as_bytes(), signature.trim_start_matches("sha256=")));
+    assert!(!webhook.verify(payload.as_bytes(), "sha256=not-hex"));
+    // Missing `X-Hub-Signature-256` header.
+    assert!(!webhook.verify(payload.as_bytes(), ""));
+    Ok(())
+}
+
+#[test]
+fn webhook_matches_rules_on_branches() -> Result<()> {
+    let webhook: GithubWebhook = GithubWebhook::new(
+        "secret",
+        vec![
+            WebhookRule::new(
+                "demikernel/demikernel",
+                Some(WebhookEventKind::Push),
+                Some("main"),
+                "push-main.yaml",
+            ),
+            WebhookRule::new(
+                "demikernel/demikernel",
+                Some(WebhookEventKind::PullRequest),
+                Some("main"),
+                "pr-main.yaml",
+            ),
+            WebhookRule::new("Demikernel/Demikernel", None, Some("dev"), "any-dev.yaml"),
+            WebhookRule::new("demikernel/other", None, None, "other.yaml"),
+        ],
+    );
+
+    // Pushes match on the branch that was pushed to.
+    let event: WebhookEvent = parse(&webhook, "push", &push_payload("main"))?;
+    assert_eq!(webhook.jobs(&event), vec!["push-main.yaml"]);
+    let event: WebhookEvent = parse(&webhook, "push", &push_payload("dev"))?;
+    assert_eq!(webhook.jobs(&event), vec!["any-dev.yaml"]);
+
+    // Pull requests match on the branch they merge into, not on the branch they come from.
+    let event: WebhookEvent = parse(&webhook, "pull_request", &pull_request_payload("dev", "main"))?;
+    assert_eq!(webhook.jobs(&event), vec!["pr-main.yaml"]);
+    assert_eq!(event.parameters().get("BASE_BRANCH").map(String::as_str), Some("main"));
+    assert_eq!(event.parameters().get("BRANCH").map(String::as_str), Some("dev"));
+    let event: WebhookEvent = parse(&webhook, "pull_request", &pull_request_payload("main", "dev"))?;
+    assert_eq!(webhook.jobs(&event), vec!["any-dev.yaml"]);
+
+    // Tags and other events do not trigger jobs.
+    let tag: String =
+        r#"{"ref":"refs/tags/v1.0","after":"abc123","repository":{"full_name":"demikernel/demikernel"}}"#.to_string();
+    assert!(webhook.parse_event("push", tag.as_bytes())?.is_none());
+    assert!(webhook.parse_event("issues", b"{}")?.is_none());
+    Ok(())
+}
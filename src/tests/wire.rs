@@ -0,0 +1,270 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use anyhow::Result;
+use ring::aead::chacha20_poly1305_openssh::{OpeningKey, SealingKey, KEY_LEN, PACKET_LENGTH_LEN, TAG_LEN};
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Builder of SSH messages.
+#[derive(Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+}
+
+/// Parser of SSH messages.
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+/// Receiving half of the SSH binary packet protocol.
+pub struct PacketReader {
+    stream: TcpStream,
+    key: Option<OpeningKey>,
+    sequence: u32,
+}
+
+/// Sending half of the SSH binary packet protocol.
+pub struct PacketWriter {
+    stream: TcpStream,
+    key: Option<SealingKey>,
+    sequence: u32,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Encoder {
+    /// Instantiates a new [Encoder] for a message of a given type.
+    pub fn message(kind: u8) -> Self {
+        Self { bytes: vec![kind] }
+    }
+
+    pub fn u8(mut self, value: u8) -> Self {
+        self.bytes.push(value);
+        self
+    }
+
+    pub fn u32(mut self, value: u32) -> Self {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn bool(self, value: bool) -> Self {
+        self.u8(value as u8)
+    }
+
+    pub fn bytes(mut self, value: &[u8]) -> Self {
+        self.bytes.extend_from_slice(value);
+        self
+    }
+
+    pub fn string(self, value: &[u8]) -> Self {
+        self.u32(value.len() as u32).bytes(value)
+    }
+
+    pub fn str(self, value: &str) -> Self {
+        self.string(value.as_bytes())
+    }
+
+    /// Appends an unsigned big-endian integer in the `mpint` format.
+    pub fn mpint(self, value: &[u8]) -> Self {
+        let value: &[u8] = match value.iter().position(|b| *b != 0) {
+            Some(start) => &value[start..],
+            None => &[],
+        };
+        match value.first() {
+            Some(b) if b & 0x80 != 0 => self.u32(value.len() as u32 + 1).u8(0).bytes(value),
+            _ => self.string(value),
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<'a> Decoder<'a> {
+    /// Instantiates a new [Decoder] that skips the type of a message.
+    pub fn message(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes: bytes.get(1..).unwrap_or_default(),
+        }
+    }
+
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        let bytes: &[u8] = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn string(&mut self) -> Result<&'a [u8]> {
+        let len: usize = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub fn str(&mut self) -> Result<String> {
+        Ok(String::from_utf8_lossy(self.string()?).to_string())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            anyhow::bail!("truncated message");
+        }
+        let (head, tail): (&[u8], &[u8]) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+}
+
+impl PacketReader {
+    /// Largest packet that is accepted.
+    const MAX_PACKET_LEN: usize = 256 * 1024;
+
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
+            stream,
+            key: None,
+            sequence: 0,
+        }
+    }
+
+    /// Reads the identification line of the peer, skipping any lines that precede it.
+    pub fn read_version(&mut self) -> Result<String> {
+        loop {
+            let mut line: Vec<u8> = Vec::new();
+            let mut byte: [u8; 1] = [0];
+            while line.last() != Some(&b'\n') {
+                self.stream.read_exact(&mut byte)?;
+                line.push(byte[0]);
+            }
+            let line: String = String::from_utf8_lossy(&line).trim_end().to_string();
+            if line.starts_with("SSH-") {
+                return Ok(line);
+            }
+        }
+    }
+
+    /// Encrypts subsequent packets with a key.
+    pub fn set_key(&mut self, key: &[u8; KEY_LEN]) {
+        self.key = Some(OpeningKey::new(key));
+    }
+
+    /// Reads the payload of the next packet.
+    pub fn read(&mut self) -> Result<Vec<u8>> {
+        let mut length: [u8; PACKET_LENGTH_LEN] = [0; PACKET_LENGTH_LEN];
+        self.stream.read_exact(&mut length)?;
+        let sequence: u32 = self.sequence;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let packet: Vec<u8> = match &self.key {
+            None => {
+                let mut packet: Vec<u8> = vec![0; Self::check_length(u32::from_be_bytes(length))?];
+                self.stream.read_exact(&mut packet)?;
+                packet
+            },
+            Some(key) => {
+                let len: usize = Self::check_length(u32::from_be_bytes(key.decrypt_packet_length(sequence, length)))?;
+                let mut packet: Vec<u8> = length.to_vec();
+                packet.resize(PACKET_LENGTH_LEN + len, 0);
+                self.stream.read_exact(&mut packet[PACKET_LENGTH_LEN..])?;
+                let mut tag: [u8; TAG_LEN] = [0; TAG_LEN];
+                self.stream.read_exact(&mut tag)?;
+                match key.open_in_place(sequence, &mut packet, &tag) {
+                    Ok(plaintext) => plaintext.to_vec(),
+                    Err(_) => anyhow::bail!("failed to authenticate packet"),
+                }
+            },
+        };
+
+        let padding: usize = packet[0] as usize;
+        if padding + 1 > packet.len() {
+            anyhow::bail!("malformed packet");
+        }
+        Ok(packet[1..packet.len() - padding].to_vec())
+    }
+
+    fn check_length(len: u32) -> Result<usize> {
+        match len as usize {
+            len if len == 0 || len > Self::MAX_PACKET_LEN => anyhow::bail!("bad packet length (len={})", len),
+            len => Ok(len),
+        }
+    }
+}
+
+impl PacketWriter {
+    /// Cipher block size, which packets are padded to.
+    const BLOCK_SIZE: usize = 8;
+    /// Minimum amount of padding in a packet.
+    const MIN_PADDING: usize = 4;
+
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
+            stream,
+            key: None,
+            sequence: 0,
+        }
+    }
+
+    /// Writes the identification line of this end.
+    pub fn write_version(&mut self, version: &str) -> Result<()> {
+        self.stream.write_all(format!("{}\r\n", version).as_bytes())?;
+        Ok(())
+    }
+
+    /// Encrypts subsequent packets with a key.
+    pub fn set_key(&mut self, key: &[u8; KEY_LEN]) {
+        self.key = Some(SealingKey::new(key));
+    }
+
+    /// Writes a packet with a given payload.
+    pub fn write(&mut self, payload: &[u8]) -> Result<()> {
+        // The packet length is not part of the padded data when it is encrypted separately.
+        let unpadded: usize = match self.key {
+            Some(_) => 1 + payload.len(),
+            None => PACKET_LENGTH_LEN + 1 + payload.len(),
+        };
+        let mut padding: usize = Self::BLOCK_SIZE - unpadded % Self::BLOCK_SIZE;
+        if padding < Self::MIN_PADDING {
+            padding += Self::BLOCK_SIZE;
+        }
+
+        let mut packet: Vec<u8> = Vec::with_capacity(PACKET_LENGTH_LEN + 1 + payload.len() + padding + TAG_LEN);
+        packet.extend_from_slice(&((1 + payload.len() + padding) as u32).to_be_bytes());
+        packet.push(padding as u8);
+        packet.extend_from_slice(payload);
+        packet.resize(packet.len() + padding, 0);
+
+        if let Some(key) = &self.key {
+            let mut tag: [u8; TAG_LEN] = [0; TAG_LEN];
+            key.seal_in_place(self.sequence, &mut packet, &mut tag);
+            packet.extend_from_slice(&tag);
+        }
+        self.sequence = self.sequence.wrapping_add(1);
+
+        self.stream.write_all(&packet)?;
+        Ok(())
+    }
+}
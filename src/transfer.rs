@@ -0,0 +1,426 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{
+    action::{FileTransfer, TransferDirection},
+    cancellation::Cancellation,
+};
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use ssh2::{Channel, FileStat, OpenFlags, OpenType, Session, Sftp};
+use std::{
+    fs::{self, File, Metadata},
+    io::{self, ErrorKind, Read, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Copies files and directories between the orchestrator and a remote host over SFTP. Every file that is copied is
+/// checked against a SHA-256 checksum that is computed on the remote host.
+pub struct FileCopier<'a> {
+    /// Session with the remote host.
+    session: &'a Session,
+    /// SFTP channel on top of the session.
+    sftp: Sftp,
+    /// Point in time after which the copy is abandoned.
+    deadline: Option<Instant>,
+    /// Cancellation of the job that requested the copy, which also abandons it.
+    cancellation: &'a Cancellation,
+    /// Callback to which progress lines are handed.
+    on_line: &'a mut dyn FnMut(String),
+    /// Was the copy abandoned because it timed out?
+    timed_out: bool,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl<'a> FileCopier<'a> {
+    /// Size of the chunks in which files are copied.
+    const CHUNK_SIZE: usize = 64 * 1024;
+    /// Files that are at least this large have their progress reported.
+    const PROGRESS_THRESHOLD: u64 = 1024 * 1024;
+    /// Progress is reported whenever this percentage of a file has been copied.
+    const PROGRESS_STEP: u64 = 10;
+    /// Mode of directories that are created on the remote host.
+    const DIRECTORY_MODE: i32 = 0o755;
+
+    /// Instantiates a new [FileCopier] on top of an existing session.
+    pub fn new(
+        session: &'a Session,
+        deadline: Option<Instant>,
+        cancellation: &'a Cancellation,
+        on_line: &'a mut dyn FnMut(String),
+    ) -> Result<Self> {
+        let sftp: Sftp = match session.sftp() {
+            Ok(sftp) => sftp,
+            Err(e) => {
+                let msg: String = format!("failed to open sftp channel (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        Ok(Self {
+            session,
+            sftp,
+            deadline,
+            cancellation,
+            on_line,
+            timed_out: false,
+        })
+    }
+
+    /// Checks if the last copy was abandoned because it timed out.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Performs a file transfer. Directories are copied recursively.
+    pub fn copy(&mut self, transfer: &FileTransfer) -> Result<()> {
+        let source: &Path = Path::new(transfer.source());
+        let destination: &Path = Path::new(transfer.destination());
+        match transfer.direction() {
+            TransferDirection::Upload => self.upload(source, destination),
+            TransferDirection::Download => self.download(source, destination),
+        }
+    }
+
+    /// Copies a local file or directory to the remote host.
+    fn upload(&mut self, source: &Path, destination: &Path) -> Result<()> {
+        let metadata: Metadata = match fs::metadata(source) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                let msg: String = format!("failed to stat local file (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        if !metadata.is_dir() {
+            return self.upload_file(source, destination, &metadata);
+        }
+
+        // Create the remote directory, unless it already exists.
+        if self.sftp.stat(destination).is_err() {
+            if let Err(e) = self.sftp.mkdir(destination, Self::DIRECTORY_MODE) {
+                let msg: String = format!("failed to create remote directory (path={:?}, e={:?})", destination, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            }
+        }
+
+        let mut entries: Vec<PathBuf> = match fs::read_dir(source) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect(),
+            Err(e) => {
+                let msg: String = format!("failed to read local directory (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        entries.sort();
+        for entry in entries {
+            if let Some(name) = entry.file_name() {
+                self.upload(&entry, &destination.join(name))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies a local file to the remote host, preserving its permissions.
+    fn upload_file(&mut self, source: &Path, destination: &Path, metadata: &Metadata) -> Result<()> {
+        let mut local_file: File = match File::open(source) {
+            Ok(file) => file,
+            Err(e) => {
+                let msg: String = format!("failed to open local file (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let mode: i32 = (metadata.permissions().mode() & 0o777) as i32;
+        let flags: OpenFlags = OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE;
+        let mut remote_file: ssh2::File = match self.sftp.open_mode(destination, flags, mode, OpenType::File) {
+            Ok(file) => file,
+            Err(e) => {
+                let msg: String = format!("failed to create remote file (path={:?}, e={:?})", destination, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let label: String = format!("[upload] {} -> {}", source.display(), destination.display());
+        let checksum: String = self.copy_stream(&mut local_file, &mut remote_file, metadata.len(), &label)?;
+        drop(remote_file);
+
+        self.verify(destination, &checksum, metadata.len(), &label)
+    }
+
+    /// Copies a remote file or directory to the orchestrator.
+    fn download(&mut self, source: &Path, destination: &Path) -> Result<()> {
+        let stat: FileStat = match self.sftp.stat(source) {
+            Ok(stat) => stat,
+            Err(e) => {
+                let msg: String = format!("failed to stat remote file (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        if !stat.is_dir() {
+            return self.download_file(source, destination, &stat);
+        }
+
+        if let Err(e) = fs::create_dir_all(destination) {
+            let msg: String = format!("failed to create local directory (path={:?}, e={:?})", destination, e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        let mut entries: Vec<PathBuf> = match self.sftp.readdir(source) {
+            Ok(entries) => entries.into_iter().map(|(path, _)| path).collect(),
+            Err(e) => {
+                let msg: String = format!("failed to read remote directory (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        entries.sort();
+        for entry in entries {
+            if let Some(name) = entry.file_name() {
+                self.download(&entry, &destination.join(name))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies a remote file to the orchestrator, preserving its permissions.
+    fn download_file(&mut self, source: &Path, destination: &Path, stat: &FileStat) -> Result<()> {
+        let mut remote_file: ssh2::File = match self.sftp.open(source) {
+            Ok(file) => file,
+            Err(e) => {
+                let msg: String = format!("failed to open remote file (path={:?}, e={:?})", source, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        if let Some(parent) = destination.parent() {
+            if !parent.as_os_str().is_empty() {
+                if let Err(e) = fs::create_dir_all(parent) {
+                    let msg: String = format!("failed to create local directory (path={:?}, e={:?})", parent, e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                }
+            }
+        }
+
+        let mut local_file: File = match File::create(destination) {
+            Ok(file) => file,
+            Err(e) => {
+                let msg: String = format!("failed to create local file (path={:?}, e={:?})", destination, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let size: u64 = stat.size.unwrap_or(0);
+        let label: String = format!("[download] {} -> {}", source.display(), destination.display());
+        let checksum: String = self.copy_stream(&mut remote_file, &mut local_file, size, &label)?;
+
+        if let Some(perm) = stat.perm {
+            if let Err(e) = fs::set_permissions(destination, fs::Permissions::from_mode(perm & 0o777)) {
+                log::warn!("failed to set permissions (path={:?}, e={:?})", destination, e);
+            }
+        }
+
+        self.verify(source, &checksum, size, &label)
+    }
+
+    /// Copies a stream in chunks, reporting progress along the way, and returns the SHA-256 checksum of the data.
+    fn copy_stream(&mut self, reader: &mut dyn Read, writer: &mut dyn Write, size: u64, label: &str) -> Result<String> {
+        let mut hasher: Sha256 = Sha256::new();
+        let mut buf: Vec<u8> = vec![0; Self::CHUNK_SIZE];
+        let mut copied: u64 = 0;
+        let mut next_report: u64 = Self::PROGRESS_STEP;
+
+        loop {
+            self.check_progress(label)?;
+
+            let n: usize = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                // The read stalled, so check the deadline and cancellation before trying again.
+                Err(e) if is_stalled(&e) => continue,
+                Err(e) => {
+                    let msg: String = format!("failed to read ({}, e={:?})", label, e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+
+            let mut written: usize = 0;
+            while written < n {
+                match writer.write(&buf[written..n]) {
+                    Ok(0) => {
+                        let msg: String = format!("failed to write ({}, e=zero bytes written)", label);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                    Ok(m) => written += m,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                    // The write stalled, so check the deadline and cancellation before trying again.
+                    Err(e) if is_stalled(&e) => self.check_progress(label)?,
+                    Err(e) => {
+                        let msg: String = format!("failed to write ({}, e={:?})", label, e);
+                        log::error!("{}", msg);
+                        anyhow::bail!(msg);
+                    },
+                }
+            }
+            hasher.update(&buf[..n]);
+            copied += n as u64;
+
+            // Report progress of large files.
+            if size >= Self::PROGRESS_THRESHOLD {
+                let percent: u64 = copied * 100 / size;
+                if percent >= next_report && percent < 100 {
+                    self.report(format!("{} {}% ({}/{} bytes)", label, percent, copied, size));
+                    next_report = percent - percent % Self::PROGRESS_STEP + Self::PROGRESS_STEP;
+                }
+            }
+        }
+
+        loop {
+            match writer.flush() {
+                Ok(()) => break,
+                Err(e) if is_stalled(&e) => self.check_progress(label)?,
+                Err(e) => {
+                    let msg: String = format!("failed to flush ({}, e={:?})", label, e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            }
+        }
+
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Fails if the copy of the target [FileCopier] has run past its deadline or was cancelled.
+    fn check_progress(&mut self, label: &str) -> Result<()> {
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                self.timed_out = true;
+                let msg: String = format!("transfer timed out ({})", label);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            }
+        }
+        if self.cancellation.is_cancelled() {
+            let msg: String = format!("transfer cancelled ({})", label);
+            log::warn!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a copied file matches its counterpart on the remote host.
+    fn verify(&mut self, remote_path: &Path, checksum: &str, size: u64, label: &str) -> Result<()> {
+        let remote_checksum: String = self.remote_checksum(remote_path, label)?;
+        if remote_checksum != checksum {
+            let msg: String = format!(
+                "checksum mismatch ({}, expected={}, actual={})",
+                label, checksum, remote_checksum
+            );
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+
+        self.report(format!("{} ({} bytes, sha256={})", label, size, checksum));
+        Ok(())
+    }
+
+    /// Computes the SHA-256 checksum of a file on the remote host.
+    fn remote_checksum(&mut self, path: &Path, label: &str) -> Result<String> {
+        let mut channel: Channel = match self.session.channel_session() {
+            Ok(channel) => channel,
+            Err(e) => {
+                let msg: String = format!("failed to open session-based channel (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let cmdline: String = format!("sha256sum -- {}", quote(&path.to_string_lossy()));
+        let mut output: Vec<u8> = Vec::new();
+        let result: Result<()> = match channel.exec(&cmdline) {
+            Ok(()) => self.read_checksum(&mut channel, &mut output, label),
+            Err(e) => Err(anyhow::anyhow!("failed to execute command (e={:?})", e)),
+        };
+
+        if let Err(e) = channel.close().and_then(|_| channel.wait_close()) {
+            log::warn!("failed to close channel (e={:?})", e);
+        }
+        result?;
+
+        let output: String = String::from_utf8_lossy(&output).to_string();
+        match (channel.exit_status(), output.split_whitespace().next()) {
+            (Ok(0), Some(checksum)) => Ok(checksum.to_string()),
+            _ => {
+                let msg: String = format!("failed to compute remote checksum (path={:?})", path);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Reads the output of a checksum command until the remote host closes the channel.
+    fn read_checksum(&mut self, channel: &mut Channel, output: &mut Vec<u8>, label: &str) -> Result<()> {
+        let mut buf: [u8; 256] = [0; 256];
+        loop {
+            match channel.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(n) => output.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => {},
+                // Checksums of large files take a while, so check the deadline and cancellation while waiting.
+                Err(e) if is_stalled(&e) => self.check_progress(label)?,
+                Err(e) => anyhow::bail!("failed to read checksum (e={:?})", e),
+            }
+        }
+    }
+
+    /// Hands a progress line to the callback of the target [FileCopier].
+    fn report(&mut self, line: String) {
+        log::info!("{}", line);
+        (self.on_line)(line);
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Checks if an I/O operation gave up because the session timeout elapsed, in which case it may be retried.
+fn is_stalled(e: &io::Error) -> bool {
+    e.kind() == ErrorKind::TimedOut || e.kind() == ErrorKind::WouldBlock
+}
+
+/// Quotes a string for use as a single argument in a shell command line.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
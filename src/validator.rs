@@ -0,0 +1,788 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use crate::{action::TransferDirection, config::Config, job::Job};
+use ::anyhow::Result;
+use ::yaml_rust::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::{Marker, ScanError, TScalarStyle},
+    yaml::Hash,
+    Yaml,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    io::Read,
+};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Severity of a [Diagnostic].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The job cannot run, or it does not do what it says.
+    Error,
+    /// The job runs, but likely not as intended.
+    Warning,
+}
+
+/// Problem found in a job file.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    path: String,
+    /// Line of the job file on which the problem is found, starting from one.
+    line: usize,
+    severity: Severity,
+    message: String,
+}
+
+/// Node of a YAML document, along with the line on which it starts.
+#[derive(Debug, Clone)]
+struct Node {
+    value: NodeValue,
+    line: usize,
+}
+
+#[derive(Debug, Clone)]
+enum NodeValue {
+    Scalar(Yaml),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Node, Node)>),
+}
+
+/// Sequence or mapping whose entries are still being parsed.
+struct PendingNode {
+    node: Node,
+    anchor: usize,
+    /// Key of a mapping entry whose value is yet to be parsed.
+    key: Option<Node>,
+}
+
+/// Builds [Node]s out of the events of a YAML parser, which unlike [yaml_rust::YamlLoader] keeps track of lines.
+#[derive(Default)]
+struct NodeBuilder {
+    docs: Vec<Node>,
+    stack: Vec<PendingNode>,
+    anchors: HashMap<usize, Node>,
+}
+
+/// Checks job files for mistakes that would otherwise only be found when they are run, or not at all.
+pub struct JobValidator {
+    path: String,
+    diagnostics: Vec<Diagnostic>,
+    /// Names of the actions seen so far, along with the lines on which they are declared. Names may repeat.
+    action_names: HashMap<String, Vec<usize>>,
+    /// Names of the workers seen so far, in the order in which they first run something.
+    worker_names: Vec<String>,
+    /// Labels required by each worker so far, along with the lines on which they are declared.
+    requirements: HashMap<String, HashMap<String, (String, usize)>>,
+    /// Dependencies between actions, as (action, dependency, line) tuples.
+    needs: Vec<(String, String, usize)>,
+    /// Environment variables referenced by commands, along with the lines on which they are referenced.
+    references: Vec<(String, usize)>,
+    /// Line of the last barrier seen so far.
+    last_barrier: Option<usize>,
+    /// Number of actions seen since the last barrier.
+    actions_since_barrier: usize,
+    /// Line of the job entry.
+    job_line: usize,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl Diagnostic {
+    /// Returns the severity of the target [Diagnostic].
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+}
+
+impl Node {
+    fn as_str(&self) -> Option<&str> {
+        match &self.value {
+            NodeValue::Scalar(Yaml::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_mapping(&self) -> Option<&Vec<(Node, Node)>> {
+        match &self.value {
+            NodeValue::Mapping(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Looks up an entry of a mapping by its key.
+    fn get(&self, key: &str) -> Option<&Node> {
+        self.as_mapping()?
+            .iter()
+            .find(|(k, _)| k.as_str() == Some(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Converts the target [Node] into the value that [yaml_rust::YamlLoader] would have produced for it.
+    fn to_yaml(&self) -> Yaml {
+        match &self.value {
+            NodeValue::Scalar(yaml) => yaml.clone(),
+            NodeValue::Sequence(nodes) => Yaml::Array(nodes.iter().map(|node| node.to_yaml()).collect()),
+            NodeValue::Mapping(entries) => {
+                let mut hash: Hash = Hash::new();
+                for (key, value) in entries {
+                    hash.insert(key.to_yaml(), value.to_yaml());
+                }
+                Yaml::Hash(hash)
+            },
+        }
+    }
+}
+
+impl NodeBuilder {
+    /// Parses all documents of a YAML stream.
+    fn load(source: &str) -> Result<Vec<Node>, ScanError> {
+        let mut builder: NodeBuilder = NodeBuilder::default();
+        Parser::new(source.chars()).load(&mut builder, true)?;
+        Ok(builder.docs)
+    }
+
+    /// Attaches a complete node to the node that contains it, if any.
+    fn insert(&mut self, node: Node, anchor: usize) {
+        // Anchors are numbered from one.
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        match self.stack.last_mut() {
+            None => self.docs.push(node),
+            Some(parent) => match &mut parent.node.value {
+                NodeValue::Sequence(nodes) => nodes.push(node),
+                NodeValue::Mapping(entries) => match parent.key.take() {
+                    Some(key) => entries.push((key, node)),
+                    None => parent.key = Some(node),
+                },
+                NodeValue::Scalar(_) => unreachable!("scalars do not contain other nodes"),
+            },
+        }
+    }
+}
+
+impl JobValidator {
+    /// Parameters that are set by the server itself, either for all jobs (`JOB`) or for jobs triggered by webhooks.
+    const SERVER_PARAMETERS: [&'static str; 7] = [
+        "JOB",
+        "EVENT",
+        "REPOSITORY",
+        "BRANCH",
+        "SHA",
+        "BASE_BRANCH",
+        "PR_NUMBER",
+    ];
+
+    /// Checks a job file and returns the problems found in it, ordered by line. Fails only if the file cannot be read.
+    pub fn validate(job_path: &str) -> Result<Vec<Diagnostic>> {
+        let mut job_s: String = String::new();
+        File::open(job_path)?.read_to_string(&mut job_s)?;
+
+        let mut validator: Self = Self {
+            path: job_path.to_string(),
+            diagnostics: Vec::new(),
+            action_names: HashMap::new(),
+            worker_names: Vec::new(),
+            requirements: HashMap::new(),
+            needs: Vec::new(),
+            references: Vec::new(),
+            last_barrier: None,
+            actions_since_barrier: 0,
+            job_line: 1,
+        };
+
+        match NodeBuilder::load(&job_s) {
+            Ok(docs) => validator.check_docs(&docs),
+            Err(e) => validator.error(e.marker().line(), format!("malformed job file (e={})", e)),
+        }
+
+        // Look for the problems that are only found once the whole job has been read, such as dependency cycles.
+        if validator.num_errors() == 0 {
+            if let Err(e) = Job::load(job_path, HashMap::new()) {
+                validator.error(validator.job_line, e.to_string());
+            }
+        }
+
+        validator.diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+        Ok(validator.diagnostics)
+    }
+
+    fn num_errors(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .count()
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.report(line, Severity::Error, message);
+    }
+
+    fn warning(&mut self, line: usize, message: String) {
+        self.report(line, Severity::Warning, message);
+    }
+
+    fn report(&mut self, line: usize, severity: Severity, message: String) {
+        self.diagnostics.push(Diagnostic {
+            path: self.path.clone(),
+            line,
+            severity,
+            message,
+        });
+    }
+
+    /// Reports an error if parsing an entry fails.
+    fn check<T>(&mut self, node: &Node, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error(node.line, e.to_string());
+                None
+            },
+        }
+    }
+
+    /// Checks that the keys of a mapping are known and distinct.
+    fn check_keys(&mut self, entries: &[(Node, Node)], known_keys: &[&str], context: &str) {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for (key, _) in entries {
+            match key.as_str() {
+                Some(name) if !known_keys.contains(&name) => {
+                    self.error(key.line, format!("unknown entry (entry={:?}, {})", name, context));
+                },
+                Some(name) => {
+                    if let Some(line) = seen.insert(name.to_string(), key.line) {
+                        self.error(
+                            key.line,
+                            format!(
+                                "duplicate entry (entry={:?}, {}, previous_line={})",
+                                name, context, line
+                            ),
+                        );
+                    }
+                },
+                None => self.error(
+                    key.line,
+                    format!("malformed entry key (key={:?}, {})", key.to_yaml(), context),
+                ),
+            }
+        }
+    }
+
+    fn check_docs(&mut self, docs: &[Node]) {
+        let doc: &Node = match docs.first() {
+            Some(doc) => doc,
+            None => {
+                self.error(1, format!("missing {} entry", Job::JOB_ENTRY_NAME));
+                return;
+            },
+        };
+        if let Some(other) = docs.get(1) {
+            self.warning(other.line, "only the first document of a job file is read".to_string());
+        }
+
+        let entries: &Vec<(Node, Node)> = match doc.as_mapping() {
+            Some(entries) => entries,
+            None => {
+                self.error(doc.line, "malformed job file, expected a mapping".to_string());
+                return;
+            },
+        };
+        self.check_keys(
+            entries,
+            &[
+                Job::JOB_ENTRY_NAME,
+                Job::TIMEOUT_ENTRY_NAME,
+                Job::MATRIX_ENTRY_NAME,
+                Job::PRIORITY_ENTRY_NAME,
+                Job::ALLOCATION_TIMEOUT_ENTRY_NAME,
+                Job::SECRETS_ENTRY_NAME,
+            ],
+            "scope=job file",
+        );
+
+        // Check job-level entries, and collect the environment variables that they define.
+        let mut defined: HashSet<String> = Self::SERVER_PARAMETERS.iter().map(|name| name.to_string()).collect();
+        if let Some(timeout) = doc.get(Job::TIMEOUT_ENTRY_NAME) {
+            self.check(timeout, Job::parse_timeout(&timeout.to_yaml()));
+        }
+        if let Some(allocation_timeout) = doc.get(Job::ALLOCATION_TIMEOUT_ENTRY_NAME) {
+            self.check(allocation_timeout, Job::parse_timeout(&allocation_timeout.to_yaml()));
+        }
+        if let Some(priority) = doc.get(Job::PRIORITY_ENTRY_NAME) {
+            if !matches!(priority.to_yaml(), Yaml::Integer(_)) {
+                self.error(
+                    priority.line,
+                    format!(
+                        "failed to parse {} entry (entry={:?})",
+                        Job::PRIORITY_ENTRY_NAME,
+                        priority.to_yaml()
+                    ),
+                );
+            }
+        }
+        if let Some(secrets) = doc.get(Job::SECRETS_ENTRY_NAME) {
+            let names: Vec<String> = self
+                .check(secrets, Job::parse_names(&secrets.to_yaml(), Job::SECRETS_ENTRY_NAME))
+                .unwrap_or_default();
+            defined.extend(names.iter().map(|name| name.to_uppercase()));
+        }
+        if let Some(matrix) = doc.get(Job::MATRIX_ENTRY_NAME) {
+            if let Some(combinations) = self.check(matrix, Job::parse_matrix(&[doc.to_yaml()])) {
+                for (key, _) in combinations.first().into_iter().flatten() {
+                    defined.insert(key.to_uppercase().replace('-', "_"));
+                }
+            }
+        }
+
+        let job: &Node = match doc.get(Job::JOB_ENTRY_NAME) {
+            Some(job) => job,
+            None => {
+                self.error(doc.line, format!("missing {} entry", Job::JOB_ENTRY_NAME));
+                return;
+            },
+        };
+        self.job_line = job.line;
+        let tasks: &Vec<Node> = match &job.value {
+            NodeValue::Sequence(tasks) => tasks,
+            _ => {
+                self.error(
+                    job.line,
+                    format!("malformed {} entry, expected a list", Job::JOB_ENTRY_NAME),
+                );
+                return;
+            },
+        };
+        for task in tasks {
+            if task.get(Job::ACTION_ENTRY_NAME).is_some() {
+                self.check_action(task);
+            } else if task.get(Job::BARRIER_ENTRY_NAME).is_some() {
+                self.check_barrier(task);
+            } else {
+                self.error(
+                    task.line,
+                    format!(
+                        "unknown job entry, expected an {} or a {}",
+                        Job::ACTION_ENTRY_NAME,
+                        Job::BARRIER_ENTRY_NAME
+                    ),
+                );
+            }
+        }
+        if let (Some(line), 0) = (self.last_barrier, self.actions_since_barrier) {
+            self.warning(line, "barrier has no effect, since no action runs after it".to_string());
+        }
+
+        // Dependencies may refer to actions that are declared later on, and must refer to a single one.
+        let mut needed: HashSet<String> = HashSet::new();
+        for (action, name, line) in std::mem::take(&mut self.needs) {
+            if !self.action_names.contains_key(&name) {
+                self.error(
+                    line,
+                    format!("unknown dependency (action={:?}, needs={:?})", action, name),
+                );
+            }
+            needed.insert(name);
+        }
+        for name in needed {
+            let lines: Vec<usize> = self.action_names.get(&name).cloned().unwrap_or_default();
+            for pair in lines.windows(2) {
+                self.error(
+                    pair[1],
+                    format!(
+                        "duplicate action name, which another action needs (name={:?}, previous_line={})",
+                        name, pair[0]
+                    ),
+                );
+            }
+        }
+
+        // The scheduler exports the address of the runner that is assigned to each worker.
+        defined.extend(self.worker_names.iter().map(|name| name.to_uppercase()));
+        self.check_references(&defined);
+    }
+
+    fn check_action(&mut self, task: &Node) {
+        let entries: &Vec<(Node, Node)> = task.as_mapping().expect("actions are mappings");
+        let name: String = match task.get(Job::ACTION_ENTRY_NAME) {
+            Some(name) => match name.as_str() {
+                Some(name) => name.to_string(),
+                None => {
+                    self.error(name.line, format!("failed to parse {} entry", Job::ACTION_ENTRY_NAME));
+                    return;
+                },
+            },
+            None => unreachable!("actions have a name"),
+        };
+        self.check_keys(
+            entries,
+            &[
+                Job::ACTION_ENTRY_NAME,
+                Job::RUNS_ON_ENTRY_NAME,
+                Job::COMMANDS_ENTRY_NAME,
+                Job::UPLOAD_ENTRY_NAME,
+                Job::DOWNLOAD_ENTRY_NAME,
+                Job::TIMEOUT_ENTRY_NAME,
+                Job::NEEDS_ENTRY_NAME,
+                Job::LABELS_ENTRY_NAME,
+                Job::ARTIFACTS_ENTRY_NAME,
+            ],
+            &format!("action={:?}", name),
+        );
+        self.action_names.entry(name.clone()).or_default().push(task.line);
+        self.actions_since_barrier += 1;
+
+        // Check the worker on which the action runs.
+        let runs_on: Option<String> = match task.get(Job::RUNS_ON_ENTRY_NAME) {
+            Some(runs_on) => match runs_on.as_str() {
+                Some(worker) if !worker.is_empty() => Some(worker.to_string()),
+                _ => {
+                    self.error(
+                        runs_on.line,
+                        format!("failed to parse {} entry (action={:?})", Job::RUNS_ON_ENTRY_NAME, name),
+                    );
+                    None
+                },
+            },
+            None => {
+                self.error(
+                    task.line,
+                    format!("missing {} entry (action={:?})", Job::RUNS_ON_ENTRY_NAME, name),
+                );
+                None
+            },
+        };
+        if let Some(worker) = &runs_on {
+            if !self.worker_names.contains(worker) {
+                if let Some(line) = self.last_barrier {
+                    self.warning(
+                        task.line,
+                        format!(
+                            "worker first runs after the barrier on line {}, so it does not wait for it (worker={:?})",
+                            line, worker
+                        ),
+                    );
+                }
+                self.worker_names.push(worker.clone());
+            }
+        }
+
+        // Check what the action does.
+        let commands: Option<&Node> = task.get(Job::COMMANDS_ENTRY_NAME);
+        let upload: Option<&Node> = task.get(Job::UPLOAD_ENTRY_NAME);
+        let download: Option<&Node> = task.get(Job::DOWNLOAD_ENTRY_NAME);
+        match (commands, upload, download) {
+            (Some(commands), None, None) => self.check_commands(commands, &name),
+            (None, Some(upload), None) => self.check_transfer(upload, TransferDirection::Upload),
+            (None, None, Some(download)) => self.check_transfer(download, TransferDirection::Download),
+            (None, None, None) => self.error(
+                task.line,
+                format!("missing {} entry (action={:?})", Job::COMMANDS_ENTRY_NAME, name),
+            ),
+            _ => self.error(
+                task.line,
+                format!(
+                    "only one of {}, {}, and {} entries is allowed (action={:?})",
+                    Job::COMMANDS_ENTRY_NAME,
+                    Job::UPLOAD_ENTRY_NAME,
+                    Job::DOWNLOAD_ENTRY_NAME,
+                    name
+                ),
+            ),
+        }
+
+        if let Some(timeout) = task.get(Job::TIMEOUT_ENTRY_NAME) {
+            self.check(timeout, Job::parse_timeout(&timeout.to_yaml()));
+        }
+        if let Some(needs) = task.get(Job::NEEDS_ENTRY_NAME) {
+            if let Some(names) = self.check(needs, Job::parse_names(&needs.to_yaml(), Job::NEEDS_ENTRY_NAME)) {
+                for dependency in names {
+                    self.needs.push((name.clone(), dependency, needs.line));
+                }
+            }
+        }
+        if let Some(artifacts) = task.get(Job::ARTIFACTS_ENTRY_NAME) {
+            self.check(artifacts, Job::parse_artifacts(&artifacts.to_yaml()));
+        }
+        if let Some(labels) = task.get(Job::LABELS_ENTRY_NAME) {
+            let line: usize = labels.line;
+            let labels: HashMap<String, String> = match self.check(labels, Job::parse_labels(&labels.to_yaml())) {
+                Some(labels_) => labels_,
+                None => return,
+            };
+            let worker: String = match runs_on {
+                Some(worker) => worker,
+                None => return,
+            };
+            // Labels of all actions that run on a worker are merged, so they must agree with each other.
+            let mut conflicts: Vec<String> = Vec::new();
+            let requirements: &mut HashMap<String, (String, usize)> =
+                self.requirements.entry(worker.clone()).or_default();
+            for (key, value) in labels {
+                match requirements.get(&key) {
+                    Some((other, other_line)) if *other != value => conflicts.push(format!(
+                        "conflicting labels (worker={:?}, key={:?}, values=[{:?}, {:?}], previous_line={})",
+                        worker, key, other, value, other_line
+                    )),
+                    _ => {
+                        requirements.insert(key, (value, line));
+                    },
+                }
+            }
+            for conflict in conflicts {
+                self.error(line, conflict);
+            }
+        }
+    }
+
+    fn check_commands(&mut self, commands: &Node, action: &str) {
+        let commands: &Vec<Node> = match &commands.value {
+            NodeValue::Sequence(commands_) if commands_.is_empty() => {
+                self.error(
+                    commands.line,
+                    format!("empty {} entry (action={:?})", Job::COMMANDS_ENTRY_NAME, action),
+                );
+                return;
+            },
+            NodeValue::Sequence(commands_) => commands_,
+            _ => {
+                self.error(
+                    commands.line,
+                    format!(
+                        "failed to parse {} entry (action={:?})",
+                        Job::COMMANDS_ENTRY_NAME,
+                        action
+                    ),
+                );
+                return;
+            },
+        };
+
+        for command in commands {
+            match command.as_str() {
+                // Commands are chained with `&&`, so an empty one breaks the whole chain.
+                Some(command_str) if command_str.trim().is_empty() => {
+                    self.error(command.line, format!("empty command (action={:?})", action));
+                },
+                Some(command_str) => {
+                    for name in variable_references(command_str) {
+                        self.references.push((name, command.line));
+                    }
+                },
+                None => self.error(
+                    command.line,
+                    format!(
+                        "failed to parse {} entry, expected a string (action={:?})",
+                        Job::COMMANDS_ENTRY_NAME,
+                        action
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn check_transfer(&mut self, transfer: &Node, direction: TransferDirection) {
+        if let Some(entries) = transfer.as_mapping() {
+            self.check_keys(
+                entries,
+                &[Job::SOURCE_ENTRY_NAME, Job::DESTINATION_ENTRY_NAME],
+                &format!("scope={}", direction),
+            );
+        }
+        if self
+            .check(transfer, Job::parse_transfer(&transfer.to_yaml(), direction))
+            .is_none()
+        {
+            return;
+        }
+
+        // File transfers do not go through a shell.
+        for key in [Job::SOURCE_ENTRY_NAME, Job::DESTINATION_ENTRY_NAME] {
+            if let Some(path) = transfer.get(key) {
+                if let Some(name) = variable_references(path.as_str().unwrap_or("")).first() {
+                    self.warning(
+                        path.line,
+                        format!(
+                            "environment variables are not expanded in {} paths (variable={:?})",
+                            direction, name
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_barrier(&mut self, task: &Node) {
+        let entries: &Vec<(Node, Node)> = task.as_mapping().expect("barriers are mappings");
+        self.check_keys(entries, &[Job::BARRIER_ENTRY_NAME], "scope=barrier");
+
+        // Only the workers that have run something so far take part in a barrier.
+        match (self.worker_names.len(), self.last_barrier) {
+            (0, _) => self.warning(
+                task.line,
+                "barrier has no participants, since no action runs before it".to_string(),
+            ),
+            (1, _) => self.warning(
+                task.line,
+                format!("barrier has a single participant (worker={:?})", self.worker_names[0]),
+            ),
+            (_, Some(line)) if self.actions_since_barrier == 0 => self.warning(
+                task.line,
+                format!(
+                    "barrier is redundant, since no action runs after the barrier on line {}",
+                    line
+                ),
+            ),
+            _ => {},
+        }
+        self.last_barrier = Some(task.line);
+        self.actions_since_barrier = 0;
+    }
+
+    /// Checks that the environment variables that commands reference are set when the job runs.
+    fn check_references(&mut self, defined: &HashSet<String>) {
+        let prefix: String = Config::env_var_prefix();
+        for (name, line) in std::mem::take(&mut self.references) {
+            let key: &str = &name[prefix.len()..];
+            if defined.contains(key) {
+                continue;
+            }
+            if key.chars().any(|c| c.is_ascii_lowercase()) {
+                self.error(
+                    line,
+                    format!(
+                        "variable is never set, since parameters, matrix values, secrets, and worker addresses are \
+                         all upper case (variable={:?})",
+                        name
+                    ),
+                );
+            } else {
+                self.warning(
+                    line,
+                    format!(
+                        "variable is not defined by the job, so it must be passed as a parameter (variable={:?})",
+                        name
+                    ),
+                );
+            }
+        }
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl MarkedEventReceiver for NodeBuilder {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        let line: usize = marker.line();
+        match event {
+            Event::SequenceStart(anchor) => self.stack.push(PendingNode {
+                node: Node {
+                    value: NodeValue::Sequence(Vec::new()),
+                    line,
+                },
+                anchor,
+                key: None,
+            }),
+            Event::MappingStart(anchor) => self.stack.push(PendingNode {
+                node: Node {
+                    value: NodeValue::Mapping(Vec::new()),
+                    line,
+                },
+                anchor,
+                key: None,
+            }),
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some(pending) = self.stack.pop() {
+                    self.insert(pending.node, pending.anchor);
+                }
+            },
+            Event::Scalar(value, style, anchor, _) => {
+                // Only plain scalars are subject to type resolution.
+                let yaml: Yaml = match style {
+                    TScalarStyle::Plain => Yaml::from_str(&value),
+                    _ => Yaml::String(value),
+                };
+                self.insert(
+                    Node {
+                        value: NodeValue::Scalar(yaml),
+                        line,
+                    },
+                    anchor,
+                );
+            },
+            Event::Alias(anchor) => {
+                let node: Node = match self.anchors.get(&anchor) {
+                    Some(node) => node.clone(),
+                    None => Node {
+                        value: NodeValue::Scalar(Yaml::BadValue),
+                        line,
+                    },
+                };
+                self.insert(node, 0);
+            },
+            _ => {},
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s: &str = match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}: {}", self.path, self.line, self.severity, self.message)
+    }
+}
+
+//======================================================================================================================
+// Standalone Functions
+//======================================================================================================================
+
+/// Returns the names of the variables with the environment variable prefix that a command references, either as
+/// `$NAME` or as `${NAME}`. Escaped dollar signs are skipped.
+fn variable_references(command: &str) -> Vec<String> {
+    let prefix: String = Config::env_var_prefix();
+    let chars: Vec<char> = command.chars().collect();
+    let mut names: Vec<String> = Vec::new();
+    let mut i: usize = 0;
+    while i < chars.len() {
+        if chars[i] != '$' || (i > 0 && chars[i - 1] == '\\') {
+            i += 1;
+            continue;
+        }
+        i += 1;
+        if i < chars.len() && chars[i] == '{' {
+            i += 1;
+        }
+        let start: usize = i;
+        while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+            i += 1;
+        }
+        let name: String = chars[start..i].iter().collect();
+        if name.len() > prefix.len() && name.starts_with(&prefix) {
+            names.push(name);
+        }
+    }
+    names
+}
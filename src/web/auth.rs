@@ -0,0 +1,179 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::stream::RequestError;
+use anyhow::Result;
+use http::{header::AUTHORIZATION, Request, StatusCode};
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, fmt, str::FromStr};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Permission to use a group of endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Run jobs.
+    Submit,
+    /// Inspect jobs, their reports and artifacts, the history, and runners.
+    View,
+    /// Cancel jobs.
+    Cancel,
+    /// Manage runners. Implies all other scopes.
+    Admin,
+}
+
+/// Kind of credential that an [ApiToken] stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Token sent in the `Authorization` header.
+    Bearer,
+    /// Client certificate presented in the TLS handshake, and verified against the client certificate authority.
+    Certificate,
+}
+
+/// Bearer token or client certificate that grants access to the HTTP server. Only the SHA-256 hash of the credential
+/// is kept, so that the configuration file does not hold usable credentials.
+pub struct ApiToken {
+    /// Name of the token, used in logs.
+    name: String,
+    kind: TokenKind,
+    /// SHA-256 hash of the token, or of the DER encoding of the certificate.
+    hash: Vec<u8>,
+    /// Scopes that the token grants.
+    scopes: HashSet<Scope>,
+}
+
+/// Checks that requests carry a token with the scope that their endpoint requires.
+pub struct Authenticator {
+    tokens: Vec<ApiToken>,
+    /// Returns the scope that a request requires, or `None` if the endpoint is public.
+    required_scope: fn(&Request<Vec<u8>>) -> Option<Scope>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl ApiToken {
+    /// Instantiates a new [ApiToken] from the hexadecimal SHA-256 hash of a token or of a certificate.
+    pub fn new(name: &str, kind: TokenKind, hash: &str, scopes: HashSet<Scope>) -> Result<Self> {
+        let hash: Vec<u8> = match hex::decode(hash) {
+            Ok(hash) if hash.len() == Sha256::output_size() => hash,
+            _ => {
+                let msg: String = format!("malformed token hash (name={:?})", name);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+        Ok(Self {
+            name: name.to_string(),
+            kind,
+            hash,
+            scopes,
+        })
+    }
+
+    /// Checks if the target [ApiToken] grants a scope.
+    fn grants(&self, scope: Scope) -> bool {
+        self.scopes.contains(&Scope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+impl Authenticator {
+    /// Scheme of the `Authorization` header.
+    const BEARER_PREFIX: &'static str = "Bearer ";
+
+    /// Instantiates a new [Authenticator].
+    pub fn new(tokens: Vec<ApiToken>, required_scope: fn(&Request<Vec<u8>>) -> Option<Scope>) -> Self {
+        Self { tokens, required_scope }
+    }
+
+    /// Checks if a request may be served. Clients are identified by the certificate that they presented, if it is a
+    /// known one, and otherwise by their bearer token. Fails with `401 Unauthorized` if the request does not carry a
+    /// known token, and with `403 Forbidden` if the token does not grant the required scope.
+    pub fn authorize(&self, request: &Request<Vec<u8>>, peer_certificate: Option<&[u8]>) -> Result<()> {
+        let scope: Scope = match (self.required_scope)(request) {
+            Some(scope) => scope,
+            None => return Ok(()),
+        };
+
+        let token: &ApiToken = match peer_certificate
+            .and_then(|certificate| self.find(TokenKind::Certificate, certificate))
+        {
+            Some(token) => token,
+            None => {
+                let token: &str = match request.headers().get(AUTHORIZATION).map(|value| value.to_str()) {
+                    Some(Ok(value)) => match value.strip_prefix(Self::BEARER_PREFIX) {
+                        Some(token) => token.trim(),
+                        None => {
+                            return Self::fail(StatusCode::UNAUTHORIZED, "unsupported authorization scheme".to_string())
+                        },
+                    },
+                    _ => return Self::fail(StatusCode::UNAUTHORIZED, "missing bearer token".to_string()),
+                };
+                match self.find(TokenKind::Bearer, token.as_bytes()) {
+                    Some(token) => token,
+                    None => return Self::fail(StatusCode::UNAUTHORIZED, "invalid bearer token".to_string()),
+                }
+            },
+        };
+
+        if !token.grants(scope) {
+            return Self::fail(
+                StatusCode::FORBIDDEN,
+                format!("token lacks required scope (name={:?}, scope={})", token.name, scope),
+            );
+        }
+        log::trace!("authorized request (name={:?}, scope={})", token.name, scope);
+        Ok(())
+    }
+
+    /// Looks up the token of some kind whose hash matches that of a credential.
+    fn find(&self, kind: TokenKind, credential: &[u8]) -> Option<&ApiToken> {
+        let hash: Vec<u8> = Sha256::digest(credential).to_vec();
+        self.tokens
+            .iter()
+            .find(|candidate| candidate.kind == kind && candidate.hash == hash)
+    }
+
+    fn fail(status: StatusCode, message: String) -> Result<()> {
+        log::warn!("{}", message);
+        Err(RequestError::new(status, message).into())
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name: &str = match self {
+            Scope::Submit => "submit",
+            Scope::View => "view",
+            Scope::Cancel => "cancel",
+            Scope::Admin => "admin",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "submit" => Ok(Scope::Submit),
+            "view" => Ok(Scope::View),
+            "cancel" => Ok(Scope::Cancel),
+            "admin" => Ok(Scope::Admin),
+            _ => anyhow::bail!("unknown scope (scope={:?})", s),
+        }
+    }
+}
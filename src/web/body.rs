@@ -0,0 +1,25 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use std::{fs::File, sync::mpsc::Receiver};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Body of an HTTP response.
+pub enum HttpBody {
+    /// Lines that are known upfront.
+    Lines(Vec<String>),
+    /// Lines that are produced over time, and sent with chunked transfer encoding.
+    Stream(Receiver<String>),
+    /// Raw bytes, such as the contents of a report.
+    Bytes(Vec<u8>),
+    /// Contents of a file, along with its length, which are read as they are sent so that large files are not held
+    /// in memory.
+    File(File, u64),
+}
@@ -5,5 +5,8 @@
 // Modules
 //======================================================================================================================
 
+pub mod auth;
+pub mod body;
 pub mod server;
 mod stream;
+pub mod tls;
@@ -5,18 +5,55 @@
 // Imports
 //======================================================================================================================
 
-use super::stream::HttpStream;
+use super::{
+    auth::Authenticator,
+    body::HttpBody,
+    stream::{Connection, HttpStream, RequestError},
+    tls::TlsAcceptor,
+};
 use anyhow::{Error, Result};
-use http::Request;
+use http::{Request, Response, StatusCode, Version};
 use std::net::TcpListener;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
+/// Thread that serves a connection.
+type ConnectionThread = JoinHandle<Result<(), Error>>;
+
 pub struct HttpServer {
     pub listener: TcpListener,
+    /// Checks that requests are authorized. If `None`, all requests are served.
+    authenticator: Option<Arc<Authenticator>>,
+    /// Terminates TLS on incoming connections. If `None`, connections are not encrypted.
+    tls: Option<Arc<TlsAcceptor>>,
+}
+
+/// Number of connections that are being served, by kind.
+#[derive(Default)]
+struct Occupancy {
+    /// Connections whose requests are served right away.
+    connections: usize,
+    /// Connections whose requests stream output or wait for jobs to finish.
+    streams: usize,
+}
+
+/// Bounds the number of connections that are served at once. Long-lived connections have an allowance of their own,
+/// so that they cannot keep other requests from being served.
+struct ConnectionLimiter {
+    occupancy: Mutex<Occupancy>,
+    /// Signaled whenever a connection is released.
+    released: Condvar,
+}
+
+/// Place of a connection in a [ConnectionLimiter], which is given back when dropped.
+struct ConnectionSlot {
+    limiter: Arc<ConnectionLimiter>,
+    long_lived: bool,
 }
 
 //======================================================================================================================
@@ -24,9 +61,17 @@ pub struct HttpServer {
 //======================================================================================================================
 
 impl HttpServer {
-    const THREAD_MAX: usize = 4;
+    /// Maximum number of connections whose requests are served right away. Once reached, no more connections are
+    /// accepted until one of them is closed.
+    const CONNECTION_MAX: usize = 16;
+    /// Maximum number of connections whose requests stream output or wait for jobs to finish. Once reached, such
+    /// requests are rejected.
+    pub const STREAM_MAX: usize = 16;
+    /// Maximum amount of time that a client may take to send a request, or to accept a piece of a response, before
+    /// its connection is dropped. This keeps idle clients from holding on to threads.
+    const SOCKET_TIMEOUT: Duration = Duration::from_secs(30);
 
-    pub fn new(addr: &str) -> Result<Self> {
+    pub fn new(addr: &str, authenticator: Option<Authenticator>, tls: Option<TlsAcceptor>) -> Result<Self> {
         log::info!("bind to address={:?}", addr);
         let listener: TcpListener = match TcpListener::bind(addr) {
             Ok(listener) => listener,
@@ -36,22 +81,82 @@ impl HttpServer {
                 anyhow::bail!(msg);
             },
         };
-        Ok(Self { listener })
+        if authenticator.is_none() {
+            log::warn!("authentication is disabled, all requests will be served");
+        }
+        if tls.is_none() {
+            log::warn!("tls is disabled, connections will not be encrypted");
+        }
+        Ok(Self {
+            listener,
+            authenticator: authenticator.map(Arc::new),
+            tls: tls.map(Arc::new),
+        })
     }
 
-    pub fn run<F>(&self, dispatcher: F)
+    /// Serves requests until the listener fails. Requests for which `is_long_lived` holds are expected to keep their
+    /// connection open for a long time, and are bounded separately from the rest.
+    pub fn run<F>(&self, dispatcher: F, is_long_lived: fn(&Request<Vec<u8>>) -> bool)
     where
-        F: FnOnce(Request<()>) -> Result<Vec<String>> + Sync + std::marker::Send + 'static + Clone,
+        F: FnOnce(Request<Vec<u8>>) -> Result<Response<HttpBody>> + Sync + std::marker::Send + 'static + Clone,
     {
-        let mut threads = Vec::new();
-        for stream in self.listener.incoming() {
-            match stream {
-                Ok(_) => {
+        let limiter: Arc<ConnectionLimiter> = Arc::new(ConnectionLimiter {
+            occupancy: Mutex::new(Occupancy::default()),
+            released: Condvar::new(),
+        });
+        let mut threads: Vec<ConnectionThread> = Vec::new();
+        loop {
+            // Wait for a connection to be released before accepting a new one.
+            let mut slot: ConnectionSlot = match limiter.acquire() {
+                Ok(slot) => slot,
+                Err(e) => {
+                    log::error!("failed to acquire connection slot (e={:?})", e);
+                    return;
+                },
+            };
+            match self.listener.accept() {
+                Ok((stream, _)) => {
                     let dispatcher_ = dispatcher.clone();
-                    let thread: JoinHandle<Result<(), Error>> = thread::spawn(move || {
-                        let server: HttpStream = HttpStream::new(stream?);
-                        let request: Request<()> = server.parse_request()?;
-                        let result: Result<Vec<String>, Error> = dispatcher_(request);
+                    let authenticator: Option<Arc<Authenticator>> = self.authenticator.clone();
+                    let tls: Option<Arc<TlsAcceptor>> = self.tls.clone();
+                    let thread: ConnectionThread = thread::spawn(move || {
+                        stream.set_read_timeout(Some(Self::SOCKET_TIMEOUT))?;
+                        stream.set_write_timeout(Some(Self::SOCKET_TIMEOUT))?;
+                        let connection: Connection = match tls {
+                            Some(tls) => tls.accept(stream)?,
+                            None => Connection::Plain(stream),
+                        };
+                        let mut server: HttpStream = HttpStream::new(connection);
+                        let request: Request<Vec<u8>> = match server.parse_request() {
+                            Ok(request) => request,
+                            Err(e) => {
+                                // Let the client know why the request was rejected, if it is still listening.
+                                let status: StatusCode = match e.downcast_ref::<RequestError>() {
+                                    Some(e) => e.status(),
+                                    None => StatusCode::BAD_REQUEST,
+                                };
+                                let response: Response<HttpBody> = Response::builder()
+                                    .version(Version::HTTP_11)
+                                    .status(status)
+                                    .header("Content-Type", "text/plain")
+                                    .header("Connection", "close")
+                                    .body(HttpBody::Lines(vec![e.to_string()]))?;
+                                let _ = server.send_response(Ok(response));
+                                return Err(e);
+                            },
+                        };
+                        if let Some(authenticator) = &authenticator {
+                            if let Err(e) = authenticator.authorize(&request, server.peer_certificate()) {
+                                return server.send_response(Err(e));
+                            }
+                        }
+                        if is_long_lived(&request) && !slot.promote()? {
+                            let msg: String = format!("too many long-lived connections (max={})", Self::STREAM_MAX);
+                            log::warn!("{}", msg);
+                            return server
+                                .send_response(Err(RequestError::new(StatusCode::SERVICE_UNAVAILABLE, msg).into()));
+                        }
+                        let result: Result<Response<HttpBody>, Error> = dispatcher_(request);
                         server.send_response(result)?;
                         Ok(())
                     });
@@ -62,12 +167,11 @@ impl HttpServer {
                 },
             }
 
-            // Join threads.
-            if threads.len() < Self::THREAD_MAX {
-                continue;
-            }
-
-            while let Some(thread) = threads.pop() {
+            // Join threads. Connections that stream output may be long-lived, so only join threads that have finished.
+            let (finished, running): (Vec<ConnectionThread>, Vec<ConnectionThread>) =
+                threads.into_iter().partition(|thread| thread.is_finished());
+            threads = running;
+            for thread in finished {
                 if let Err(e) = thread.join() {
                     log::error!("failed to join thread: {:?}", e);
                 }
@@ -75,3 +179,73 @@ impl HttpServer {
         }
     }
 }
+
+impl ConnectionLimiter {
+    /// Blocks until a connection may be served, and returns its slot.
+    fn acquire(self: &Arc<Self>) -> Result<ConnectionSlot> {
+        let mut occupancy: MutexGuard<'_, Occupancy> = self.lock()?;
+        while occupancy.connections >= HttpServer::CONNECTION_MAX {
+            occupancy = match self.released.wait(occupancy) {
+                Ok(occupancy) => occupancy,
+                Err(e) => {
+                    let msg: String = format!("failed to wait for connection slot (e={:?})", e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+        }
+        occupancy.connections += 1;
+
+        Ok(ConnectionSlot {
+            limiter: self.clone(),
+            long_lived: false,
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, Occupancy>> {
+        match self.occupancy.lock() {
+            Ok(occupancy) => Ok(occupancy),
+            Err(e) => {
+                let msg: String = format!("failed to lock connection limiter (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+}
+
+impl ConnectionSlot {
+    /// Moves the target [ConnectionSlot] to the allowance of long-lived connections, freeing its place for other
+    /// connections. Returns `false` if there is no room left for long-lived connections.
+    fn promote(&mut self) -> Result<bool> {
+        let mut occupancy: MutexGuard<'_, Occupancy> = self.limiter.lock()?;
+        if occupancy.streams >= HttpServer::STREAM_MAX {
+            return Ok(false);
+        }
+        occupancy.connections -= 1;
+        occupancy.streams += 1;
+        self.long_lived = true;
+        self.limiter.released.notify_all();
+        Ok(true)
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        match self.limiter.lock() {
+            Ok(mut occupancy) => {
+                if self.long_lived {
+                    occupancy.streams -= 1;
+                } else {
+                    occupancy.connections -= 1;
+                }
+                self.limiter.released.notify_all();
+            },
+            Err(e) => log::warn!("failed to release connection slot (e={:?})", e),
+        }
+    }
+}
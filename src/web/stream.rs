@@ -5,18 +5,36 @@
 // Imports
 //======================================================================================================================
 
+use super::body::HttpBody;
 use anyhow::Result;
-use http::{Request, Response, StatusCode, Uri, Version};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use http::{header::WWW_AUTHENTICATE, request::Builder, Method, Request, Response, StatusCode, Uri, Version};
+use rustls::{ServerConnection, StreamOwned};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::net::TcpStream;
-use std::str::FromStr;
+use std::str::{FromStr, SplitWhitespace};
 
 //======================================================================================================================
 // Structures
 //======================================================================================================================
 
 pub struct HttpStream {
-    stream: TcpStream,
+    stream: Connection,
+}
+
+/// Connection to a client.
+pub enum Connection {
+    /// Unencrypted connection.
+    Plain(TcpStream),
+    /// Connection that is encrypted with TLS. The handshake happens on first use.
+    Tls(Box<StreamOwned<ServerConnection, TcpStream>>),
+}
+
+/// Error that prevents a request from being parsed, along with the status that should be reported to the client.
+#[derive(Debug)]
+pub struct RequestError {
+    status: StatusCode,
+    message: String,
 }
 
 //======================================================================================================================
@@ -24,80 +42,171 @@ pub struct HttpStream {
 //======================================================================================================================
 
 impl HttpStream {
-    pub fn new(stream: TcpStream) -> Self {
+    /// Maximum length of the request line and of each header line, in bytes.
+    const MAX_LINE_LENGTH: usize = 8 * 1024;
+    /// Maximum number of headers in a request.
+    const MAX_HEADERS: usize = 100;
+    /// Maximum size of the body of a request, in bytes.
+    const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+    pub fn new(stream: Connection) -> Self {
         Self { stream }
     }
 
-    pub fn parse_request(&self) -> Result<Request<()>> {
-        let mut reader: BufReader<&TcpStream> = BufReader::new(&self.stream);
+    /// Returns the certificate that the client presented, if any. Certificates are only known once the handshake is
+    /// over, that is, once a request has been read, and they have been verified by then.
+    pub fn peer_certificate(&self) -> Option<&[u8]> {
+        match &self.stream {
+            Connection::Tls(stream) => stream
+                .conn
+                .peer_certificates()
+                .and_then(|certificates| certificates.first())
+                .map(|certificate| certificate.as_ref()),
+            Connection::Plain(_) => None,
+        }
+    }
 
-        let mut request_str: String = String::new();
+    /// Reads and parses an HTTP/1.x request, including its headers and its body.
+    pub fn parse_request(&mut self) -> Result<Request<Vec<u8>>> {
+        let mut reader: BufReader<&mut Connection> = BufReader::new(&mut self.stream);
 
-        if let Err(e) = reader.read_line(&mut request_str) {
-            let msg: String = format!("failed to read line (e={:?})", e);
-            log::error!("{}", msg);
-            anyhow::bail!(msg);
-        }
+        // Parse request line.
+        let request_line: String = Self::read_line(&mut reader)?;
+        let mut parts: SplitWhitespace<'_> = request_line.split_whitespace();
+        let (method, uri, version): (&str, &str, &str) = match (parts.next(), parts.next(), parts.next(), parts.next())
+        {
+            (Some(method), Some(uri), Some(version), None) => (method, uri, version),
+            _ => Self::fail(
+                StatusCode::BAD_REQUEST,
+                format!("malformed request line (line={:?})", request_line),
+            )?,
+        };
+        let method: Method = match Method::from_bytes(method.as_bytes()) {
+            Ok(method) => method,
+            Err(e) => Self::fail(StatusCode::BAD_REQUEST, format!("malformed method (e={:?})", e))?,
+        };
+        let uri: Uri = match Uri::from_str(uri) {
+            Ok(uri) => uri,
+            Err(e) => Self::fail(StatusCode::BAD_REQUEST, format!("failed to parse uri (e={:?})", e))?,
+        };
+        let version: Version = match version {
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/1.1" => Version::HTTP_11,
+            _ => Self::fail(
+                StatusCode::HTTP_VERSION_NOT_SUPPORTED,
+                format!("unsupported http version (version={:?})", version),
+            )?,
+        };
+        let mut builder: Builder = Request::builder().method(method).uri(uri).version(version);
 
-        let request_str: &str = &request_str;
-        let mut req: Request<()> = Request::default();
+        // Parse headers.
+        let mut content_length: Option<usize> = None;
+        let mut chunked: bool = false;
+        let mut expect_continue: bool = false;
+        let mut num_headers: usize = 0;
+        loop {
+            let line: String = Self::read_line(&mut reader)?;
+            if line.is_empty() {
+                break;
+            }
+            num_headers += 1;
+            if num_headers > Self::MAX_HEADERS {
+                Self::fail(
+                    StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                    format!("too many headers (max={})", Self::MAX_HEADERS),
+                )?;
+            }
 
-        for line in request_str.lines() {
-            if line.starts_with("GET") {
-                let uri: Uri = match line.split_whitespace().nth(1) {
-                    Some(uri_str) => match Uri::from_str(uri_str) {
-                        Ok(uri) => uri,
-                        Err(e) => {
-                            let msg: String = format!("failed to parse uri (e={:?})", e);
-                            log::error!("{}", msg);
-                            anyhow::bail!(msg);
-                        },
+            let (name, value): (&str, &str) = match line.split_once(':') {
+                Some((name, value)) if !name.is_empty() && !name.ends_with(char::is_whitespace) => (name, value.trim()),
+                _ => Self::fail(StatusCode::BAD_REQUEST, format!("malformed header (line={:?})", line))?,
+            };
+            if name.eq_ignore_ascii_case("content-length") {
+                match value.parse::<usize>() {
+                    Ok(length) if content_length.is_none() || content_length == Some(length) => {
+                        content_length = Some(length)
                     },
-                    None => {
-                        let msg: String = format!("missing uri (line={:?})", line);
-                        log::error!("{}", msg);
-                        anyhow::bail!(msg);
-                    },
-                };
-                *req.uri_mut() = uri;
-            } else if line.starts_with("HTTP") {
-                let version: Version = match line.split_whitespace().nth(0) {
-                    Some(version_str) => match version_str {
-                        "HTTP/1.0" => Version::HTTP_10,
-                        "HTTP/1.1" => Version::HTTP_11,
-                        _ => {
-                            let msg: String = format!("unsupported http version (version={:?})", version_str);
-                            log::error!("{}", msg);
-                            anyhow::bail!(msg);
-                        },
-                    },
-                    None => {
-                        let msg: String = format!("missing version (line={:?})", line);
-                        log::error!("{}", msg);
-                        anyhow::bail!(msg);
-                    },
-                };
-                *req.version_mut() = version;
+                    _ => Self::fail(
+                        StatusCode::BAD_REQUEST,
+                        format!("malformed content length (value={:?})", value),
+                    )?,
+                }
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                // Chunked must be the last encoding, and no other encodings are supported.
+                if !value.eq_ignore_ascii_case("chunked") {
+                    Self::fail(
+                        StatusCode::NOT_IMPLEMENTED,
+                        format!("unsupported transfer encoding (value={:?})", value),
+                    )?;
+                }
+                chunked = true;
+            } else if name.eq_ignore_ascii_case("expect") && value.eq_ignore_ascii_case("100-continue") {
+                expect_continue = true;
+            }
+            builder = builder.header(name, value);
+        }
+        if chunked && content_length.is_some() {
+            Self::fail(
+                StatusCode::BAD_REQUEST,
+                "both content length and chunked transfer encoding were specified".to_string(),
+            )?;
+        }
+        if let Some(length) = content_length {
+            if length > Self::MAX_BODY_SIZE {
+                Self::fail(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!(
+                        "request body is too large (length={}, max={})",
+                        length,
+                        Self::MAX_BODY_SIZE
+                    ),
+                )?;
             }
         }
 
-        Ok(req)
+        // Let the client know that it may send the body.
+        if expect_continue && (chunked || content_length.unwrap_or(0) > 0) {
+            write!(reader.get_mut(), "HTTP/1.1 100 Continue\r\n\r\n")?;
+            reader.get_mut().flush()?;
+        }
+
+        // Parse body.
+        let body: Vec<u8> = if chunked {
+            Self::read_chunked_body(&mut reader)?
+        } else {
+            Self::read_body(&mut reader, content_length.unwrap_or(0))?
+        };
+
+        match builder.body(body) {
+            Ok(request) => Ok(request),
+            Err(e) => Self::fail(StatusCode::BAD_REQUEST, format!("malformed request (e={:?})", e)),
+        }
     }
 
-    pub fn send_response(&self, message: Result<Vec<String>>) -> Result<()> {
-        let mut writer: BufWriter<&TcpStream> = BufWriter::new(&self.stream);
-
-        let response: Result<Response<Vec<String>>, http::Error> = match message {
-            Ok(message) => Response::builder()
-                .version(Version::HTTP_11)
-                .status(StatusCode::OK)
-                .header("Content-Type", "text/plain")
-                .body(message),
-            Err(_) => Response::builder()
-                .version(Version::HTTP_11)
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .header("Content-Type", "text/plain")
-                .body(Vec::default()),
+    pub fn send_response(&mut self, message: Result<Response<HttpBody>>) -> Result<()> {
+        let mut writer: BufWriter<&mut Connection> = BufWriter::new(&mut self.stream);
+
+        let response: Result<Response<HttpBody>, http::Error> = match message {
+            Ok(response) => Ok(response),
+            // Let the client know why the request was rejected (e.g. it was not authorized).
+            Err(e) => match e.downcast_ref::<RequestError>() {
+                Some(e) if e.status() == StatusCode::UNAUTHORIZED => Response::builder()
+                    .version(Version::HTTP_11)
+                    .status(e.status())
+                    .header("Content-Type", "text/plain")
+                    .header(WWW_AUTHENTICATE, "Bearer")
+                    .body(HttpBody::Lines(vec![e.to_string()])),
+                Some(e) => Response::builder()
+                    .version(Version::HTTP_11)
+                    .status(e.status())
+                    .header("Content-Type", "text/plain")
+                    .body(HttpBody::Lines(vec![e.to_string()])),
+                None => Response::builder()
+                    .version(Version::HTTP_11)
+                    .status(StatusCode::INTERNAL_SERVER_ERROR)
+                    .header("Content-Type", "text/plain")
+                    .body(HttpBody::Lines(Vec::default())),
+            },
         };
 
         match response {
@@ -106,14 +215,47 @@ impl HttpStream {
                 for (name, value) in response.headers() {
                     write!(writer, "{}: {}\r\n", name.as_str(), value.to_str()?)?;
                 }
-                write!(writer, "\r\n")?;
-                for line in response.body() {
-                    write!(writer, "{}\r\n", line)?;
+                match response.body() {
+                    HttpBody::Lines(lines) => {
+                        write!(writer, "\r\n")?;
+                        for line in lines {
+                            write!(writer, "{}\r\n", line)?;
+                        }
+                    },
+                    HttpBody::Stream(receiver) => {
+                        write!(writer, "Transfer-Encoding: chunked\r\n\r\n")?;
+                        writer.flush()?;
+                        // Send each line in its own chunk, as soon as it is available.
+                        for line in receiver.iter() {
+                            let chunk: String = format!("{}\r\n", line);
+                            write!(writer, "{:x}\r\n{}\r\n", chunk.len(), chunk)?;
+                            writer.flush()?;
+                        }
+                        write!(writer, "0\r\n\r\n")?;
+                    },
+                    HttpBody::Bytes(bytes) => {
+                        write!(writer, "Content-Length: {}\r\n\r\n", bytes.len())?;
+                        writer.write_all(bytes)?;
+                    },
+                    HttpBody::File(file, length) => {
+                        write!(writer, "Content-Length: {}\r\n\r\n", length)?;
+                        // Never send more than announced, even if the file grew in the meantime.
+                        let copied: u64 = io::copy(&mut file.take(*length), &mut writer)?;
+                        if copied != *length {
+                            let msg: String =
+                                format!("file shrank while being sent (length={}, sent={})", length, copied);
+                            log::error!("{}", msg);
+                            anyhow::bail!(msg);
+                        }
+                    },
                 }
                 if let Err(e) = writer.flush() {
                     let msg: String = format!("failed to flush writer (e={:?})", e);
                     log::warn!("{}", msg);
                 }
+                // The end of the response is signaled by closing the connection.
+                drop(writer);
+                self.stream.close();
                 Ok(())
             },
             Err(e) => {
@@ -123,4 +265,155 @@ impl HttpStream {
             },
         }
     }
+
+    /// Reads a body of known length.
+    fn read_body(reader: &mut BufReader<&mut Connection>, length: usize) -> Result<Vec<u8>> {
+        let mut body: Vec<u8> = vec![0; length];
+        if let Err(e) = reader.read_exact(&mut body) {
+            Self::fail(StatusCode::BAD_REQUEST, format!("failed to read body (e={:?})", e))?;
+        }
+        Ok(body)
+    }
+
+    /// Reads a body that is sent with chunked transfer encoding. Trailers are discarded.
+    fn read_chunked_body(reader: &mut BufReader<&mut Connection>) -> Result<Vec<u8>> {
+        let mut body: Vec<u8> = Vec::new();
+        loop {
+            // Chunk extensions are ignored.
+            let line: String = Self::read_line(reader)?;
+            let size: &str = line.split(';').next().unwrap_or("").trim();
+            let size: usize = match usize::from_str_radix(size, 16) {
+                Ok(size) => size,
+                Err(_) => Self::fail(
+                    StatusCode::BAD_REQUEST,
+                    format!("malformed chunk size (line={:?})", line),
+                )?,
+            };
+            if size == 0 {
+                break;
+            }
+            // Chunk sizes come from the client, so adding them up could overflow.
+            if size > Self::MAX_BODY_SIZE - body.len() {
+                Self::fail(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("request body is too large (max={})", Self::MAX_BODY_SIZE),
+                )?;
+            }
+
+            body.append(&mut Self::read_body(reader, size)?);
+            if !Self::read_line(reader)?.is_empty() {
+                Self::fail(StatusCode::BAD_REQUEST, format!("malformed chunk (size={})", size))?;
+            }
+        }
+
+        // Skip trailers, which count towards the limit on headers.
+        let mut num_trailers: usize = 0;
+        while !Self::read_line(reader)?.is_empty() {
+            num_trailers += 1;
+            if num_trailers > Self::MAX_HEADERS {
+                Self::fail(
+                    StatusCode::BAD_REQUEST,
+                    format!("too many trailers (max={})", Self::MAX_HEADERS),
+                )?;
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// Reads a line that is terminated by CRLF (or LF), and strips the terminator.
+    fn read_line(reader: &mut BufReader<&mut Connection>) -> Result<String> {
+        let mut line: Vec<u8> = Vec::new();
+        let limit: u64 = Self::MAX_LINE_LENGTH as u64 + 2;
+        if let Err(e) = reader.by_ref().take(limit).read_until(b'\n', &mut line) {
+            let msg: String = format!("failed to read line (e={:?})", e);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+        if !line.ends_with(b"\n") {
+            if line.len() as u64 >= limit {
+                Self::fail(
+                    StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                    format!("line is too long (max={})", Self::MAX_LINE_LENGTH),
+                )?;
+            }
+            Self::fail(StatusCode::BAD_REQUEST, "unexpected end of request".to_string())?;
+        }
+        line.pop();
+        if line.ends_with(b"\r") {
+            line.pop();
+        }
+
+        match String::from_utf8(line) {
+            Ok(line) => Ok(line),
+            Err(e) => Self::fail(StatusCode::BAD_REQUEST, format!("malformed line (e={:?})", e)),
+        }
+    }
+
+    fn fail<T>(status: StatusCode, message: String) -> Result<T> {
+        log::error!("{}", message);
+        Err(RequestError::new(status, message).into())
+    }
+}
+
+impl Connection {
+    /// Lets the client know that no more data will be sent. For TLS connections, this tells apart a complete response
+    /// from a truncated one.
+    fn close(&mut self) {
+        if let Connection::Tls(stream) = self {
+            stream.conn.send_close_notify();
+            if let Err(e) = stream.flush() {
+                log::warn!("failed to send close notification (e={:?})", e);
+            }
+        }
+    }
+}
+
+impl RequestError {
+    /// Instantiates a new [RequestError].
+    pub fn new(status: StatusCode, message: String) -> Self {
+        Self { status, message }
+    }
+
+    /// Returns the status that should be reported to the client.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+//======================================================================================================================
+// Trait Implementations
+//======================================================================================================================
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.read(buf),
+            Connection::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.write(buf),
+            Connection::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Connection::Plain(stream) => stream.flush(),
+            Connection::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
 }
+
+impl std::error::Error for RequestError {}
@@ -0,0 +1,115 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use super::stream::Connection;
+use anyhow::Result;
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+};
+use std::{fs::File, io::BufReader, net::TcpStream, sync::Arc};
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Terminates TLS on incoming connections.
+pub struct TlsAcceptor {
+    config: Arc<ServerConfig>,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl TlsAcceptor {
+    /// Instantiates a new [TlsAcceptor] from PEM files. If the path to a certificate authority is supplied, client
+    /// certificates that are presented must be signed by it. Clients may still connect without one, since some of
+    /// them, like webhooks, cannot present one.
+    pub fn new(certificate_path: &str, key_path: &str, client_ca_path: Option<&str>) -> Result<Self> {
+        let certificates: Vec<CertificateDer<'static>> = Self::load_certificates(certificate_path)?;
+        let key: PrivateKeyDer<'static> = match rustls_pemfile::private_key(&mut Self::open(key_path)?) {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                let msg: String = format!("no private key found (path={:?})", key_path);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+            Err(e) => {
+                let msg: String = format!("failed to parse private key (path={:?}, e={:?})", key_path, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        };
+
+        let config: Result<ServerConfig, rustls::Error> = match client_ca_path {
+            Some(client_ca_path) => {
+                let mut roots: RootCertStore = RootCertStore::empty();
+                for certificate in Self::load_certificates(client_ca_path)? {
+                    roots.add(certificate)?;
+                }
+                let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                    .allow_unauthenticated()
+                    .build()?;
+                log::info!("client certificates are verified (ca={:?})", client_ca_path);
+                ServerConfig::builder()
+                    .with_client_cert_verifier(verifier)
+                    .with_single_cert(certificates, key)
+            },
+            None => ServerConfig::builder()
+                .with_no_client_auth()
+                .with_single_cert(certificates, key),
+        };
+        match config {
+            Ok(config) => Ok(Self {
+                config: Arc::new(config),
+            }),
+            Err(e) => {
+                let msg: String = format!("failed to configure tls (e={:?})", e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+
+    /// Wraps an incoming connection. The handshake happens as the request is read.
+    pub fn accept(&self, stream: TcpStream) -> Result<Connection> {
+        let connection: ServerConnection = ServerConnection::new(self.config.clone())?;
+        Ok(Connection::Tls(Box::new(StreamOwned::new(connection, stream))))
+    }
+
+    /// Loads all certificates in a PEM file.
+    fn load_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+        let certificates: Vec<CertificateDer<'static>> =
+            match rustls_pemfile::certs(&mut Self::open(path)?).collect::<Result<Vec<_>, _>>() {
+                Ok(certificates) => certificates,
+                Err(e) => {
+                    let msg: String = format!("failed to parse certificates (path={:?}, e={:?})", path, e);
+                    log::error!("{}", msg);
+                    anyhow::bail!(msg);
+                },
+            };
+        if certificates.is_empty() {
+            let msg: String = format!("no certificates found (path={:?})", path);
+            log::error!("{}", msg);
+            anyhow::bail!(msg);
+        }
+        Ok(certificates)
+    }
+
+    fn open(path: &str) -> Result<BufReader<File>> {
+        match File::open(path) {
+            Ok(file) => Ok(BufReader::new(file)),
+            Err(e) => {
+                let msg: String = format!("failed to open file (path={:?}, e={:?})", path, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// Copyright (c) Microsoft Corporation.
+// Licensed under the MIT license.
+
+//======================================================================================================================
+// Imports
+//======================================================================================================================
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+
+//======================================================================================================================
+// Structures
+//======================================================================================================================
+
+/// Receiver of GitHub webhooks, which triggers jobs on pushes and pull requests.
+pub struct GithubWebhook {
+    /// Secret shared with GitHub, used to sign payloads.
+    secret: String,
+    /// Rules that map events to jobs.
+    rules: Vec<WebhookRule>,
+}
+
+/// Rule that maps events of a repository to a job file.
+pub struct WebhookRule {
+    /// Full name of the repository (e.g. `microsoft/demikernel`).
+    repository: String,
+    /// Event that triggers the job, if restricted to one.
+    event: Option<WebhookEventKind>,
+    /// Branch that triggers the job, if restricted to one. For pull requests, this is the base branch.
+    branch: Option<String>,
+    /// Job file to run, relative to the jobs directory.
+    job: String,
+}
+
+/// Kind of a GitHub event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEventKind {
+    Push,
+    PullRequest,
+}
+
+/// GitHub event that may trigger jobs.
+#[derive(Debug)]
+pub struct WebhookEvent {
+    kind: WebhookEventKind,
+    /// Full name of the repository.
+    repository: String,
+    /// Branch that was pushed to, or the head branch of a pull request.
+    branch: String,
+    /// Base branch of a pull request.
+    base_branch: Option<String>,
+    /// Commit to test.
+    sha: String,
+    /// Number of the pull request.
+    pull_request: Option<u64>,
+}
+
+/// Payload of a `push` event.
+#[derive(Deserialize)]
+struct PushPayload {
+    #[serde(rename = "ref")]
+    git_ref: String,
+    after: String,
+    #[serde(default)]
+    deleted: bool,
+    repository: RepositoryPayload,
+}
+
+/// Payload of a `pull_request` event.
+#[derive(Deserialize)]
+struct PullRequestPayload {
+    action: String,
+    number: u64,
+    pull_request: PullRequestRefs,
+    repository: RepositoryPayload,
+}
+
+#[derive(Deserialize)]
+struct PullRequestRefs {
+    head: GitRef,
+    base: GitRef,
+}
+
+#[derive(Deserialize)]
+struct GitRef {
+    #[serde(rename = "ref")]
+    name: String,
+    sha: String,
+}
+
+#[derive(Deserialize)]
+struct RepositoryPayload {
+    full_name: String,
+}
+
+//======================================================================================================================
+// Associated Functions
+//======================================================================================================================
+
+impl GithubWebhook {
+    /// Prefix of the signature in the `X-Hub-Signature-256` header.
+    const SIGNATURE_PREFIX: &'static str = "sha256=";
+
+    /// Actions of pull requests that trigger jobs. Other actions (e.g. labeling) do not change the code.
+    const PULL_REQUEST_ACTIONS: [&'static str; 3] = ["opened", "synchronize", "reopened"];
+
+    /// Instantiates a new [GithubWebhook].
+    pub fn new(secret: &str, rules: Vec<WebhookRule>) -> Self {
+        Self {
+            secret: secret.to_string(),
+            rules,
+        }
+    }
+
+    /// Checks if a payload was signed with the secret of the target [GithubWebhook]. The signature is the value of
+    /// the `X-Hub-Signature-256` header.
+    pub fn verify(&self, payload: &[u8], signature: &str) -> bool {
+        let signature: Vec<u8> = match signature.strip_prefix(Self::SIGNATURE_PREFIX).map(hex::decode) {
+            Some(Ok(signature)) => signature,
+            _ => return false,
+        };
+        let mut mac: Hmac<Sha256> = match Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()) {
+            Ok(mac) => mac,
+            Err(_) => return false,
+        };
+        mac.update(payload);
+        // This comparison runs in constant time.
+        mac.verify_slice(&signature).is_ok()
+    }
+
+    /// Parses the payload of an event, given the value of the `X-GitHub-Event` header. Returns `None` if the event
+    /// does not trigger jobs.
+    pub fn parse_event(&self, event: &str, payload: &[u8]) -> Result<Option<WebhookEvent>> {
+        match event {
+            "push" => {
+                let payload: PushPayload = Self::decode(event, payload)?;
+                // Tags and deleted branches are not tested.
+                match payload.git_ref.strip_prefix("refs/heads/") {
+                    Some(branch) if !payload.deleted => Ok(Some(WebhookEvent {
+                        kind: WebhookEventKind::Push,
+                        repository: payload.repository.full_name,
+                        branch: branch.to_string(),
+                        base_branch: None,
+                        sha: payload.after,
+                        pull_request: None,
+                    })),
+                    _ => Ok(None),
+                }
+            },
+            "pull_request" => {
+                let payload: PullRequestPayload = Self::decode(event, payload)?;
+                if !Self::PULL_REQUEST_ACTIONS.contains(&payload.action.as_str()) {
+                    return Ok(None);
+                }
+                Ok(Some(WebhookEvent {
+                    kind: WebhookEventKind::PullRequest,
+                    repository: payload.repository.full_name,
+                    branch: payload.pull_request.head.name,
+                    base_branch: Some(payload.pull_request.base.name),
+                    sha: payload.pull_request.head.sha,
+                    pull_request: Some(payload.number),
+                }))
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the job files that an event triggers, in the order in which rules are declared.
+    pub fn jobs(&self, event: &WebhookEvent) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(event))
+            .map(|rule| rule.job.as_str())
+            .collect()
+    }
+
+    fn decode<'a, T: Deserialize<'a>>(event: &str, payload: &'a [u8]) -> Result<T> {
+        match serde_json::from_slice::<T>(payload) {
+            Ok(payload) => Ok(payload),
+            Err(e) => {
+                let msg: String = format!("malformed webhook payload (event={:?}, e={})", event, e);
+                log::error!("{}", msg);
+                anyhow::bail!(msg);
+            },
+        }
+    }
+}
+
+impl WebhookRule {
+    /// Instantiates a new [WebhookRule].
+    pub fn new(repository: &str, event: Option<WebhookEventKind>, branch: Option<&str>, job: &str) -> Self {
+        Self {
+            repository: repository.to_string(),
+            event,
+            branch: branch.map(|branch| branch.to_string()),
+            job: job.to_string(),
+        }
+    }
+
+    /// Checks if an event matches the target [WebhookRule].
+    fn matches(&self, event: &WebhookEvent) -> bool {
+        if !self.repository.eq_ignore_ascii_case(&event.repository) {
+            return false;
+        }
+        if let Some(kind) = self.event {
+            if kind != event.kind {
+                return false;
+            }
+        }
+        if let Some(branch) = &self.branch {
+            let target: &str = event.base_branch.as_deref().unwrap_or(&event.branch);
+            if branch != target {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl WebhookEventKind {
+    /// Parses the name of an event, as used in the configuration file and in the `X-GitHub-Event` header.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "push" => Some(WebhookEventKind::Push),
+            "pull_request" => Some(WebhookEventKind::PullRequest),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the target [WebhookEventKind].
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEventKind::Push => "push",
+            WebhookEventKind::PullRequest => "pull_request",
+        }
+    }
+}
+
+impl WebhookEvent {
+    /// Returns the parameters that are passed on to jobs triggered by the target [WebhookEvent]. Keys are not
+    /// prefixed.
+    pub fn parameters(&self) -> HashMap<String, String> {
+        let mut parameters: HashMap<String, String> = HashMap::new();
+        parameters.insert("EVENT".to_string(), self.kind.name().to_string());
+        parameters.insert("REPOSITORY".to_string(), self.repository.clone());
+        parameters.insert("BRANCH".to_string(), self.branch.clone());
+        parameters.insert("SHA".to_string(), self.sha.clone());
+        if let Some(base_branch) = &self.base_branch {
+            parameters.insert("BASE_BRANCH".to_string(), base_branch.clone());
+        }
+        if let Some(pull_request) = self.pull_request {
+            parameters.insert("PR_NUMBER".to_string(), pull_request.to_string());
+        }
+        parameters
+    }
+}
@@ -6,15 +6,22 @@
 //======================================================================================================================
 
 use crate::{
-    action::Action,
+    action::{Action, ExitStatus, FileTransfer, TransferDirection},
+    artifacts::ArtifactStore,
+    cancellation::Cancellation,
+    dependencies::DependencyTracker,
     job::Job,
+    rendezvous::Rendezvous,
     runner::Runner,
+    secrets::SecretMask,
     task::{Task, TaskQueue},
 };
 use anyhow::Result;
 use std::{
     collections::HashMap,
-    sync::{Arc, Barrier, Mutex},
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Arc, Mutex},
+    time::{Instant, SystemTime},
 };
 
 //======================================================================================================================
@@ -23,11 +30,18 @@ use std::{
 
 pub struct Worker {
     env: HashMap<String, String>,
-    runner: Option<Arc<Mutex<Runner>>>,
+    runner: Option<Arc<Mutex<Box<dyn Runner>>>>,
     scheduled_tasks: Arc<Mutex<TaskQueue>>,
     completed_tasks: Arc<Mutex<TaskQueue>>,
-    barriers: Arc<Vec<Barrier>>,
-    next_barrier: Arc<Mutex<usize>>,
+    barriers: Arc<Vec<Rendezvous>>,
+    /// Tracker of completed actions across all workers of the job.
+    dependencies: Arc<DependencyTracker>,
+    /// Sink to which output lines are sent as soon as they are produced.
+    sink: Option<Sender<String>>,
+    /// Directory where artifacts of the job are collected.
+    artifacts_dir: PathBuf,
+    /// Redacts secrets of the job from its output.
+    mask: SecretMask,
 }
 
 //======================================================================================================================
@@ -36,12 +50,18 @@ pub struct Worker {
 
 impl Worker {
     pub fn new(
-        runner: Arc<Mutex<Runner>>,
+        runner: Arc<Mutex<Box<dyn Runner>>>,
         runner_name: &str,
         job: &mut Job,
-        barriers: Arc<Vec<Barrier>>,
+        barriers: Arc<Vec<Rendezvous>>,
+        dependencies: Arc<DependencyTracker>,
+        sink: Option<Sender<String>>,
+        artifacts_dir: PathBuf,
     ) -> Result<Self> {
-        let env = job.env().clone();
+        // Secrets are passed on to commands, but they are redacted from everything that they print.
+        let mut env = job.env().clone();
+        env.extend(job.secrets().clone());
+        let mask: SecretMask = SecretMask::new(job.secrets().values());
         let tasks: TaskQueue = match job.get_worker_tasks(runner_name) {
             Some(tasks) => tasks,
             None => {
@@ -56,7 +76,10 @@ impl Worker {
             scheduled_tasks: Arc::new(Mutex::new(tasks)),
             completed_tasks: Arc::new(Mutex::new(TaskQueue::default())),
             barriers: barriers.clone(),
-            next_barrier: Arc::new(Mutex::new(0)),
+            dependencies,
+            sink,
+            artifacts_dir,
+            mask,
         })
     }
 
@@ -71,7 +94,21 @@ impl Worker {
         }
     }
 
-    pub fn push_task(&self, task: Action) -> Result<()> {
+    /// Checks if the target [Worker] has actions left that it did not get to run.
+    pub fn has_pending_actions(&self) -> bool {
+        match self.scheduled_tasks.lock() {
+            Ok(scheduled_tasks) => scheduled_tasks
+                .tasks()
+                .iter()
+                .any(|task| matches!(task, Task::Action(_))),
+            Err(e) => {
+                log::warn!("failed to lock queue of scheduled tasks (e={:?})", e);
+                true
+            },
+        }
+    }
+
+    pub fn push_task(&self, task: Box<Action>) -> Result<()> {
         match self.completed_tasks.lock() {
             Ok(mut completed_tasks) => completed_tasks.push_back(Task::Action(task)),
             Err(e) => {
@@ -83,39 +120,90 @@ impl Worker {
         Ok(())
     }
 
-    pub fn wait_others(&self) -> Result<()> {
-        match self.next_barrier.lock() {
-            Ok(mut next_barrier) => {
-                self.barriers[*next_barrier].wait();
-                *next_barrier += 1;
-                Ok(())
-            },
-            Err(e) => {
-                let msg: String = format!("failed to lock next barrier (e={:?})", e);
+    /// Waits for other workers to reach a barrier. Fails if the barrier is cancelled.
+    pub fn wait_others(&self, barrier_index: usize) -> Result<()> {
+        match self.barriers.get(barrier_index) {
+            Some(barrier) => barrier.wait(),
+            None => {
+                let msg: String = format!("invalid barrier (barrier_index={:?})", barrier_index);
                 log::error!("{}", msg);
                 Err(anyhow::anyhow!("{}", msg))
             },
         }
     }
 
-    pub fn run(&self, action: &mut Action) -> Result<()> {
+    /// Cancels all barriers and dependencies, so that other workers do not wait for the target [Worker] forever.
+    pub fn abort(&self) {
+        for barrier in self.barriers.iter() {
+            barrier.cancel();
+        }
+        self.dependencies.cancel();
+    }
+
+    /// Runs an [Action] once all actions that it needs have completed, and records its output and exit status. Fails
+    /// if the action did not complete successfully. The action is killed if the job is cancelled while it runs.
+    pub fn run(&self, action: &mut Action, cancellation: &Cancellation) -> Result<()> {
+        self.dependencies.wait_for(action.needs())?;
+        let result: Result<()> = self.do_run(action, cancellation);
+        self.dependencies.complete(action.name(), result.is_ok());
+        result
+    }
+
+    fn do_run(&self, action: &mut Action, cancellation: &Cancellation) -> Result<()> {
         if let Some(runner) = &self.runner {
             match runner.lock() {
-                Ok(mut runner) => match runner.run(action, &self.env) {
-                    Ok(result) => {
-                        // Pre-append runner name and worker name to each line of the output.
-                        let result: Vec<String> = result
-                            .iter()
-                            .map(|s| format!("[{}][{}]{}", action.runs_on(), action.name(), s))
-                            .collect();
-                        action.set_output(result);
-                        Ok(())
-                    },
-                    Err(e) => {
-                        let msg: String = format!("failed to run task (e={:?})", e);
-                        log::error!("{}", msg);
-                        Err(anyhow::anyhow!("{}", msg))
-                    },
+                Ok(mut runner) => {
+                    let prefix: String = format!("[{}][{}]", action.runs_on(), action.name());
+                    let mut on_line = |line: &str| self.stream_line(format!("{}{}", prefix, line));
+                    let started_at: SystemTime = SystemTime::now();
+                    let start: Instant = Instant::now();
+                    let result: Result<(Vec<String>, ExitStatus)> = match action.transfer() {
+                        Some(transfer) => runner.transfer(transfer, action.timeout(), cancellation, &mut on_line),
+                        None => runner.run(action, &self.env, cancellation, &mut on_line),
+                    };
+                    action.set_timing(started_at, start.elapsed());
+                    match result {
+                        Ok((result, exit_status)) => {
+                            // Pre-append runner name and worker name to each line of the output.
+                            let mut result: Vec<String> = result
+                                .iter()
+                                .map(|s| self.mask.apply(&format!("{}{}", prefix, s)))
+                                .collect();
+                            let exit_line: String = format!(
+                                "{}[exit] code={}, signal={:?}, timed_out={}",
+                                prefix,
+                                exit_status.code(),
+                                exit_status.signal(),
+                                exit_status.timed_out()
+                            );
+                            self.stream_line(exit_line.clone());
+                            result.push(exit_line);
+
+                            // Collect artifacts regardless of the exit status, since they are most useful on failures.
+                            for line in self.collect_artifacts(runner.as_mut(), action, cancellation) {
+                                result.push(self.mask.apply(&format!("{}{}", prefix, line)));
+                            }
+                            action.set_output(result);
+                            action.set_exit_status(exit_status);
+
+                            if !action.succeeded() {
+                                let msg: String = format!(
+                                    "task failed (name={:?}, exit_status={:?})",
+                                    action.name(),
+                                    action.exit_status()
+                                );
+                                log::error!("{}", msg);
+                                anyhow::bail!(msg);
+                            }
+
+                            Ok(())
+                        },
+                        Err(e) => {
+                            let msg: String = format!("failed to run task (e={:?})", e);
+                            log::error!("{}", msg);
+                            Err(anyhow::anyhow!("{}", msg))
+                        },
+                    }
                 },
                 Err(e) => {
                     let msg: String = format!("failed to lock runner (e={:?})", e);
@@ -128,24 +216,93 @@ impl Worker {
         }
     }
 
-    pub fn take_runner(&mut self) -> Option<Arc<Mutex<Runner>>> {
+    /// Fetches the artifacts of an [Action] into the artifacts directory, under `<worker>/<action>/<file name>`.
+    /// Artifacts that cannot be fetched are reported, but they do not cause the action to fail.
+    fn collect_artifacts(&self, runner: &mut dyn Runner, action: &Action, cancellation: &Cancellation) -> Vec<String> {
+        let prefix: String = format!("[{}][{}]", action.runs_on(), action.name());
+        let mut on_line = |line: &str| self.stream_line(format!("{}{}", prefix, line));
+        let action_dir: PathBuf = self.artifacts_dir.join(action.runs_on()).join(action.name());
+        // Names of workers and actions come from the job file, so they must not lead out of the artifact directory.
+        let sanitized: bool =
+            ArtifactStore::is_file_name(action.runs_on()) && ArtifactStore::is_file_name(action.name());
+
+        let mut output: Vec<String> = Vec::new();
+        for artifact in action.artifacts() {
+            if !sanitized {
+                log::warn!(
+                    "malformed artifact directory (runs_on={:?}, action={:?})",
+                    action.runs_on(),
+                    action.name()
+                );
+                let line: String = format!("[artifact] failed to collect {}", artifact);
+                on_line(&line);
+                output.push(line);
+                continue;
+            }
+            let destination: PathBuf = match Path::new(artifact).file_name() {
+                Some(file_name) => action_dir.join(file_name),
+                None => {
+                    log::warn!("artifact path has no file name (path={:?})", artifact);
+                    let line: String = format!("[artifact] failed to collect {}", artifact);
+                    on_line(&line);
+                    output.push(line);
+                    continue;
+                },
+            };
+            let transfer: FileTransfer =
+                FileTransfer::new(TransferDirection::Download, artifact, &destination.to_string_lossy());
+            let collected: bool = match runner.transfer(&transfer, None, cancellation, &mut on_line) {
+                Ok((mut lines, exit_status)) => {
+                    output.append(&mut lines);
+                    exit_status.success()
+                },
+                Err(e) => {
+                    log::warn!("failed to collect artifact (path={:?}, e={:?})", artifact, e);
+                    false
+                },
+            };
+            if !collected {
+                let line: String = format!("[artifact] failed to collect {}", artifact);
+                on_line(&line);
+                output.push(line);
+            }
+        }
+
+        output
+    }
+
+    /// Sends a line of output to the sink of the target [Worker], if any. Secrets are redacted from the line.
+    fn stream_line(&self, line: String) {
+        if self.sink.is_some() {
+            Self::send_line(&self.sink, self.mask.apply(&line));
+        }
+    }
+
+    /// Sends a line of output to a sink, if any.
+    pub fn send_line(sink: &Option<Sender<String>>, line: String) {
+        if let Some(sink) = sink {
+            // The receiving end may have gone away, but this should not affect the job.
+            if let Err(e) = sink.send(line) {
+                log::trace!("failed to stream line (e={:?})", e);
+            }
+        }
+    }
+
+    pub fn take_runner(&mut self) -> Option<Arc<Mutex<Box<dyn Runner>>>> {
         self.runner.take()
     }
 
-    pub fn collect_output(&self) -> Result<Vec<String>> {
+    /// Returns a copy of all actions that the target [Worker] has completed.
+    pub fn collect_actions(&self) -> Result<Vec<Action>> {
         match self.completed_tasks.lock() {
             Ok(completed_tasks) => {
-                let mut output: Vec<String> = Vec::default();
+                let mut actions: Vec<Action> = Vec::default();
                 for task in completed_tasks.tasks() {
                     if let Task::Action(task) = task {
-                        if let Some(lines) = task.output() {
-                            for line in lines {
-                                output.push(line.clone());
-                            }
-                        }
+                        actions.push(task.as_ref().clone());
                     }
                 }
-                Ok(output)
+                Ok(actions)
             },
             Err(e) => {
                 let msg: String = format!("failed to lock queue of completed tasks (e={:?})", e);